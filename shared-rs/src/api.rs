@@ -1,5 +1,10 @@
 use serde::{Deserialize, Serialize};
 use std::error::Error;
+use std::time::Instant;
+use thiserror::Error;
+use tracing::Instrument;
+
+use crate::wire::{self, anthropic, bridge, openai, responses, DecodeError};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct SearchResult {
@@ -10,17 +15,221 @@ pub struct SearchResult {
     pub conversation_id: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub request_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usage: Option<TokenUsage>,
+    /// Model reasoning returned next to the answer (Anthropic extended thinking,
+    /// `reasoning_content` of OpenAI-compatible servers)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub thinking: Option<String>,
+    /// All answers when several were requested (`search_candidates`); `text` is the first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub candidates: Vec<String>,
+}
+
+/// The provider answered, but not with something usable as an answer.
+/// Returned boxed from `search`; callers can downcast to tell the cases apart.
+#[derive(Debug, Error)]
+pub enum ApiError {
+    #[error("{provider} returned no answer text (stop reason: {})", .stop_reason.as_deref().unwrap_or("none"))]
+    EmptyAnswer {
+        provider: String,
+        stop_reason: Option<String>,
+    },
+    #[error("{provider} refused to answer{}", .message.as_deref().map(|m| format!(": {}", m)).unwrap_or_default())]
+    Refused {
+        provider: String,
+        message: Option<String>,
+    },
+    /// The provider accepted the request but reports that generating the answer failed
+    #[error("{provider} failed to answer{}: {message}", .code.as_deref().map(|c| format!(" ({})", c)).unwrap_or_default())]
+    Failed {
+        provider: String,
+        code: Option<String>,
+        message: String,
+    },
+    /// Non-2xx status; `kind` and `message` come from the error body when it could be parsed
+    #[error("{provider} API error ({status}{}): {message}", .kind.as_deref().map(|k| format!(" {}", k)).unwrap_or_default())]
+    Http {
+        provider: String,
+        status: u16,
+        kind: Option<String>,
+        message: String,
+    },
+    /// The server speaks a protocol version or mode this client cannot use
+    #[error("{provider} is incompatible with this client: {message}")]
+    Incompatible {
+        provider: String,
+        message: String,
+    },
+}
+
+impl ApiError {
+    pub(crate) fn http(provider: &str, status: reqwest::StatusCode, kind: Option<String>, message: String) -> Self {
+        Self::Http {
+            provider: provider.to_string(),
+            status: status.as_u16(),
+            kind,
+            message,
+        }
+    }
+}
+
+/// Token counts reported by the provider for one request
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TokenUsage {
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    /// Tokens written to the prompt cache (Anthropic)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache_creation_input_tokens: Option<u64>,
+    /// Tokens served from the prompt cache (Anthropic; OpenAI `cached_tokens`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache_read_input_tokens: Option<u64>,
+    /// Part of `output_tokens` spent on reasoning (OpenAI reasoning models; Anthropic
+    /// does not report thinking tokens separately)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reasoning_tokens: Option<u64>,
+}
+
+impl TokenUsage {
+    /// Counts of several requests added up
+    pub fn total<'a>(usages: impl IntoIterator<Item = &'a TokenUsage>) -> Option<Self> {
+        let add = |a: Option<u64>, b: Option<u64>| match (a, b) {
+            (None, None) => None,
+            (a, b) => Some(a.unwrap_or(0) + b.unwrap_or(0)),
+        };
+        usages.into_iter().fold(None, |total: Option<Self>, usage| {
+            Some(match total {
+                None => usage.clone(),
+                Some(total) => Self {
+                    input_tokens: total.input_tokens + usage.input_tokens,
+                    output_tokens: total.output_tokens + usage.output_tokens,
+                    cache_creation_input_tokens: add(total.cache_creation_input_tokens, usage.cache_creation_input_tokens),
+                    cache_read_input_tokens: add(total.cache_read_input_tokens, usage.cache_read_input_tokens),
+                    reasoning_tokens: add(total.reasoning_tokens, usage.reasoning_tokens),
+                },
+            })
+        })
+    }
+
+    pub(crate) fn from_anthropic(usage: &anthropic::Usage) -> Self {
+        Self {
+            input_tokens: usage.input_tokens,
+            output_tokens: usage.output_tokens,
+            cache_creation_input_tokens: usage.cache_creation_input_tokens,
+            cache_read_input_tokens: usage.cache_read_input_tokens,
+            reasoning_tokens: None,
+        }
+    }
+
+    pub(crate) fn from_openai(usage: &openai::Usage) -> Self {
+        Self {
+            input_tokens: usage.prompt_tokens,
+            output_tokens: usage.completion_tokens,
+            cache_creation_input_tokens: None,
+            cache_read_input_tokens: usage.prompt_tokens_details.as_ref().and_then(|d| d.cached_tokens),
+            reasoning_tokens: usage.completion_tokens_details.as_ref().and_then(|d| d.reasoning_tokens),
+        }
+    }
+
+    pub(crate) fn from_responses(usage: &responses::Usage) -> Self {
+        Self {
+            input_tokens: usage.input_tokens,
+            output_tokens: usage.output_tokens,
+            cache_creation_input_tokens: None,
+            cache_read_input_tokens: usage.input_tokens_details.as_ref().and_then(|d| d.cached_tokens),
+            reasoning_tokens: usage.output_tokens_details.as_ref().and_then(|d| d.reasoning_tokens),
+        }
+    }
+}
+
+/// A piece of text sent ahead of the query (a document, instructions, ...).
+/// With `cache` set, Anthropic caches the prompt prefix up to and including it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PromptBlock {
+    pub text: String,
+    #[serde(default)]
+    pub cache: bool,
 }
 
+impl PromptBlock {
+    pub fn new(text: String) -> Self {
+        Self { text, cache: false }
+    }
+
+    pub fn cached(text: String) -> Self {
+        Self { text, cache: true }
+    }
+}
+
+/// Anthropic accepts at most this many `cache_control` breakpoints per request
+const ANTHROPIC_MAX_CACHE_BREAKPOINTS: usize = 4;
+/// Smallest extended-thinking budget Anthropic accepts
+const ANTHROPIC_MIN_THINKING_BUDGET: u32 = 1024;
+const ANTHROPIC_DEFAULT_MAX_TOKENS: u32 = 1024;
+
 #[async_trait::async_trait]
 #[allow(dead_code)]
 pub trait ApiClient: Send + Sync {
     async fn search(&self, query: &str) -> Result<SearchResult, Box<dyn Error + Send + Sync>>;
+
+    /// `n` answers to the same query, in `SearchResult.candidates`.
+    /// By default the query is sent `n` times in parallel; candidates that fail
+    /// are dropped unless all of them fail. Usage is the total of all requests.
+    async fn search_candidates(&self, query: &str, n: usize) -> Result<SearchResult, Box<dyn Error + Send + Sync>> {
+        let outcomes = futures_util::future::join_all((0..n.max(1)).map(|_| self.search(query))).await;
+        merge_candidates(outcomes)
+    }
 }
 
+/// One result holding every successful candidate; failures are dropped unless
+/// all of them failed
+fn merge_candidates(
+    outcomes: Vec<Result<SearchResult, Box<dyn Error + Send + Sync>>>,
+) -> Result<SearchResult, Box<dyn Error + Send + Sync>> {
+    let mut results = Vec::new();
+    let mut first_error = None;
+    for outcome in outcomes {
+        match outcome {
+            Ok(result) => results.push(result),
+            Err(e) => {
+                first_error.get_or_insert(e);
+            }
+        }
+    }
+    if results.is_empty() {
+        return Err(first_error.unwrap_or_else(|| "No candidates were returned".into()));
+    }
+
+    let usage = TokenUsage::total(results.iter().filter_map(|r| r.usage.as_ref()));
+    let candidates = results.iter().map(|r| r.text.clone()).collect();
+    let mut result = results.swap_remove(0);
+    result.usage = usage;
+    result.candidates = candidates;
+    Ok(result)
+}
+
+const ANTHROPIC_BASE_URL: &str = "https://api.anthropic.com";
+const ANTHROPIC_DEFAULT_MODEL: &str = "claude-3-sonnet-20240229";
+const BRIDGE_DEFAULT_PROVIDER: &str = "anthropic";
+const BRIDGE_DEFAULT_MAX_TOKENS: u32 = 1024;
+/// Bridge protocol version spoken by this client (`X-APP-ID: apiai-v2`)
+pub const BRIDGE_PROTOCOL_VERSION: u32 = 2;
+/// Oldest bridge protocol version this client can talk to
+pub const BRIDGE_MIN_PROTOCOL_VERSION: u32 = 2;
+pub(crate) const OPENAI_BASE_URL: &str = "https://api.openai.com/v1";
+pub(crate) const OPENAI_DEFAULT_MODEL: &str = "gpt-4o";
+
 #[allow(dead_code)]
 pub struct AnthropicClient {
     api_key: String,
+    base_url: String,
+    model: String,
+    max_tokens: u32,
+    thinking_budget: Option<u32>,
+    system_prompt: Option<String>,
+    cache_system_prompt: bool,
+    context: Vec<PromptBlock>,
     _client: reqwest::Client,
 }
 
@@ -29,52 +238,184 @@ impl AnthropicClient {
     pub fn new(api_key: String) -> Self {
         Self {
             api_key,
+            base_url: ANTHROPIC_BASE_URL.to_string(),
+            model: ANTHROPIC_DEFAULT_MODEL.to_string(),
+            max_tokens: ANTHROPIC_DEFAULT_MAX_TOKENS,
+            thinking_budget: None,
+            system_prompt: None,
+            cache_system_prompt: false,
+            context: Vec::new(),
             _client: reqwest::Client::new(),
         }
     }
-}
 
-#[async_trait::async_trait]
-#[async_trait::async_trait]
-impl ApiClient for AnthropicClient {
-    async fn search(&self, query: &str) -> Result<SearchResult, Box<dyn Error + Send + Sync>> {
-        if self.api_key.is_empty() {
-             return Err("Anthropic API key is missing".into());
+    /// Send requests to another server (proxy, gateway, local mock) instead of
+    /// `https://api.anthropic.com`. The `/v1/messages` path is appended.
+    pub fn with_base_url(mut self, base_url: String) -> Self {
+        self.base_url = base_url;
+        self
+    }
+
+    pub fn with_model(mut self, model: String) -> Self {
+        self.model = model;
+        self
+    }
+
+    pub fn with_system_prompt(mut self, system_prompt: String) -> Self {
+        self.system_prompt = Some(system_prompt);
+        self
+    }
+
+    /// Token limit for the answer (default 1024)
+    pub fn with_max_tokens(mut self, max_tokens: u32) -> Self {
+        self.max_tokens = max_tokens;
+        self
+    }
+
+    /// Enable extended thinking with this many tokens to think (at least 1024).
+    /// The budget is added on top of `max_tokens`, so the answer keeps its limit.
+    pub fn with_thinking(mut self, budget_tokens: u32) -> Self {
+        self.thinking_budget = Some(budget_tokens);
+        self
+    }
+
+    pub(crate) fn thinking_enabled(&self) -> bool {
+        self.thinking_budget.is_some()
+    }
+
+    /// Put a cache breakpoint after the system prompt
+    pub fn with_cached_system_prompt(mut self, system_prompt: String) -> Self {
+        self.system_prompt = Some(system_prompt);
+        self.cache_system_prompt = true;
+        self
+    }
+
+    /// Blocks sent in the user message before the query, e.g. long documents
+    pub fn with_context(mut self, context: Vec<PromptBlock>) -> Self {
+        self.context = context;
+        self
+    }
+
+    pub(crate) fn model(&self) -> &str {
+        &self.model
+    }
+
+    /// Messages API body for a single-turn query. Callers may set more fields (tools, ...)
+    pub(crate) fn request_body(&self, query: &str) -> anthropic::MessagesRequest {
+        // Plain string content unless context blocks need their own cache_control
+        let content = if self.context.is_empty() {
+            anthropic::MessageContent::Text(query.to_string())
+        } else {
+            let mut blocks: Vec<anthropic::TextBlock> = self.context.iter()
+                .map(|block| anthropic::TextBlock::new(block.text.clone(), block.cache))
+                .collect();
+            blocks.push(anthropic::TextBlock::new(query.to_string(), false));
+            anthropic::MessageContent::Blocks(blocks)
+        };
+
+        let system = self.system_prompt.as_ref().map(|system| {
+            if self.cache_system_prompt {
+                anthropic::MessageContent::Blocks(vec![anthropic::TextBlock::new(system.clone(), true)])
+            } else {
+                anthropic::MessageContent::Text(system.clone())
+            }
+        });
+
+        anthropic::MessagesRequest {
+            model: self.model.clone(),
+            max_tokens: self.max_tokens + self.thinking_budget.unwrap_or(0),
+            messages: vec![anthropic::Message {
+                role: "user".to_string(),
+                content,
+            }],
+            system,
+            tools: Vec::new(),
+            tool_choice: None,
+            thinking: self.thinking_budget
+                .map(|budget_tokens| anthropic::ThinkingConfig::Enabled { budget_tokens }),
         }
+    }
 
-        let client = reqwest::Client::new();
-        let response = client
-            .post("https://api.anthropic.com/v1/messages")
-            .header("x-api-key", &self.api_key)
-            .header("anthropic-version", "2023-06-01")
-            .header("content-type", "application/json")
-            .json(&serde_json::json!({
-                "model": "claude-3-sonnet-20240229",
-                "max_tokens": 1024,
-                "messages": [
-                    {"role": "user", "content": query}
-                ]
-            }))
+    fn cache_breakpoints(&self) -> usize {
+        usize::from(self.cache_system_prompt && self.system_prompt.is_some())
+            + self.context.iter().filter(|block| block.cache).count()
+    }
+
+    /// Reject option combinations the API would refuse
+    pub(crate) fn check_options(&self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        if self.cache_breakpoints() > ANTHROPIC_MAX_CACHE_BREAKPOINTS {
+            return Err(format!(
+                "Too many cacheable blocks: Anthropic allows at most {} cache breakpoints",
+                ANTHROPIC_MAX_CACHE_BREAKPOINTS
+            ).into());
+        }
+        if let Some(budget) = self.thinking_budget.filter(|&b| b < ANTHROPIC_MIN_THINKING_BUDGET) {
+            return Err(format!(
+                "Thinking budget of {} tokens is too small: Anthropic requires at least {}",
+                budget, ANTHROPIC_MIN_THINKING_BUDGET
+            ).into());
+        }
+        Ok(())
+    }
+
+    /// POST to the Messages API and decode the response
+    pub(crate) async fn send(&self, body: &anthropic::MessagesRequest) -> Result<anthropic::MessagesResponse, Box<dyn Error + Send + Sync>> {
+        self.check_options()?;
+        let started = Instant::now();
+        let response = self.request(reqwest::Method::POST, &self.endpoint("/v1/messages"))?
+            .json(body)
             .send()
             .await?;
 
-        if !response.status().is_success() {
-            let error_text = response.text().await?;
-            return Err(format!("Anthropic API error: {}", error_text).into());
+        let text = Self::response_text(response).await?;
+        tracing::debug!(provider = "anthropic", model = %body.model, response_bytes = text.len(), elapsed_ms = elapsed_ms(started), "answered");
+        Ok(wire::decode("Anthropic", &text)?)
+    }
+
+    pub(crate) fn endpoint(&self, path: &str) -> String {
+        format!("{}{}", self.base_url.trim_end_matches('/'), path)
+    }
+
+    /// Request with the API key and version headers
+    pub(crate) fn request(&self, method: reqwest::Method, url: &str) -> Result<reqwest::RequestBuilder, Box<dyn Error + Send + Sync>> {
+        if self.api_key.is_empty() {
+             return Err("Anthropic API key is missing".into());
+        }
+        Ok(reqwest::Client::new()
+            .request(method, url)
+            .header("x-api-key", &self.api_key)
+            .header("anthropic-version", "2023-06-01"))
+    }
+
+    /// Body of a successful response; other statuses become `ApiError::Http`
+    pub(crate) async fn response_text(response: reqwest::Response) -> Result<String, Box<dyn Error + Send + Sync>> {
+        let status = response.status();
+        let text = response.text().await?;
+        if !status.is_success() {
+            let error = match wire::decode::<anthropic::ErrorResponse>("Anthropic", &text) {
+                Ok(body) => ApiError::http("Anthropic", status, Some(body.error.kind), body.error.message),
+                Err(_) => ApiError::http("Anthropic", status, None, text),
+            };
+            return Err(error.into());
         }
+        Ok(text)
+    }
+}
 
-        let json: serde_json::Value = response.json().await?;
-        let text = json["content"][0]["text"]
-            .as_str()
-            .unwrap_or("No response text found")
-            .to_string();
+#[async_trait::async_trait]
+impl ApiClient for AnthropicClient {
+    async fn search(&self, query: &str) -> Result<SearchResult, Box<dyn Error + Send + Sync>> {
+        let response = self.send(&self.request_body(query)).await?;
 
         Ok(SearchResult {
-            text,
+            text: response.answer_text()?,
             provider: "Anthropic".to_string(),
-            model: Some("claude-3-sonnet-20240229".to_string()),
+            model: Some(self.model.clone()),
             conversation_id: None,
             request_id: None,
+            usage: response.usage.as_ref().map(TokenUsage::from_anthropic),
+            thinking: response.thinking(),
+            candidates: Vec::new(),
         })
     }
 }
@@ -82,6 +423,11 @@ impl ApiClient for AnthropicClient {
 #[allow(dead_code)]
 pub struct OpenAIClient {
     api_key: String,
+    base_url: String,
+    model: String,
+    system_prompt: Option<String>,
+    reasoning_effort: Option<openai::ReasoningEffort>,
+    max_completion_tokens: Option<u32>,
     _client: reqwest::Client,
 }
 
@@ -90,56 +436,151 @@ impl OpenAIClient {
     pub fn new(api_key: String) -> Self {
         Self {
             api_key,
+            base_url: OPENAI_BASE_URL.to_string(),
+            model: OPENAI_DEFAULT_MODEL.to_string(),
+            system_prompt: None,
+            reasoning_effort: None,
+            max_completion_tokens: None,
             _client: reqwest::Client::new(),
         }
     }
-}
 
-#[async_trait::async_trait]
-impl ApiClient for OpenAIClient {
-    async fn search(&self, query: &str) -> Result<SearchResult, Box<dyn Error + Send + Sync>> {
-        if self.api_key.is_empty() {
-             return Err("OpenAI API key is missing".into());
+    /// Send requests to another server (proxy, gateway, local mock) instead of
+    /// `https://api.openai.com/v1`. The `/chat/completions` path is appended.
+    pub fn with_base_url(mut self, base_url: String) -> Self {
+        self.base_url = base_url;
+        self
+    }
+
+    pub fn with_model(mut self, model: String) -> Self {
+        self.model = model;
+        self
+    }
+
+    pub fn with_system_prompt(mut self, system_prompt: String) -> Self {
+        self.system_prompt = Some(system_prompt);
+        self
+    }
+
+    /// Reasoning effort for reasoning models (o-series, gpt-5); other models reject it
+    pub fn with_reasoning_effort(mut self, effort: openai::ReasoningEffort) -> Self {
+        self.reasoning_effort = Some(effort);
+        self
+    }
+
+    /// Limit on generated tokens, reasoning included (`max_completion_tokens`)
+    pub fn with_max_completion_tokens(mut self, max_completion_tokens: u32) -> Self {
+        self.max_completion_tokens = Some(max_completion_tokens);
+        self
+    }
+
+    pub(crate) fn model(&self) -> &str {
+        &self.model
+    }
+
+    /// Chat Completions body for a single-turn query. Callers may set more fields (response_format, ...)
+    pub(crate) fn request_body(&self, query: &str) -> openai::ChatRequest {
+        let mut messages = Vec::new();
+        if let Some(ref system) = self.system_prompt {
+            messages.push(openai::ChatMessage::new("system", system.clone()));
         }
+        messages.push(openai::ChatMessage::new("user", query));
 
-        let client = reqwest::Client::new();
-        let response = client
-            .post("https://api.openai.com/v1/chat/completions")
-            .header("Authorization", format!("Bearer {}", self.api_key))
-            .header("Content-Type", "application/json")
-            .json(&serde_json::json!({
-                "model": "gpt-4o",
-                "messages": [
-                    {"role": "user", "content": query}
-                ]
-            }))
+        openai::ChatRequest {
+            model: self.model.clone(),
+            messages,
+            response_format: None,
+            reasoning_effort: self.reasoning_effort,
+            max_completion_tokens: self.max_completion_tokens,
+            n: None,
+        }
+    }
+
+    /// POST to Chat Completions and decode the response
+    pub(crate) async fn send(&self, body: &openai::ChatRequest) -> Result<openai::ChatResponse, Box<dyn Error + Send + Sync>> {
+        let started = Instant::now();
+        let response = self.request(reqwest::Method::POST, "/chat/completions")?
+            .json(body)
             .send()
             .await?;
 
-        if !response.status().is_success() {
-            let error_text = response.text().await?;
-            return Err(format!("OpenAI API error: {}", error_text).into());
+        let text = Self::response_text(response).await?;
+        tracing::debug!(provider = "openai", model = %body.model, response_bytes = text.len(), elapsed_ms = elapsed_ms(started), "answered");
+        Ok(wire::decode("OpenAI", &text)?)
+    }
+
+    /// Request to `{base_url}{path}` with the API key
+    pub(crate) fn request(&self, method: reqwest::Method, path: &str) -> Result<reqwest::RequestBuilder, Box<dyn Error + Send + Sync>> {
+        if self.api_key.is_empty() {
+             return Err("OpenAI API key is missing".into());
+        }
+        Ok(reqwest::Client::new()
+            .request(method, format!("{}{}", self.base_url.trim_end_matches('/'), path))
+            .header("Authorization", format!("Bearer {}", self.api_key)))
+    }
+
+    /// Body of a successful response; other statuses become `ApiError::Http`
+    pub(crate) async fn response_text(response: reqwest::Response) -> Result<String, Box<dyn Error + Send + Sync>> {
+        let status = response.status();
+        let text = response.text().await?;
+        if !status.is_success() {
+            let error = match wire::decode::<openai::ErrorResponse>("OpenAI", &text) {
+                Ok(body) => ApiError::http("OpenAI", status, body.error.kind, body.error.message),
+                Err(_) => ApiError::http("OpenAI", status, None, text),
+            };
+            return Err(error.into());
         }
+        Ok(text)
+    }
+}
 
-        let json: serde_json::Value = response.json().await?;
-        let text = json["choices"][0]["message"]["content"]
-            .as_str()
-            .unwrap_or("No response text found")
-            .to_string();
+#[async_trait::async_trait]
+impl ApiClient for OpenAIClient {
+    async fn search(&self, query: &str) -> Result<SearchResult, Box<dyn Error + Send + Sync>> {
+        let response = self.send(&self.request_body(query)).await?;
+        let text = response.answer_text()?;
 
         Ok(SearchResult {
             text,
             provider: "OpenAI".to_string(),
-            model: Some("gpt-4o".to_string()),
+            model: Some(self.model.clone()),
+            conversation_id: None,
+            request_id: None,
+            usage: response.usage.as_ref().map(TokenUsage::from_openai),
+            thinking: response.reasoning(),
+            candidates: Vec::new(),
+        })
+    }
+
+    /// One request with `n` choices
+    async fn search_candidates(&self, query: &str, n: usize) -> Result<SearchResult, Box<dyn Error + Send + Sync>> {
+        let mut body = self.request_body(query);
+        body.n = Some(n.max(1) as u32);
+        let response = self.send(&body).await?;
+        let candidates = response.candidate_texts()?;
+
+        Ok(SearchResult {
+            text: candidates[0].clone(),
+            provider: "OpenAI".to_string(),
+            model: Some(self.model.clone()),
             conversation_id: None,
             request_id: None,
+            usage: response.usage.as_ref().map(TokenUsage::from_openai),
+            thinking: response.reasoning(),
+            candidates,
         })
     }
 }
 
-#[allow(dead_code)]
-use crate::encryption::SecureMessenger;
+use std::sync::{Arc, Mutex};
 
+use crate::bridge_transport::{
+    self, AesGcmEnvelope, EncryptionPolicy, EncryptionPolicyError, PlainJson, Transport, TransportSecurity,
+};
+use crate::logging;
+use crate::signing::RequestSigner;
+
+#[derive(Clone)]
 pub struct TelegramClient {
     url: String,
     api_key: String,
@@ -147,6 +588,18 @@ pub struct TelegramClient {
     use_encryption: bool,
     chat_mode: bool,
     conversation_id: Option<String>,
+    request_id: String,
+    /// Request ids of candidate queries in flight, shared by clones so `cancel` reaches them
+    candidate_ids: Arc<Mutex<Vec<String>>>,
+    provider: String,
+    model: Option<String>,
+    max_tokens: u32,
+    temperature: Option<f32>,
+    capabilities: Option<bridge::Capabilities>,
+    signer: Option<RequestSigner>,
+    transport: Option<Arc<dyn Transport>>,
+    encryption_policy: EncryptionPolicy,
+    allow_plaintext_http: bool,
     _client: reqwest::Client,
 }
 
@@ -159,209 +612,519 @@ impl TelegramClient {
             use_encryption,
             chat_mode,
             conversation_id,
+            request_id: uuid::Uuid::new_v4().to_string(),
+            candidate_ids: Arc::default(),
+            provider: BRIDGE_DEFAULT_PROVIDER.to_string(),
+            model: None,
+            max_tokens: BRIDGE_DEFAULT_MAX_TOKENS,
+            temperature: None,
+            capabilities: None,
+            signer: None,
+            transport: None,
+            encryption_policy: EncryptionPolicy::default(),
+            allow_plaintext_http: false,
             _client: reqwest::Client::new(),
         }
     }
 
-    async fn search_encrypted(&self, query: &str) -> Result<SearchResult, Box<dyn Error + Send + Sync>> {
-        let enc_key = self.encryption_key.as_ref().ok_or("Encryption key is missing")?;
-        let messenger = SecureMessenger::new(enc_key).map_err(|e| format!("Failed to init encryption: {}", e))?;
+    /// Upstream provider the bridge should forward to (default "anthropic")
+    pub fn with_provider(mut self, provider: String) -> Self {
+        self.provider = provider;
+        self
+    }
+
+    /// Upstream model; without one the bridge uses its default for the provider
+    pub fn with_model(mut self, model: String) -> Self {
+        self.model = Some(model);
+        self
+    }
 
-        // Prepare payload - server expects "prompt" field
-        let mut payload = serde_json::json!({
-            "prompt": query,
-            "provider": "anthropic",
-            "max_tokens": 1024,
-            "chat_mode": self.chat_mode
-        });
-        
-        // Add conversation_id if exists
-        if let Some(ref conv_id) = self.conversation_id {
-            payload["conversation_id"] = serde_json::json!(conv_id);
-        }
+    pub fn with_max_tokens(mut self, max_tokens: u32) -> Self {
+        self.max_tokens = max_tokens;
+        self
+    }
 
-        // Encrypt payload
-        let encrypted_data = messenger.encrypt_json(&payload)
-            .map_err(|e| format!("Encryption failed: {}", e))?;
+    pub fn with_temperature(mut self, temperature: f32) -> Self {
+        self.temperature = Some(temperature);
+        self
+    }
 
-        // Use the URL as provided - don't modify it
-        // This allows using /echo, /ai_query/secure, or any other endpoint
-        let url = self.url.trim_end_matches('/').to_string();
+    /// Sign every request (queries, cancel, handshake) with HMAC headers; see `signing`
+    pub fn with_signer(mut self, signer: RequestSigner) -> Self {
+        self.signer = Some(signer);
+        self
+    }
 
-        // === NETWORK LOG ===
-        eprintln!("\n🌐 === HTTP REQUEST (ENCRYPTED) ===");
-        eprintln!("📍 URL: {}", url);
-        eprintln!("🔒 Encryption: ENABLED");
-        eprintln!("📤 Payload (plain): {}", serde_json::to_string_pretty(&payload).unwrap_or_default());
-        eprintln!("🔐 Payload (encrypted): {{ \"data\": \"{}...\" }}", &encrypted_data.chars().take(40).collect::<String>());
-        eprintln!("🔑 Headers: X-API-KEY: ****, X-APP-ID: apiai-v2");
-        eprintln!("=====================================\n");
+    /// Use this transport instead of the one picked from the encryption settings
+    pub fn with_transport(mut self, transport: Arc<dyn Transport>) -> Self {
+        self.transport = Some(transport);
+        self
+    }
+
+    /// Decide when payloads must be encrypted (default `Prefer`: follow `use_encryption`)
+    pub fn with_encryption_policy(mut self, policy: EncryptionPolicy) -> Self {
+        self.encryption_policy = policy;
+        self
+    }
+
+    /// Allow unencrypted payloads to a remote server over plain `http://`
+    pub fn allow_plaintext_http(mut self, allow: bool) -> Self {
+        self.allow_plaintext_http = allow;
+        self
+    }
+
+    /// Use a caller-supplied request id instead of the generated one
+    /// (e.g. one the frontend already holds for cancellation)
+    pub fn with_request_id(mut self, request_id: String) -> Self {
+        self.request_id = request_id;
+        self
+    }
+
+    /// Id sent to the bridge with the request; known before the response arrives
+    pub fn request_id(&self) -> &str {
+        &self.request_id
+    }
+
+    /// Ask the bridge to stop working on this client's request.
+    /// The cancel endpoint sits next to `/ai_query` on the same server.
+    pub async fn cancel(&self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let cancel_url = self.server_url("/cancel_request");
+        let candidate_ids = self.candidate_ids.lock().unwrap().clone();
 
         let client = reqwest::Client::new();
-        let mut headers = reqwest::header::HeaderMap::new();
-        if !self.api_key.is_empty() {
-            headers.insert("X-API-KEY", reqwest::header::HeaderValue::from_str(&self.api_key)?);
+        for request_id in std::iter::once(self.request_id.clone()).chain(candidate_ids) {
+            tracing::info!(request_id = %request_id, url = %cancel_url, "cancelling bridge request");
+            let cancel = bridge::CancelRequest { request_id };
+            let response = self.post(&client, &cancel_url, &cancel)?.send().await?;
+
+            if !response.status().is_success() {
+                return Err(Self::server_error(response).await.into());
+            }
         }
-        headers.insert("X-APP-ID", reqwest::header::HeaderValue::from_static("apiai-v2"));
 
-        let response = client
-            .post(&url)
-            .headers(headers)
-            .json(&serde_json::json!({
-                "data": encrypted_data
-            }))
-            .send()
-            .await?;
+        Ok(())
+    }
 
-        if !response.status().is_success() {
-            let status = response.status();
-            let error_text = response.text().await?;
-            return Err(format!("Server error ({}): {}", status, error_text).into());
+    /// URL of another endpoint on the same bridge server as `url`
+    pub(crate) fn server_url(&self, path: &str) -> String {
+        let url = self.url.trim_end_matches('/');
+        let root = ["/ai_query", "/echo"].iter()
+            .find_map(|endpoint| url.find(endpoint).map(|i| &url[..i]))
+            .unwrap_or(url);
+        format!("{}{}", root, path)
+    }
+
+    /// Upstream providers the bridge advertises, or `None` for bridges
+    /// without a `/providers` endpoint
+    pub async fn providers(&self) -> Result<Option<Vec<bridge::UpstreamProvider>>, Box<dyn Error + Send + Sync>> {
+        let response = self.get(&reqwest::Client::new(), &self.server_url("/providers"))?.send().await?;
+
+        match response.status() {
+            reqwest::StatusCode::NOT_FOUND => Ok(None),
+            status if status.is_success() => {
+                let body: bridge::ProvidersResponse = wire::decode("bridge", &response.text().await?)?;
+                Ok(Some(body.providers))
+            }
+            _ => Err(Self::server_error(response).await.into()),
         }
+    }
 
-        let response_json: serde_json::Value = response.json().await?;
-        let encrypted_response = response_json["data"].as_str()
-            .ok_or("Invalid response format: missing 'data'")?;
+    /// Capabilities handshake. Bridges without `/capabilities` are assumed to speak
+    /// the current protocol; their providers come from `/providers` if available.
+    pub async fn capabilities(&self) -> Result<bridge::Capabilities, Box<dyn Error + Send + Sync>> {
+        let response = self.get(&reqwest::Client::new(), &self.server_url("/capabilities"))?.send().await?;
+
+        match response.status() {
+            reqwest::StatusCode::NOT_FOUND => Ok(bridge::Capabilities {
+                protocol_version: BRIDGE_PROTOCOL_VERSION,
+                min_protocol_version: None,
+                server_version: None,
+                providers: self.providers().await?.unwrap_or_default(),
+                encryption: bridge::EncryptionRequirement::Optional,
+                streaming: false,
+                jobs: false,
+                advertised: false,
+            }),
+            status if status.is_success() => {
+                let mut capabilities: bridge::Capabilities = wire::decode("bridge", &response.text().await?)?;
+                capabilities.advertised = true;
+                Ok(capabilities)
+            }
+            _ => Err(Self::server_error(response).await.into()),
+        }
+    }
 
-        // Decrypt response
-        let decrypted_data: serde_json::Value = messenger.decrypt_json(encrypted_response)
-            .map_err(|e| format!("Decryption failed: {}", e))?;
+    /// Adapt to what the bridge reported, or refuse when it cannot work:
+    /// incompatible protocol versions, encryption required without a key or
+    /// requested but unsupported, and upstream settings the bridge does not offer.
+    /// A bridge that requires encryption gets it when a key is configured; one that
+    /// cannot encrypt under the `Require` policy is an `EncryptionPolicyError`.
+    pub fn with_capabilities(mut self, capabilities: bridge::Capabilities) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let incompatible = |message: String| ApiError::Incompatible {
+            provider: "Bridge".to_string(),
+            message,
+        };
+        if capabilities.protocol_version < BRIDGE_MIN_PROTOCOL_VERSION {
+            return Err(incompatible(format!(
+                "bridge speaks protocol v{}, this client needs at least v{}; update the bridge",
+                capabilities.protocol_version, BRIDGE_MIN_PROTOCOL_VERSION
+            )).into());
+        }
+        if let Some(min) = capabilities.min_protocol_version.filter(|&min| min > BRIDGE_PROTOCOL_VERSION) {
+            return Err(incompatible(format!(
+                "bridge requires protocol v{} or newer, this client speaks v{}; update the app",
+                min, BRIDGE_PROTOCOL_VERSION
+            )).into());
+        }
 
-        let result_text = if let Some(resp) = decrypted_data.get("response") {
-            resp.as_str().unwrap_or("").to_string()
-        } else {
-            decrypted_data.to_string()
+        let has_key = self.encryption_key().is_some();
+        let policy = self.encryption_policy;
+        match capabilities.encryption {
+            bridge::EncryptionRequirement::Required if !has_key => {
+                return Err(incompatible("bridge requires encryption but no encryption key is configured".to_string()).into());
+            }
+            bridge::EncryptionRequirement::Required if policy == EncryptionPolicy::Off => {
+                return Err(incompatible("bridge requires encryption but the encryption policy is off".to_string()).into());
+            }
+            bridge::EncryptionRequirement::Required => self.use_encryption = true,
+            bridge::EncryptionRequirement::Unsupported if policy == EncryptionPolicy::Require => {
+                return Err(EncryptionPolicyError::BridgeUnencrypted.into());
+            }
+            bridge::EncryptionRequirement::Unsupported if self.use_encryption && policy == EncryptionPolicy::Prefer => {
+                return Err(incompatible("encryption is enabled but the bridge does not support it".to_string()).into());
+            }
+            _ => {}
+        }
+
+        if !capabilities.providers.is_empty() {
+            self.check_upstream(&capabilities.providers)?;
+        }
+        self.capabilities = Some(capabilities);
+        Ok(self)
+    }
+
+    /// Capabilities applied with `with_capabilities`
+    pub fn negotiated_capabilities(&self) -> Option<&bridge::Capabilities> {
+        self.capabilities.as_ref()
+    }
+
+    /// Check the upstream provider, model and `max_tokens` against what the bridge advertises
+    pub fn check_upstream(&self, providers: &[bridge::UpstreamProvider]) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let Some(upstream) = providers.iter().find(|p| p.name == self.provider) else {
+            let names: Vec<&str> = providers.iter().map(|p| p.name.as_str()).collect();
+            return Err(format!(
+                "Bridge does not offer provider '{}' (available: {})",
+                self.provider,
+                names.join(", ")
+            ).into());
+        };
+        if let Some(model) = self.model.as_ref().filter(|m| !upstream.models.is_empty() && !upstream.models.contains(m)) {
+            return Err(format!(
+                "Bridge does not offer model '{}' for {} (available: {})",
+                model,
+                upstream.name,
+                upstream.models.join(", ")
+            ).into());
+        }
+        if let Some(limit) = upstream.max_tokens.filter(|&limit| self.max_tokens > limit) {
+            return Err(format!(
+                "max_tokens {} exceeds the bridge limit of {} for {}",
+                self.max_tokens, limit, upstream.name
+            ).into());
+        }
+        Ok(())
+    }
+
+    /// `ApiError::Http` from a non-2xx bridge response, using `detail` when present
+    pub(crate) async fn server_error(response: reqwest::Response) -> ApiError {
+        let status = response.status();
+        let text = match response.text().await {
+            Ok(text) => text,
+            Err(e) => return ApiError::http("Bridge", status, None, e.to_string()),
         };
-        
-        let conversation_id = decrypted_data.get("conversation_id")
-            .and_then(|v| v.as_str())
-            .map(|s| s.to_string());
-        
-        // Extract provider and model from server response
-        let provider = decrypted_data.get("provider")
-            .and_then(|v| v.as_str())
-            .unwrap_or("anthropic")
-            .to_string();
-        let model = decrypted_data.get("model")
-            .and_then(|v| v.as_str())
-            .map(|s| s.to_string());
-            
-        let request_id = decrypted_data.get("request_id")
-            .and_then(|v| v.as_str())
-            .map(|s| s.to_string());
+        match wire::decode::<bridge::ErrorResponse>("bridge", &text) {
+            Ok(body) => ApiError::http("Bridge", status, None, body.message()),
+            Err(_) => ApiError::http("Bridge", status, None, text),
+        }
+    }
+
+    pub(crate) fn query_request(&self, query: &str) -> bridge::QueryRequest {
+        // Server expects "prompt" field
+        bridge::QueryRequest {
+            prompt: query.to_string(),
+            provider: self.provider.clone(),
+            model: self.model.clone(),
+            max_tokens: self.max_tokens,
+            temperature: self.temperature,
+            stream: false,
+            chat_mode: self.chat_mode,
+            request_id: self.request_id.clone(),
+            conversation_id: self.conversation_id.clone(),
+        }
+    }
 
+    pub(crate) fn search_result(&self, response: bridge::QueryResponse) -> Result<SearchResult, DecodeError> {
         Ok(SearchResult {
-            text: result_text,
-            provider,
-            model,
-            conversation_id,
-            request_id,
+            text: response.text()?,
+            provider: response.provider.unwrap_or_else(|| self.provider.clone()),
+            model: response.model.or_else(|| self.model.clone()),
+            conversation_id: response.conversation_id,
+            request_id: response.request_id.or_else(|| Some(self.request_id.clone())),
+            usage: None,
+            thinking: None,
+            candidates: Vec::new(),
         })
     }
-}
 
-#[async_trait::async_trait]
-impl ApiClient for TelegramClient {
-    async fn search(&self, query: &str) -> Result<SearchResult, Box<dyn Error + Send + Sync>> {
-        // Check encryption settings
-        if self.use_encryption {
-            if self.encryption_key.is_none() || self.encryption_key.as_ref().unwrap().is_empty() {
-                eprintln!("WARNING: Encryption is enabled but encryption key is missing or empty!");
-                eprintln!("Request will be sent UNENCRYPTED!");
-                // Continue with unencrypted request
-            } else {
-                // Use encrypted channel
-                return self.search_encrypted(query).await;
+    /// Configured encryption key, if any, whether or not encryption is on
+    pub(crate) fn encryption_key(&self) -> Option<&str> {
+        self.encryption_key.as_deref().filter(|key| !key.is_empty())
+    }
+
+    pub(crate) fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Transport for the next call, checked against the encryption policy: the
+    /// one set with `with_transport`, else the AES-GCM envelope when the policy
+    /// asks for encryption, else plain JSON. Refusals are `EncryptionPolicyError`s.
+    pub(crate) fn transport(&self) -> Result<Arc<dyn Transport>, Box<dyn Error + Send + Sync>> {
+        let policy = self.encryption_policy;
+        let encrypt = match policy {
+            EncryptionPolicy::Require => true,
+            EncryptionPolicy::Prefer => self.use_encryption,
+            EncryptionPolicy::Off => false,
+        };
+        let transport: Arc<dyn Transport> = match (&self.transport, self.encryption_key()) {
+            (Some(transport), _) => transport.clone(),
+            (None, Some(key)) if encrypt => Arc::new(AesGcmEnvelope::new(key)?),
+            (None, None) if policy == EncryptionPolicy::Require => return Err(EncryptionPolicyError::KeyMissing.into()),
+            (None, None) if encrypt => {
+                tracing::warn!(url = %self.url, "encryption is on but no encryption key is configured; sending unencrypted");
+                Arc::new(PlainJson)
+            }
+            (None, _) => Arc::new(PlainJson),
+        };
+
+        if !transport.is_encrypted() {
+            if policy == EncryptionPolicy::Require {
+                return Err(EncryptionPolicyError::Unencrypted(transport.name()).into());
+            }
+            let url = reqwest::Url::parse(self.url.trim())?;
+            if !self.allow_plaintext_http && !bridge_transport::is_private_channel(&url) {
+                let host = url.host_str().unwrap_or_default().to_string();
+                return Err(EncryptionPolicyError::PlaintextHttp(host).into());
             }
         }
+        Ok(transport)
+    }
 
-        if self.url.is_empty() {
-             return Err("Server URL is missing".into());
+    /// What the next call would be sent with, or why it would be refused; nothing is sent
+    pub fn transport_security(&self) -> TransportSecurity {
+        let https = reqwest::Url::parse(self.url.trim()).is_ok_and(|url| url.scheme() == "https");
+        let (transport, refused) = match self.transport() {
+            Ok(transport) => (Some(transport), None),
+            Err(e) => (None, Some(e.to_string())),
+        };
+        TransportSecurity {
+            policy: self.encryption_policy,
+            transport: transport.as_ref().map(|transport| transport.name()),
+            encrypted: transport.is_some_and(|transport| transport.is_encrypted()),
+            https,
+            refused,
         }
+    }
 
-        let client = reqwest::Client::new();
-        
-        // Construct headers
+    fn headers(&self) -> Result<reqwest::header::HeaderMap, Box<dyn Error + Send + Sync>> {
         let mut headers = reqwest::header::HeaderMap::new();
         if !self.api_key.is_empty() {
             headers.insert("X-API-KEY", reqwest::header::HeaderValue::from_str(&self.api_key)?);
         }
         headers.insert("X-APP-ID", reqwest::header::HeaderValue::from_static("apiai-v2"));
+        headers.insert("X-Protocol-Version", reqwest::header::HeaderValue::from(BRIDGE_PROTOCOL_VERSION));
+        headers.insert("X-Request-ID", reqwest::header::HeaderValue::from_str(&self.request_id)?);
+        Ok(headers)
+    }
 
-        // Prepare payload
-        let mut payload = serde_json::json!({
-            "prompt": query,
-            "provider": "anthropic",
-            "max_tokens": 1024,
-            "chat_mode": self.chat_mode
-        });
-        
-        // Add conversation_id if exists
-        if let Some(ref conv_id) = self.conversation_id {
-            payload["conversation_id"] = serde_json::json!(conv_id);
-        }
-
-        // === NETWORK LOG ===
-        eprintln!("\n🌐 === HTTP REQUEST (UNENCRYPTED) ===");
-        eprintln!("📍 URL: {}", &self.url);
-        eprintln!("⚠️  Encryption: DISABLED - DATA SENT IN PLAIN TEXT!");
-        eprintln!("📤 Payload: {}", serde_json::to_string_pretty(&payload).unwrap_or_default());
-        eprintln!("🔑 Headers: X-API-KEY: ****, X-APP-ID: apiai-v2");
-        eprintln!("=====================================\n");
-
-        let response = client
-            .post(&self.url)
-            .headers(headers)
-            .json(&payload)
-            .send()
-            .await?;
+    /// Bodiless request to the bridge with the common headers, signed when a signer is set
+    pub(crate) fn request(
+        &self,
+        client: &reqwest::Client,
+        method: reqwest::Method,
+        url: &str,
+    ) -> Result<reqwest::RequestBuilder, Box<dyn Error + Send + Sync>> {
+        self.signed(client.request(method.clone(), url), method.as_str(), url, Vec::new())
+    }
+
+    /// JSON request to the bridge. The body is serialized here so the signature
+    /// covers exactly the bytes that are sent.
+    pub(crate) fn request_json<T: serde::Serialize>(
+        &self,
+        client: &reqwest::Client,
+        method: reqwest::Method,
+        url: &str,
+        body: &T,
+    ) -> Result<reqwest::RequestBuilder, Box<dyn Error + Send + Sync>> {
+        let body = serde_json::to_vec(body)?;
+        let request = client.request(method.clone(), url).header(reqwest::header::CONTENT_TYPE, "application/json");
+        self.signed(request, method.as_str(), url, body)
+    }
+
+    pub(crate) fn get(&self, client: &reqwest::Client, url: &str) -> Result<reqwest::RequestBuilder, Box<dyn Error + Send + Sync>> {
+        self.request(client, reqwest::Method::GET, url)
+    }
+
+    pub(crate) fn post<T: serde::Serialize>(
+        &self,
+        client: &reqwest::Client,
+        url: &str,
+        body: &T,
+    ) -> Result<reqwest::RequestBuilder, Box<dyn Error + Send + Sync>> {
+        self.request_json(client, reqwest::Method::POST, url, body)
+    }
 
+    /// POST of a JSON payload sealed by the transport; the signature covers the sealed body
+    pub(crate) fn post_sealed<T: serde::Serialize>(
+        &self,
+        client: &reqwest::Client,
+        transport: &dyn Transport,
+        url: &str,
+        payload: &T,
+    ) -> Result<reqwest::RequestBuilder, Box<dyn Error + Send + Sync>> {
+        let body = transport.seal(serde_json::to_vec(payload)?)?;
+        let request = client.post(url).header(reqwest::header::CONTENT_TYPE, "application/json");
+        self.signed(request, "POST", url, body)
+    }
+
+    /// Payload of a bridge answer opened by the transport, or `None` when the
+    /// body is empty. Errors are read as plain JSON whatever the transport.
+    pub(crate) async fn open_response(
+        transport: &dyn Transport,
+        response: reqwest::Response,
+    ) -> Result<Option<String>, Box<dyn Error + Send + Sync>> {
         if !response.status().is_success() {
-            let status = response.status();
-            let error_text = response.text().await?;
-            return Err(format!("Server error ({}): {}", status, error_text).into());
+            return Err(Self::server_error(response).await.into());
         }
-
         let text = response.text().await?;
-        
-        // Try to parse as JSON first
-        let (result_text, conversation_id, provider, model, request_id) = if let Ok(json) = serde_json::from_str::<serde_json::Value>(&text) {
-            let text_content = if let Some(resp) = json.get("response") {
-                resp.as_str().unwrap_or(&text).to_string()
-            } else if let Some(content) = json.get("content") {
-                 content.as_str().unwrap_or(&text).to_string()
-            } else {
-                text.clone()
+        if text.trim().is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(transport.open(&text)?))
+    }
+
+    /// Answer to a query: JSON with `response` or `content` must match the protocol;
+    /// anything else (plain text, other endpoints such as `/echo`) is the answer as is
+    pub(crate) fn query_answer(&self, payload: String) -> Result<SearchResult, Box<dyn Error + Send + Sync>> {
+        match serde_json::from_str::<serde_json::Value>(&payload) {
+            Ok(json) if json.get("response").is_some() || json.get("content").is_some() => {
+                Ok(self.search_result(wire::decode_value("bridge", json)?)?)
+            }
+            _ => Ok(SearchResult {
+                text: payload,
+                provider: "Telegram".to_string(),
+                model: None,
+                conversation_id: None,
+                request_id: Some(self.request_id.clone()),
+                usage: None,
+                thinking: None,
+                candidates: Vec::new(),
+            }),
+        }
+    }
+
+    fn signed(
+        &self,
+        request: reqwest::RequestBuilder,
+        method: &str,
+        url: &str,
+        body: Vec<u8>,
+    ) -> Result<reqwest::RequestBuilder, Box<dyn Error + Send + Sync>> {
+        tracing::debug!(method, url, request_bytes = body.len(), signed = self.signer.is_some(), "bridge request");
+        let mut request = request.headers(self.headers()?);
+        if let Some(signer) = &self.signer {
+            let url = reqwest::Url::parse(url)?;
+            let path = match url.query() {
+                Some(query) => format!("{}?{}", url.path(), query),
+                None => url.path().to_string(),
             };
-            
-            let conv_id = json.get("conversation_id")
-                .and_then(|v| v.as_str())
-                .map(|s| s.to_string());
-            
-            // Extract provider and model from response if available
-            let provider_str = json.get("provider")
-                .and_then(|v| v.as_str())
-                .unwrap_or("anthropic")
-                .to_string();
-            let model_str = json.get("model")
-                .and_then(|v| v.as_str())
-                .map(|s| s.to_string());
-                
-            let req_id = json.get("request_id")
-                .and_then(|v| v.as_str())
-                .map(|s| s.to_string());
-            
-            (text_content, conv_id, provider_str, model_str, req_id)
-        } else {
-            (text, None, "Telegram".to_string(), None, None)
-        };
+            for (name, value) in signer.sign(method, &path, &body) {
+                request = request.header(name, value);
+            }
+        }
+        Ok(if body.is_empty() { request } else { request.body(body) })
+    }
+}
 
-        Ok(SearchResult {
-            text: result_text,
-            provider,
-            model,
-            conversation_id,
-            request_id,
-        })
+#[async_trait::async_trait]
+impl ApiClient for TelegramClient {
+    async fn search(&self, query: &str) -> Result<SearchResult, Box<dyn Error + Send + Sync>> {
+        if self.url.is_empty() {
+             return Err("Server URL is missing".into());
+        }
+
+        let transport = self.transport()?;
+        let payload = self.query_request(query);
+
+        // Use the URL as provided - don't modify it
+        // This allows using /echo, /ai_query/secure, or any other endpoint
+        let url = self.url.trim_end_matches('/');
+
+        let span = tracing::info_span!(
+            "bridge_query",
+            request_id = %self.request_id,
+            provider = %self.provider,
+            transport = transport.name(),
+        );
+        async {
+            tracing::debug!(url, prompt = %logging::body(&payload.prompt), "sending query");
+            let started = Instant::now();
+            let client = reqwest::Client::new();
+            let response = self.post_sealed(&client, transport.as_ref(), url, &payload)?.send().await?;
+            let status = response.status().as_u16();
+            let answer = Self::open_response(transport.as_ref(), response)
+                .await
+                .inspect_err(|e| tracing::warn!(status, elapsed_ms = elapsed_ms(started), error = %e, "bridge query failed"))?
+                .ok_or_else(|| ApiError::EmptyAnswer {
+                    provider: "Bridge".to_string(),
+                    stop_reason: None,
+                })?;
+            tracing::info!(
+                status,
+                response_bytes = answer.len(),
+                elapsed_ms = elapsed_ms(started),
+                answer = %logging::body(&answer),
+                "bridge answered"
+            );
+            self.query_answer(answer)
+        }
+        .instrument(span)
+        .await
     }
+
+    /// Every candidate is its own bridge request with its own request id
+    /// (`<request id>-<n>`), so each can be told apart in logs and cancelled.
+    /// Refused in chat mode, where each would add a turn to the conversation.
+    async fn search_candidates(&self, query: &str, n: usize) -> Result<SearchResult, Box<dyn Error + Send + Sync>> {
+        if n <= 1 {
+            return self.search(query).await;
+        }
+        if self.chat_mode {
+            return Err("Several candidates cannot be requested in chat mode".into());
+        }
+
+        let clients: Vec<TelegramClient> = (1..=n)
+            .map(|i| self.clone().with_request_id(format!("{}-{}", self.request_id, i)))
+            .collect();
+        *self.candidate_ids.lock().unwrap() = clients.iter().map(|c| c.request_id.clone()).collect();
+        let outcomes = futures_util::future::join_all(clients.iter().map(|client| client.search(query))).await;
+        self.candidate_ids.lock().unwrap().clear();
+
+        let mut result = merge_candidates(outcomes)?;
+        result.request_id = Some(self.request_id.clone());
+        Ok(result)
+    }
+}
+
+pub(crate) fn elapsed_ms(started: Instant) -> u64 {
+    started.elapsed().as_millis() as u64
 }
@@ -0,0 +1,392 @@
+// Batch jobs
+// Bulk processing through the providers' batch APIs (Anthropic Message
+// Batches, OpenAI Batch): about half the price of single requests, results
+// within 24 hours. A job is created from prompts with caller-chosen ids, polled
+// until it has ended, and its results are mapped back to those ids.
+//
+//     let job = client.create_batch(&requests).await?;
+//     let job = client.wait_for_batch(&job.id, Duration::from_secs(60), &mut |_| {}).await?;
+//     let results = match_results(&requests, client.batch_results(&job).await?);
+
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::time::Duration;
+
+use crate::api::{AnthropicClient, OpenAIClient, SearchResult, TokenUsage};
+use crate::wire::{self, anthropic, openai};
+
+/// Longest custom id Anthropic accepts
+const MAX_CUSTOM_ID_LEN: usize = 64;
+
+/// One prompt of a batch
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchRequest {
+    /// Unique within the batch; letters, digits, `_` and `-` only
+    pub custom_id: String,
+    pub query: String,
+}
+
+impl BatchRequest {
+    pub fn new(custom_id: impl Into<String>, query: impl Into<String>) -> Self {
+        Self {
+            custom_id: custom_id.into(),
+            query: query.into(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BatchStatus {
+    InProgress,
+    Cancelling,
+    Completed,
+    Cancelled,
+    Failed,
+    Expired,
+}
+
+impl BatchStatus {
+    /// No further progress will be made; results (if any) can be fetched
+    pub fn is_finished(self) -> bool {
+        !matches!(self, BatchStatus::InProgress | BatchStatus::Cancelling)
+    }
+}
+
+/// Provider-neutral view of a batch job
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchJob {
+    pub id: String,
+    pub provider: String,
+    pub status: BatchStatus,
+    pub total: u64,
+    pub succeeded: u64,
+    pub failed: u64,
+    /// Where the results are read from: Anthropic's `results_url`, or
+    /// OpenAI's output and error file ids
+    #[serde(default)]
+    pub result_files: Vec<String>,
+}
+
+/// Outcome of one request of a batch; exactly one of `result` and `error` is set
+#[derive(Debug)]
+pub struct BatchItemResult {
+    pub custom_id: String,
+    pub result: Option<SearchResult>,
+    pub error: Option<String>,
+}
+
+impl BatchItemResult {
+    fn success(custom_id: String, result: SearchResult) -> Self {
+        Self { custom_id, result: Some(result), error: None }
+    }
+
+    fn failure(custom_id: String, error: impl Into<String>) -> Self {
+        Self { custom_id, result: None, error: Some(error.into()) }
+    }
+}
+
+/// Providers with an asynchronous batch API
+#[async_trait::async_trait]
+pub trait BatchClient: Send + Sync {
+    async fn create_batch(&self, requests: &[BatchRequest]) -> Result<BatchJob, Box<dyn Error + Send + Sync>>;
+
+    async fn batch_status(&self, batch_id: &str) -> Result<BatchJob, Box<dyn Error + Send + Sync>>;
+
+    async fn cancel_batch(&self, batch_id: &str) -> Result<BatchJob, Box<dyn Error + Send + Sync>>;
+
+    /// Results of a finished job, in the order the provider returns them
+    async fn batch_results(&self, job: &BatchJob) -> Result<Vec<BatchItemResult>, Box<dyn Error + Send + Sync>>;
+
+    /// Poll until the job is finished, reporting every status seen
+    async fn wait_for_batch(
+        &self,
+        batch_id: &str,
+        interval: Duration,
+        on_progress: &mut (dyn for<'j> FnMut(&'j BatchJob) + Send),
+    ) -> Result<BatchJob, Box<dyn Error + Send + Sync>> {
+        loop {
+            let job = self.batch_status(batch_id).await?;
+            on_progress(&job);
+            if job.status.is_finished() {
+                return Ok(job);
+            }
+            tokio::time::sleep(interval).await;
+        }
+    }
+}
+
+/// Results in the order of `requests`; requests without a result are reported as errors
+pub fn match_results(requests: &[BatchRequest], results: Vec<BatchItemResult>) -> Vec<BatchItemResult> {
+    let mut by_id: HashMap<String, BatchItemResult> = results.into_iter()
+        .map(|item| (item.custom_id.clone(), item))
+        .collect();
+    requests.iter()
+        .map(|request| by_id.remove(&request.custom_id)
+            .unwrap_or_else(|| BatchItemResult::failure(request.custom_id.clone(), "No result returned for this request")))
+        .collect()
+}
+
+/// Ids must be unique and valid for both providers so input files stay portable
+fn check_requests(requests: &[BatchRequest]) -> Result<(), Box<dyn Error + Send + Sync>> {
+    if requests.is_empty() {
+        return Err("A batch needs at least one request".into());
+    }
+    let mut seen = HashSet::new();
+    for request in requests {
+        let id = &request.custom_id;
+        if id.is_empty() || id.len() > MAX_CUSTOM_ID_LEN
+            || !id.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        {
+            return Err(format!(
+                "Invalid custom id '{}': use 1-{} letters, digits, '_' or '-'",
+                id, MAX_CUSTOM_ID_LEN
+            ).into());
+        }
+        if !seen.insert(id.as_str()) {
+            return Err(format!("Duplicate custom id '{}'", id).into());
+        }
+    }
+    Ok(())
+}
+
+/// Parse a JSONL document, reporting the failing line number
+fn parse_jsonl<T: serde::de::DeserializeOwned>(origin: &str, text: &str) -> Result<Vec<T>, Box<dyn Error + Send + Sync>> {
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| wire::decode(origin, line)
+            .map_err(|e| format!("Line {} of the batch results: {}", index + 1, e).into()))
+        .collect()
+}
+
+// --- Anthropic Message Batches -------------------------------------------------
+
+impl AnthropicClient {
+    fn batch_job(batch: anthropic::MessageBatch) -> BatchJob {
+        let counts = &batch.request_counts;
+        let status = match batch.processing_status.as_str() {
+            "canceling" => BatchStatus::Cancelling,
+            "ended" => BatchStatus::Completed,
+            _ => BatchStatus::InProgress,
+        };
+        BatchJob {
+            id: batch.id,
+            provider: "Anthropic".to_string(),
+            status,
+            total: counts.processing + counts.succeeded + counts.errored + counts.canceled + counts.expired,
+            succeeded: counts.succeeded,
+            failed: counts.errored + counts.canceled + counts.expired,
+            result_files: batch.results_url.into_iter().collect(),
+        }
+    }
+
+    async fn batch_request(&self, method: reqwest::Method, path: &str) -> Result<BatchJob, Box<dyn Error + Send + Sync>> {
+        let response = self.request(method, &self.endpoint(path))?.send().await?;
+        let batch: anthropic::MessageBatch = wire::decode("Anthropic", &Self::response_text(response).await?)?;
+        Ok(Self::batch_job(batch))
+    }
+
+    fn batch_item(&self, line: anthropic::BatchResultLine) -> BatchItemResult {
+        let custom_id = line.custom_id;
+        match line.result {
+            anthropic::BatchResult::Succeeded { message } => match message.answer_text() {
+                Ok(text) => BatchItemResult::success(custom_id, SearchResult {
+                    text,
+                    provider: "Anthropic".to_string(),
+                    model: message.model.clone().or_else(|| Some(self.model().to_string())),
+                    conversation_id: None,
+                    request_id: message.id.clone(),
+                    usage: message.usage.as_ref().map(TokenUsage::from_anthropic),
+                    thinking: message.thinking(),
+                    candidates: Vec::new(),
+                }),
+                Err(e) => BatchItemResult::failure(custom_id, e.to_string()),
+            },
+            anthropic::BatchResult::Errored { error } => {
+                BatchItemResult::failure(custom_id, format!("{}: {}", error.error.kind, error.error.message))
+            }
+            anthropic::BatchResult::Canceled => BatchItemResult::failure(custom_id, "Request was cancelled"),
+            anthropic::BatchResult::Expired => BatchItemResult::failure(custom_id, "Request expired before it was processed"),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl BatchClient for AnthropicClient {
+    async fn create_batch(&self, requests: &[BatchRequest]) -> Result<BatchJob, Box<dyn Error + Send + Sync>> {
+        check_requests(requests)?;
+        self.check_options()?;
+        let body = anthropic::BatchCreateRequest {
+            requests: requests.iter()
+                .map(|request| anthropic::BatchRequestItem {
+                    custom_id: request.custom_id.clone(),
+                    params: self.request_body(&request.query),
+                })
+                .collect(),
+        };
+
+        let response = self.request(reqwest::Method::POST, &self.endpoint("/v1/messages/batches"))?
+            .json(&body)
+            .send()
+            .await?;
+        let batch: anthropic::MessageBatch = wire::decode("Anthropic", &Self::response_text(response).await?)?;
+        Ok(Self::batch_job(batch))
+    }
+
+    async fn batch_status(&self, batch_id: &str) -> Result<BatchJob, Box<dyn Error + Send + Sync>> {
+        self.batch_request(reqwest::Method::GET, &format!("/v1/messages/batches/{}", batch_id)).await
+    }
+
+    async fn cancel_batch(&self, batch_id: &str) -> Result<BatchJob, Box<dyn Error + Send + Sync>> {
+        self.batch_request(reqwest::Method::POST, &format!("/v1/messages/batches/{}/cancel", batch_id)).await
+    }
+
+    async fn batch_results(&self, job: &BatchJob) -> Result<Vec<BatchItemResult>, Box<dyn Error + Send + Sync>> {
+        let url = job.result_files.first()
+            .ok_or_else(|| format!("Batch {} has no results yet", job.id))?;
+        let response = self.request(reqwest::Method::GET, url)?.send().await?;
+        let lines: Vec<anthropic::BatchResultLine> = parse_jsonl("Anthropic", &Self::response_text(response).await?)?;
+        Ok(lines.into_iter().map(|line| self.batch_item(line)).collect())
+    }
+}
+
+// --- OpenAI Batch ----------------------------------------------------------------
+
+const OPENAI_BATCH_ENDPOINT: &str = "/v1/chat/completions";
+
+impl OpenAIClient {
+    fn batch_job(batch: openai::Batch) -> BatchJob {
+        let status = match batch.status.as_str() {
+            "completed" => BatchStatus::Completed,
+            "failed" => BatchStatus::Failed,
+            "expired" => BatchStatus::Expired,
+            "cancelling" => BatchStatus::Cancelling,
+            "cancelled" => BatchStatus::Cancelled,
+            // validating, in_progress, finalizing
+            _ => BatchStatus::InProgress,
+        };
+        let counts = batch.request_counts.as_ref();
+        BatchJob {
+            id: batch.id,
+            provider: "OpenAI".to_string(),
+            status,
+            total: counts.map_or(0, |c| c.total),
+            succeeded: counts.map_or(0, |c| c.completed),
+            failed: counts.map_or(0, |c| c.failed),
+            result_files: batch.output_file_id.into_iter().chain(batch.error_file_id).collect(),
+        }
+    }
+
+    async fn batch_request(&self, method: reqwest::Method, path: &str) -> Result<BatchJob, Box<dyn Error + Send + Sync>> {
+        let response = self.request(method, path)?.send().await?;
+        let batch: openai::Batch = wire::decode("OpenAI", &Self::response_text(response).await?)?;
+        Ok(Self::batch_job(batch))
+    }
+
+    fn batch_item(&self, line: openai::BatchOutputLine) -> BatchItemResult {
+        let custom_id = line.custom_id;
+        if let Some(error) = line.error {
+            let message = match error.code {
+                Some(code) => format!("{}: {}", code, error.message),
+                None => error.message,
+            };
+            return BatchItemResult::failure(custom_id, message);
+        }
+        let Some(response) = line.response else {
+            return BatchItemResult::failure(custom_id, "Result line has neither a response nor an error");
+        };
+        if response.status_code != 200 {
+            let message = wire::decode_value::<openai::ErrorResponse>("OpenAI", response.body.clone())
+                .map(|body| body.error.message)
+                .unwrap_or_else(|_| response.body.to_string());
+            return BatchItemResult::failure(custom_id, format!("HTTP {}: {}", response.status_code, message));
+        }
+
+        let chat = match wire::decode_value::<openai::ChatResponse>("OpenAI", response.body) {
+            Ok(chat) => chat,
+            Err(e) => return BatchItemResult::failure(custom_id, e.to_string()),
+        };
+        match chat.answer_text() {
+            Ok(text) => BatchItemResult::success(custom_id, SearchResult {
+                text,
+                provider: "OpenAI".to_string(),
+                model: chat.model.clone().or_else(|| Some(self.model().to_string())),
+                conversation_id: None,
+                request_id: chat.id.clone(),
+                usage: chat.usage.as_ref().map(TokenUsage::from_openai),
+                thinking: chat.reasoning(),
+                candidates: Vec::new(),
+            }),
+            Err(e) => BatchItemResult::failure(custom_id, e.to_string()),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl BatchClient for OpenAIClient {
+    /// Uploads the requests as a JSONL file, then creates the batch from it
+    async fn create_batch(&self, requests: &[BatchRequest]) -> Result<BatchJob, Box<dyn Error + Send + Sync>> {
+        check_requests(requests)?;
+        let mut jsonl = String::new();
+        for request in requests {
+            let line = openai::BatchInputLine {
+                custom_id: request.custom_id.clone(),
+                method: "POST",
+                url: OPENAI_BATCH_ENDPOINT,
+                body: self.request_body(&request.query),
+            };
+            jsonl.push_str(&serde_json::to_string(&line)?);
+            jsonl.push('\n');
+        }
+
+        let file = reqwest::multipart::Part::bytes(jsonl.into_bytes())
+            .file_name("batch.jsonl")
+            .mime_str("application/jsonl")?;
+        let form = reqwest::multipart::Form::new()
+            .text("purpose", "batch")
+            .part("file", file);
+        let response = self.request(reqwest::Method::POST, "/files")?
+            .multipart(form)
+            .send()
+            .await?;
+        let file: openai::FileObject = wire::decode("OpenAI", &Self::response_text(response).await?)?;
+
+        let body = openai::BatchCreateRequest {
+            input_file_id: file.id,
+            endpoint: OPENAI_BATCH_ENDPOINT,
+            completion_window: "24h",
+        };
+        let response = self.request(reqwest::Method::POST, "/batches")?
+            .json(&body)
+            .send()
+            .await?;
+        let batch: openai::Batch = wire::decode("OpenAI", &Self::response_text(response).await?)?;
+        Ok(Self::batch_job(batch))
+    }
+
+    async fn batch_status(&self, batch_id: &str) -> Result<BatchJob, Box<dyn Error + Send + Sync>> {
+        self.batch_request(reqwest::Method::GET, &format!("/batches/{}", batch_id)).await
+    }
+
+    async fn cancel_batch(&self, batch_id: &str) -> Result<BatchJob, Box<dyn Error + Send + Sync>> {
+        self.batch_request(reqwest::Method::POST, &format!("/batches/{}/cancel", batch_id)).await
+    }
+
+    /// Reads the output file and the error file, when present
+    async fn batch_results(&self, job: &BatchJob) -> Result<Vec<BatchItemResult>, Box<dyn Error + Send + Sync>> {
+        if job.result_files.is_empty() {
+            return Err(format!("Batch {} has no results yet", job.id).into());
+        }
+        let mut results = Vec::new();
+        for file_id in &job.result_files {
+            let response = self.request(reqwest::Method::GET, &format!("/files/{}/content", file_id))?
+                .send()
+                .await?;
+            let lines: Vec<openai::BatchOutputLine> = parse_jsonl("OpenAI", &Self::response_text(response).await?)?;
+            results.extend(lines.into_iter().map(|line| self.batch_item(line)));
+        }
+        Ok(results)
+    }
+}
@@ -0,0 +1,115 @@
+// Server-side conversations of the Telegram bridge
+// In chat mode the bridge keeps the history under the `conversation_id` it
+// hands back. These calls list, inspect, rename, reset (clear the messages but
+// keep the id) and delete them. On an encrypted transport every call goes
+// through `POST /conversations/secure` as a sealed `ConversationAction`, so
+// ids, titles and messages never cross the wire in plain text.
+
+use std::error::Error;
+
+use reqwest::Method;
+
+use crate::api::TelegramClient;
+use crate::wire::{self, bridge};
+
+impl TelegramClient {
+    pub async fn list_conversations(&self) -> Result<Vec<bridge::ConversationSummary>, Box<dyn Error + Send + Sync>> {
+        let body = self.conversation_call(bridge::ConversationAction::List).await?;
+        let body: bridge::ConversationsResponse = wire::decode_value("bridge", body.unwrap_or_default())?;
+        Ok(body.conversations)
+    }
+
+    pub async fn conversation_messages(
+        &self,
+        conversation_id: &str,
+    ) -> Result<Vec<bridge::ConversationMessage>, Box<dyn Error + Send + Sync>> {
+        let action = bridge::ConversationAction::Messages {
+            conversation_id: conversation_id.to_string(),
+        };
+        let body = self.conversation_call(action).await?;
+        let body: bridge::ConversationMessagesResponse = wire::decode_value("bridge", body.unwrap_or_default())?;
+        Ok(body.messages)
+    }
+
+    pub async fn rename_conversation(&self, conversation_id: &str, title: &str) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.conversation_call(bridge::ConversationAction::Rename {
+            conversation_id: conversation_id.to_string(),
+            title: title.to_string(),
+        })
+        .await?;
+        Ok(())
+    }
+
+    /// Clear the history but keep the conversation (and its id)
+    pub async fn reset_conversation(&self, conversation_id: &str) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.conversation_call(bridge::ConversationAction::Reset {
+            conversation_id: conversation_id.to_string(),
+        })
+        .await?;
+        Ok(())
+    }
+
+    pub async fn delete_conversation(&self, conversation_id: &str) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.conversation_call(bridge::ConversationAction::Delete {
+            conversation_id: conversation_id.to_string(),
+        })
+        .await?;
+        Ok(())
+    }
+
+    /// Run one action on the plain REST endpoints or the encrypted one.
+    /// Returns the (opened) JSON body, or `None` when the bridge sent none.
+    async fn conversation_call(
+        &self,
+        action: bridge::ConversationAction,
+    ) -> Result<Option<serde_json::Value>, Box<dyn Error + Send + Sync>> {
+        if self.url().is_empty() {
+            return Err("Server URL is missing".into());
+        }
+        let client = reqwest::Client::new();
+        let transport = self.transport()?;
+
+        // Encrypted transports take every action on one endpoint, so nothing but
+        // the envelope shows on the wire; plain JSON uses the REST endpoints
+        let request = match &action {
+            _ if transport.is_encrypted() => {
+                let url = self.server_url(&transport.endpoint("/conversations"));
+                self.post_sealed(&client, transport.as_ref(), &url, &action)?
+            }
+            bridge::ConversationAction::List => self.get(&client, &self.server_url("/conversations"))?,
+            bridge::ConversationAction::Messages { conversation_id } => {
+                self.get(&client, &self.conversation_url(conversation_id, Some("messages"))?)?
+            }
+            bridge::ConversationAction::Rename { conversation_id, title } => self.request_json(
+                &client,
+                Method::PATCH,
+                &self.conversation_url(conversation_id, None)?,
+                &bridge::RenameConversationRequest { title: title.clone() },
+            )?,
+            bridge::ConversationAction::Reset { conversation_id } => {
+                self.request(&client, Method::POST, &self.conversation_url(conversation_id, Some("reset"))?)?
+            }
+            bridge::ConversationAction::Delete { conversation_id } => {
+                self.request(&client, Method::DELETE, &self.conversation_url(conversation_id, None)?)?
+            }
+        };
+
+        match Self::open_response(transport.as_ref(), request.send().await?).await? {
+            Some(body) => Ok(Some(wire::decode("bridge", &body)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// `/conversations/{id}[/{action}]`, with the id escaped as a path segment
+    fn conversation_url(&self, conversation_id: &str, action: Option<&str>) -> Result<String, Box<dyn Error + Send + Sync>> {
+        if conversation_id.is_empty() {
+            return Err("Conversation id is missing".into());
+        }
+        let mut url = reqwest::Url::parse(&self.server_url("/conversations"))?;
+        url.path_segments_mut()
+            .map_err(|_| "Server URL cannot take a path")?
+            .push(conversation_id)
+            .extend(action);
+        Ok(url.into())
+    }
+}
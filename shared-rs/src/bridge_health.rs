@@ -0,0 +1,266 @@
+// Health check of the Telegram bridge
+// Walks the same path a query takes, one step at a time, so a failure can be
+// named: the host resolves, `/health` answers and accepts the API key, and,
+// when an encryption key is configured, `/health/secure` decrypts a probe and
+// echoes its nonce back under the same key. Each step is timed.
+
+use std::error::Error;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use reqwest::StatusCode;
+use serde::Serialize;
+
+use crate::api::{elapsed_ms, TelegramClient};
+use crate::bridge_transport::{AesGcmEnvelope, Transport};
+use crate::wire::{self, bridge};
+
+/// Upper bound for each probe request
+const HEALTH_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Why the bridge cannot be used
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HealthFailure {
+    /// `telegram_url` is not a valid http(s) URL
+    InvalidUrl,
+    /// Host name does not resolve
+    Dns,
+    /// Host resolves but nothing accepts the connection
+    Connection,
+    /// TLS handshake failed (certificate, or plain HTTP on an https URL)
+    Tls,
+    Timeout,
+    /// API key rejected
+    Auth,
+    /// Bridge could not decrypt the probe, or answered under another key
+    WrongKey,
+    /// Bridge answered with an error of its own
+    Server,
+}
+
+impl fmt::Display for HealthFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            HealthFailure::InvalidUrl => "invalid URL",
+            HealthFailure::Dns => "host not found",
+            HealthFailure::Connection => "connection failed",
+            HealthFailure::Tls => "TLS handshake failed",
+            HealthFailure::Timeout => "timed out",
+            HealthFailure::Auth => "API key rejected",
+            HealthFailure::WrongKey => "encryption key rejected",
+            HealthFailure::Server => "server error",
+        })
+    }
+}
+
+/// Outcome of `TelegramClient::check_health`; steps after a failure are left empty
+#[derive(Debug, Clone, Serialize)]
+pub struct HealthReport {
+    pub url: String,
+    pub ok: bool,
+    pub failure: Option<HealthFailure>,
+    /// Details of the failure, or notes about steps that could not be checked
+    pub message: Option<String>,
+    pub dns_ms: Option<u64>,
+    /// Round trip of the plain `/health` request
+    pub latency_ms: Option<u64>,
+    /// Round trip of the encrypted `/health/secure` probe
+    pub encrypted_latency_ms: Option<u64>,
+    pub server_version: Option<String>,
+    /// None when the bridge has no `/health` endpoint to check it against
+    pub api_key_accepted: Option<bool>,
+    /// None when no encryption key is configured or the bridge cannot check it
+    pub encryption_key_accepted: Option<bool>,
+}
+
+impl HealthReport {
+    fn fail(mut self, failure: HealthFailure, message: impl Into<String>) -> Self {
+        self.ok = false;
+        self.failure = Some(failure);
+        self.message = Some(message.into());
+        self
+    }
+}
+
+impl TelegramClient {
+    /// Probe the bridge without sending a query. Never fails itself: problems
+    /// are reported in the returned `HealthReport`.
+    pub async fn check_health(&self) -> HealthReport {
+        let mut report = HealthReport {
+            url: self.url().to_string(),
+            ok: true,
+            failure: None,
+            message: None,
+            dns_ms: None,
+            latency_ms: None,
+            encrypted_latency_ms: None,
+            server_version: None,
+            api_key_accepted: None,
+            encryption_key_accepted: None,
+        };
+
+        let url = match reqwest::Url::parse(self.url()) {
+            Ok(url) if matches!(url.scheme(), "http" | "https") => url,
+            Ok(url) => return report.fail(HealthFailure::InvalidUrl, format!("unsupported scheme '{}'", url.scheme())),
+            Err(e) => return report.fail(HealthFailure::InvalidUrl, e.to_string()),
+        };
+        let (Some(host), Some(port)) = (url.host_str(), url.port_or_known_default()) else {
+            return report.fail(HealthFailure::InvalidUrl, "URL has no host");
+        };
+
+        let started = Instant::now();
+        match tokio::net::lookup_host((host.trim_start_matches('[').trim_end_matches(']'), port))
+            .await
+            .map(|mut addresses| addresses.next())
+        {
+            Ok(Some(_)) => report.dns_ms = Some(elapsed_ms(started)),
+            Ok(None) => return report.fail(HealthFailure::Dns, format!("{} has no addresses", host)),
+            Err(e) => return report.fail(HealthFailure::Dns, format!("{}: {}", host, e)),
+        }
+
+        let client = match reqwest::Client::builder().timeout(HEALTH_TIMEOUT).build() {
+            Ok(client) => client,
+            Err(e) => return report.fail(HealthFailure::Tls, e.to_string()),
+        };
+        let request = match self.get(&client, &self.server_url("/health")) {
+            Ok(request) => request,
+            Err(e) => return report.fail(HealthFailure::Auth, e.to_string()),
+        };
+
+        // Plain round trip: reachability, TLS, API key (and signature), server version
+        let started = Instant::now();
+        let response = match request.send().await {
+            Ok(response) => response,
+            Err(e) => return report.fail(classify(&e), error_chain(&e)),
+        };
+        let status = response.status();
+        let text = response.text().await.unwrap_or_default();
+        report.latency_ms = Some(elapsed_ms(started));
+        match status {
+            StatusCode::NOT_FOUND => {
+                report.message = Some("bridge has no /health endpoint; API key not verified".to_string());
+            }
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => {
+                report.api_key_accepted = Some(false);
+                return report.fail(HealthFailure::Auth, error_detail(status, &text));
+            }
+            status if !status.is_success() => return report.fail(HealthFailure::Server, error_detail(status, &text)),
+            _ => {
+                report.api_key_accepted = Some(true);
+                match wire::decode::<bridge::HealthResponse>("bridge", &text) {
+                    Ok(health) if health.status == "ok" => report.server_version = health.version,
+                    Ok(health) => {
+                        report.server_version = health.version;
+                        return report.fail(HealthFailure::Server, format!("bridge reports status '{}'", health.status));
+                    }
+                    Err(e) => return report.fail(HealthFailure::Server, e.to_string()),
+                }
+            }
+        }
+
+        let Some(key) = self.encryption_key() else {
+            return report;
+        };
+        // The key is probed even when encryption is off, so it is known to work before it is switched on
+        let transport = match AesGcmEnvelope::new(key) {
+            Ok(transport) => transport,
+            Err(e) => return report.fail(HealthFailure::WrongKey, format!("invalid encryption key: {}", e)),
+        };
+
+        // Encrypted round trip: the bridge has to decrypt the nonce and send it back under the same key
+        let probe = bridge::HealthProbe { nonce: uuid::Uuid::new_v4().to_string() };
+        let url = self.server_url(&transport.endpoint("/health"));
+        let request = match self.post_sealed(&client, &transport, &url, &probe) {
+            Ok(request) => request,
+            Err(e) => return report.fail(HealthFailure::WrongKey, e.to_string()),
+        };
+        let started = Instant::now();
+        let response = match request.send().await {
+            Ok(response) => response,
+            Err(e) => return report.fail(classify(&e), error_chain(&e)),
+        };
+        let status = response.status();
+        let text = response.text().await.unwrap_or_default();
+        report.encrypted_latency_ms = Some(elapsed_ms(started));
+        match status {
+            StatusCode::NOT_FOUND => {
+                let note = "bridge has no /health/secure endpoint; encryption key not verified";
+                report.message = Some(match report.message.take() {
+                    Some(message) => format!("{}; {}", message, note),
+                    None => note.to_string(),
+                });
+                return report;
+            }
+            // Bridges answer 400 when the envelope does not decrypt
+            StatusCode::BAD_REQUEST => {
+                report.encryption_key_accepted = Some(false);
+                return report.fail(HealthFailure::WrongKey, error_detail(status, &text));
+            }
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => {
+                report.api_key_accepted = Some(false);
+                return report.fail(HealthFailure::Auth, error_detail(status, &text));
+            }
+            status if !status.is_success() => return report.fail(HealthFailure::Server, error_detail(status, &text)),
+            _ => {}
+        }
+
+        let echoed = transport
+            .open(&text)
+            .and_then(|payload| Ok(wire::decode::<bridge::HealthProbe>("bridge", &payload)?))
+            .map_err(|e| e.to_string());
+        match echoed {
+            Ok(echo) if echo.nonce == probe.nonce => {
+                report.encryption_key_accepted = Some(true);
+                report
+            }
+            Ok(_) => {
+                report.encryption_key_accepted = Some(false);
+                report.fail(HealthFailure::WrongKey, "bridge echoed a different nonce")
+            }
+            Err(e) => {
+                report.encryption_key_accepted = Some(false);
+                report.fail(HealthFailure::WrongKey, e)
+            }
+        }
+    }
+}
+
+fn error_detail(status: StatusCode, text: &str) -> String {
+    match wire::decode::<bridge::ErrorResponse>("bridge", text) {
+        Ok(body) => format!("HTTP {}: {}", status.as_u16(), body.message()),
+        Err(_) if text.is_empty() => format!("HTTP {}", status.as_u16()),
+        Err(_) => format!("HTTP {}: {}", status.as_u16(), text),
+    }
+}
+
+/// reqwest keeps the cause (resolver, TLS backend, io) in the source chain
+fn error_chain(error: &reqwest::Error) -> String {
+    let mut message = error.to_string();
+    let mut source = error.source();
+    while let Some(cause) = source {
+        let cause_message = cause.to_string();
+        if !message.contains(&cause_message) {
+            message.push_str(": ");
+            message.push_str(&cause_message);
+        }
+        source = cause.source();
+    }
+    message
+}
+
+fn classify(error: &reqwest::Error) -> HealthFailure {
+    if error.is_timeout() {
+        return HealthFailure::Timeout;
+    }
+    let chain = error_chain(error).to_lowercase();
+    if ["certificate", "tls", "ssl", "handshake", "corrupt message"].iter().any(|hint| chain.contains(hint)) {
+        HealthFailure::Tls
+    } else if chain.contains("dns error") || chain.contains("failed to lookup") {
+        HealthFailure::Dns
+    } else if error.is_connect() {
+        HealthFailure::Connection
+    } else {
+        HealthFailure::Server
+    }
+}
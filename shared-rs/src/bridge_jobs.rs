@@ -0,0 +1,145 @@
+// Job mode for long bridge queries
+// Instead of holding one connection open for the whole answer, the query is
+// submitted to `/jobs` and the bridge answers at once with a job id (the
+// request id, unless the bridge picks its own). The status is then polled,
+// optionally long-polled with `wait`, until the job is finished; the result
+// comes with the final status. A poll that fails on the network is simply
+// repeated, so a dropped connection costs one poll instead of the answer.
+// With encryption the query and every status travel in envelopes through
+// `/jobs/secure` and `/jobs/status/secure`. `/jobs/cancel` stops a job by
+// its id.
+
+use std::error::Error;
+use std::time::{Duration, Instant};
+
+use crate::api::{ApiError, SearchResult, TelegramClient};
+use crate::bridge_transport::Transport;
+use crate::wire::{self, bridge};
+
+/// Consecutive failed polls `wait_for_job` tolerates before giving up
+const MAX_POLL_FAILURES: u32 = 5;
+/// Pause between polls when the bridge answers at once (no long polling)
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Outcome of one poll
+#[derive(Debug)]
+pub enum JobProgress {
+    Pending(bridge::JobState),
+    Done(Box<SearchResult>),
+}
+
+impl TelegramClient {
+    /// Queue the query on the bridge; returns the job id to poll
+    pub async fn submit_job(&self, query: &str) -> Result<String, Box<dyn Error + Send + Sync>> {
+        if self.url().is_empty() {
+            return Err("Server URL is missing".into());
+        }
+        let client = reqwest::Client::new();
+        let payload = self.query_request(query);
+        let transport = self.transport()?;
+        let url = self.server_url(&transport.endpoint("/jobs"));
+        let request = self.post_sealed(&client, transport.as_ref(), &url, &payload)?;
+        let status = job_response(request.send().await?, transport.as_ref()).await?;
+        tracing::info!(request_id = %self.request_id(), job_id = %status.job_id, transport = transport.name(), "bridge job submitted");
+        Ok(status.job_id)
+    }
+
+    /// Current status of a job. With `wait` the bridge may hold the request
+    /// open until the job finishes or the time is up.
+    pub async fn job_status(&self, job_id: &str, wait: Option<Duration>) -> Result<bridge::JobStatus, Box<dyn Error + Send + Sync>> {
+        if job_id.is_empty() {
+            return Err("Job id is missing".into());
+        }
+        let client = reqwest::Client::new();
+        let wait = wait.map(|wait| wait.as_secs());
+        let transport = self.transport()?;
+        let request = if transport.is_encrypted() {
+            let poll = bridge::JobPoll { job_id: job_id.to_string(), wait };
+            let url = self.server_url(&transport.endpoint("/jobs/status"));
+            self.post_sealed(&client, transport.as_ref(), &url, &poll)?
+        } else {
+            let mut url = reqwest::Url::parse(&self.server_url("/jobs"))?;
+            url.path_segments_mut().map_err(|_| "Server URL cannot take a path")?.push(job_id);
+            if let Some(wait) = wait {
+                url.query_pairs_mut().append_pair("wait", &wait.to_string());
+            }
+            self.get(&client, url.as_str())?
+        };
+        job_response(request.send().await?, transport.as_ref()).await
+    }
+
+    /// Poll once: the answer when the job is done, its state while it is not.
+    /// Failed and cancelled jobs are errors.
+    pub async fn poll_job(&self, job_id: &str, wait: Option<Duration>) -> Result<JobProgress, Box<dyn Error + Send + Sync>> {
+        let status = self.job_status(job_id, wait).await?;
+        let failed = |message: String| ApiError::Failed {
+            provider: "Bridge".to_string(),
+            code: None,
+            message,
+        };
+        match status.status {
+            bridge::JobState::Done => {
+                let result = status.result.ok_or("Bridge reported the job done without a result")?;
+                Ok(JobProgress::Done(Box::new(self.search_result(result)?)))
+            }
+            bridge::JobState::Failed => Err(failed(status.error.unwrap_or_else(|| "job failed".to_string())).into()),
+            bridge::JobState::Cancelled => Err(failed("job was cancelled".to_string()).into()),
+            state => Ok(JobProgress::Pending(state)),
+        }
+    }
+
+    /// Stop a queued or running job. Unlike `cancel` this goes by the job
+    /// id, which the bridge may have picked instead of the request id.
+    pub async fn cancel_job(&self, job_id: &str) -> Result<(), Box<dyn Error + Send + Sync>> {
+        if job_id.is_empty() {
+            return Err("Job id is missing".into());
+        }
+        let client = reqwest::Client::new();
+        let url = self.server_url("/jobs/cancel");
+        tracing::info!(job_id, url = %url, "cancelling bridge job");
+        let cancel = bridge::JobCancel { job_id: job_id.to_string() };
+        let response = self.post(&client, &url, &cancel)?.send().await?;
+        if !response.status().is_success() {
+            return Err(Self::server_error(response).await.into());
+        }
+        Ok(())
+    }
+
+    /// Long-poll until the job is finished. Network errors and gateway
+    /// timeouts are retried up to `MAX_POLL_FAILURES` times in a row.
+    pub async fn wait_for_job(&self, job_id: &str, wait: Duration) -> Result<SearchResult, Box<dyn Error + Send + Sync>> {
+        let mut failures = 0;
+        loop {
+            let started = Instant::now();
+            match self.poll_job(job_id, Some(wait)).await {
+                Ok(JobProgress::Done(result)) => return Ok(*result),
+                Ok(JobProgress::Pending(_)) => failures = 0,
+                Err(e) if is_transient(e.as_ref()) && failures < MAX_POLL_FAILURES => {
+                    failures += 1;
+                    tracing::warn!(job_id, failures, error = %e, "bridge job poll failed; retrying");
+                }
+                Err(e) => return Err(e),
+            }
+            if let Some(pause) = POLL_INTERVAL.checked_sub(started.elapsed()) {
+                tokio::time::sleep(pause * (failures + 1)).await;
+            }
+        }
+    }
+}
+
+/// Job status from a submit or poll response, opened by the transport
+async fn job_response(
+    response: reqwest::Response,
+    transport: &dyn Transport,
+) -> Result<bridge::JobStatus, Box<dyn Error + Send + Sync>> {
+    let status = TelegramClient::open_response(transport, response).await?;
+    Ok(wire::decode("bridge", &status.unwrap_or_default())?)
+}
+
+/// Worth polling again: the connection dropped or a proxy gave up waiting
+fn is_transient(error: &(dyn Error + Send + Sync + 'static)) -> bool {
+    if let Some(error) = error.downcast_ref::<reqwest::Error>() {
+        return error.is_connect() || error.is_timeout() || error.is_request() || error.is_body();
+    }
+    matches!(error.downcast_ref::<ApiError>(), Some(ApiError::Http { status: 502..=504, .. }))
+}
@@ -0,0 +1,135 @@
+// Streaming over the Telegram bridge
+// With `stream: true` the bridge answers with server-sent events: text deltas,
+// then the finished answer. Every event is opened by the transport on its own,
+// so on the encrypted channel each one is its own AES-GCM envelope. Bridges
+// that do not advertise streaming in their capabilities get a normal request,
+// reported as a single `Completed` event.
+
+use std::collections::VecDeque;
+use std::error::Error;
+use std::sync::Arc;
+
+use crate::api::{ApiClient, ApiError, SearchResult, TelegramClient};
+use crate::bridge_transport::Transport;
+use crate::logging;
+use crate::responses::ResponseEvent;
+use crate::sse::{SseEvent, SseParser};
+use crate::wire::{self, bridge};
+
+impl TelegramClient {
+    /// Stream the answer as the bridge produces it. Falls back to a single
+    /// request when the negotiated capabilities (`with_capabilities`) do not
+    /// include streaming.
+    pub async fn search_stream(&self, query: &str) -> Result<BridgeStream, Box<dyn Error + Send + Sync>> {
+        let streaming = self.negotiated_capabilities().is_some_and(|c| c.streaming);
+        if !streaming {
+            let result = self.search(query).await?;
+            return Ok(BridgeStream {
+                client: self.clone(),
+                source: Source::Finished(Some(Box::new(result))),
+            });
+        }
+
+        if self.url().is_empty() {
+            return Err("Server URL is missing".into());
+        }
+        let mut payload = self.query_request(query);
+        payload.stream = true;
+        let transport = self.transport()?;
+        tracing::debug!(
+            request_id = %self.request_id(),
+            provider = %payload.provider,
+            transport = transport.name(),
+            prompt = %logging::body(&payload.prompt),
+            "streaming query"
+        );
+
+        let client = reqwest::Client::new();
+        let url = self.url().trim_end_matches('/');
+        let request = self.post_sealed(&client, transport.as_ref(), url, &payload)?;
+        let response = request.header("Accept", "text/event-stream").send().await?;
+        if !response.status().is_success() {
+            return Err(Self::server_error(response).await.into());
+        }
+
+        Ok(BridgeStream {
+            client: self.clone(),
+            source: Source::Events(Box::new(Events {
+                response,
+                parser: SseParser::new(),
+                pending: VecDeque::new(),
+                transport,
+            })),
+        })
+    }
+}
+
+/// Events of a streamed bridge answer, read with `next_event`
+pub struct BridgeStream {
+    client: TelegramClient,
+    source: Source,
+}
+
+enum Source {
+    Events(Box<Events>),
+    /// Answer of a non-streaming request, handed out once
+    Finished(Option<Box<SearchResult>>),
+}
+
+struct Events {
+    response: reqwest::Response,
+    parser: SseParser,
+    pending: VecDeque<SseEvent>,
+    transport: Arc<dyn Transport>,
+}
+
+impl BridgeStream {
+    /// Next event (`TextDelta`s, then `Completed`), or `None` after `Completed`
+    pub async fn next_event(&mut self) -> Result<Option<ResponseEvent>, Box<dyn Error + Send + Sync>> {
+        let (response, parser, pending, transport) = match &mut self.source {
+            Source::Finished(result) => return Ok(result.take().map(ResponseEvent::Completed)),
+            Source::Events(events) => {
+                let Events { response, parser, pending, transport } = events.as_mut();
+                (response, parser, pending, transport)
+            }
+        };
+
+        loop {
+            let Some(sse) = pending.pop_front() else {
+                match response.chunk().await? {
+                    Some(chunk) => pending.extend(parser.push(&chunk)),
+                    None => {
+                        pending.extend(parser.finish());
+                        if pending.is_empty() {
+                            self.source = Source::Finished(None);
+                            return Err("Bridge stream ended before the answer was completed".into());
+                        }
+                    }
+                }
+                continue;
+            };
+
+            let chunk: bridge::StreamChunk = wire::decode("bridge", &transport.open(&sse.data)?)?;
+
+            match chunk {
+                bridge::StreamChunk::Delta { text } => return Ok(Some(ResponseEvent::TextDelta(text))),
+                bridge::StreamChunk::Done(answer) => {
+                    let result = self.client.search_result(answer)?;
+                    tracing::info!(
+                        request_id = %self.client.request_id(),
+                        response_bytes = result.text.len(),
+                        answer = %logging::body(&result.text),
+                        "bridge stream completed"
+                    );
+                    self.source = Source::Finished(None);
+                    return Ok(Some(ResponseEvent::Completed(Box::new(result))));
+                }
+                bridge::StreamChunk::Error { detail } => {
+                    self.source = Source::Finished(None);
+                    let message = bridge::ErrorResponse { detail }.message();
+                    return Err(ApiError::Failed { provider: "Bridge".to_string(), code: None, message }.into());
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,163 @@
+// Transports of the Telegram bridge
+// Every bridge payload is JSON; a transport decides how it travels. `PlainJson`
+// sends it as is. `AesGcmEnvelope` seals it as `{"data": base64(nonce ||
+// ciphertext)}`, expects answers and every streamed event sealed the same way,
+// and talks to the `/secure` variant of an endpoint. `TelegramClient` picks the
+// transport once per call and seals requests and opens answers through it, so
+// each bridge feature is written once and its answers are parsed the same way
+// with and without encryption. Another scheme is one more `Transport`,
+// plugged in with `TelegramClient::with_transport`.
+//
+// Which transport may be used is decided by the `EncryptionPolicy`, and the
+// decision fails closed: under `Require` nothing is sent unless it is
+// encrypted, and plain text never goes to a remote `http://` server unless
+// that is explicitly allowed. Refusals are `EncryptionPolicyError`s.
+
+use std::error::Error;
+
+use base64::{engine::general_purpose, Engine as _};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::encryption::SecureMessenger;
+use crate::wire::{self, bridge};
+
+/// How bridge payloads are put on the wire and taken off it
+pub trait Transport: Send + Sync {
+    /// Short name for logs, e.g. `plain` or `aes-gcm`
+    fn name(&self) -> &'static str;
+
+    /// Whether payloads are unreadable on the wire
+    fn is_encrypted(&self) -> bool;
+
+    /// Path of an endpoint on this transport (`/jobs` → `/jobs/secure`)
+    fn endpoint(&self, path: &str) -> String;
+
+    /// Request body for a serialized JSON payload
+    fn seal(&self, payload: Vec<u8>) -> Result<Vec<u8>, Box<dyn Error + Send + Sync>>;
+
+    /// Payload of an answer body or of one streamed event
+    fn open(&self, body: &str) -> Result<String, Box<dyn Error + Send + Sync>>;
+}
+
+/// Payloads as plain JSON
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PlainJson;
+
+impl Transport for PlainJson {
+    fn name(&self) -> &'static str {
+        "plain"
+    }
+
+    fn is_encrypted(&self) -> bool {
+        false
+    }
+
+    fn endpoint(&self, path: &str) -> String {
+        path.to_string()
+    }
+
+    fn seal(&self, payload: Vec<u8>) -> Result<Vec<u8>, Box<dyn Error + Send + Sync>> {
+        Ok(payload)
+    }
+
+    fn open(&self, body: &str) -> Result<String, Box<dyn Error + Send + Sync>> {
+        Ok(body.to_string())
+    }
+}
+
+/// Payloads encrypted with AES-256-GCM under the shared key, in a `SecureEnvelope`
+pub struct AesGcmEnvelope {
+    messenger: SecureMessenger,
+}
+
+impl AesGcmEnvelope {
+    pub fn new(key: &str) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let messenger = SecureMessenger::new(key).map_err(|e| format!("Failed to init encryption: {}", e))?;
+        Ok(Self { messenger })
+    }
+}
+
+impl Transport for AesGcmEnvelope {
+    fn name(&self) -> &'static str {
+        "aes-gcm"
+    }
+
+    fn is_encrypted(&self) -> bool {
+        true
+    }
+
+    fn endpoint(&self, path: &str) -> String {
+        format!("{}/secure", path)
+    }
+
+    fn seal(&self, payload: Vec<u8>) -> Result<Vec<u8>, Box<dyn Error + Send + Sync>> {
+        let encrypted = self.messenger.encrypt(&payload).map_err(|e| format!("Encryption failed: {}", e))?;
+        let envelope = bridge::SecureEnvelope {
+            data: general_purpose::STANDARD.encode(encrypted),
+        };
+        Ok(serde_json::to_vec(&envelope)?)
+    }
+
+    fn open(&self, body: &str) -> Result<String, Box<dyn Error + Send + Sync>> {
+        let envelope: bridge::SecureEnvelope = wire::decode("bridge", body)?;
+        let decrypted = general_purpose::STANDARD
+            .decode(&envelope.data)
+            .map_err(|e| e.to_string())
+            .and_then(|data| self.messenger.decrypt(&data).map_err(|e| e.to_string()))
+            .map_err(|e| format!("Decryption failed: {}", e))?;
+        Ok(String::from_utf8(decrypted).map_err(|e| format!("Decryption failed: {}", e))?)
+    }
+}
+
+/// When bridge payloads have to be encrypted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EncryptionPolicy {
+    /// Always encrypt; refuse to send when there is no key
+    Require,
+    /// Encrypt when encryption is on and a key is configured, otherwise send plain text
+    #[default]
+    Prefer,
+    /// Never encrypt
+    Off,
+}
+
+/// A call refused by the encryption policy; nothing was sent
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum EncryptionPolicyError {
+    #[error("encryption is required but no encryption key is configured")]
+    KeyMissing,
+    #[error("encryption is required but the '{0}' transport does not encrypt")]
+    Unencrypted(&'static str),
+    #[error("encryption is required but the bridge does not support it")]
+    BridgeUnencrypted,
+    #[error("refusing to send plain text over http:// to {0}; use https, turn on encryption or allow plain-text http")]
+    PlaintextHttp(String),
+}
+
+/// What the next bridge call would be sent with, for the UI
+#[derive(Debug, Clone, Serialize)]
+pub struct TransportSecurity {
+    pub policy: EncryptionPolicy,
+    /// Transport name (`plain`, `aes-gcm`, ...), `None` when the call would be refused
+    pub transport: Option<&'static str>,
+    pub encrypted: bool,
+    pub https: bool,
+    /// Why the call would be refused
+    pub refused: Option<String>,
+}
+
+/// Plain text may go here without exposing it on the network: `https://` or a loopback host
+pub(crate) fn is_private_channel(url: &reqwest::Url) -> bool {
+    if url.scheme() == "https" {
+        return true;
+    }
+    let Some(host) = url.host_str() else {
+        return false;
+    };
+    match host.trim_start_matches('[').trim_end_matches(']').parse::<std::net::IpAddr>() {
+        Ok(ip) => ip.is_loopback(),
+        Err(_) => host == "localhost" || host.ends_with(".localhost"),
+    }
+}
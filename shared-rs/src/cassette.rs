@@ -0,0 +1,372 @@
+// Record-and-replay of provider traffic (feature "cassette").
+//
+// A CassetteServer is a local HTTP server that clients use as their base URL:
+//
+// - record: every request is forwarded to the real upstream and the
+//   request/response pair is written to the cassette file, with API keys and
+//   registered secrets (e.g. encryption keys) replaced by "[REDACTED]"
+// - replay: requests are answered from the cassette; a request without a
+//   recorded match gets a 599 response and is listed by `unmatched()`
+//
+//     let cassette = CassetteServer::from_env("tests/cassettes/dune.json", "https://api.anthropic.com").await?;
+//     let client = AnthropicClient::new(key).with_base_url(cassette.url());
+//
+// Set APIAI_CASSETTE=record to re-record; the default is replay.
+
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Request, Response, Server, StatusCode};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::error::Error;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tokio::sync::oneshot;
+
+const CASSETTE_VERSION: u32 = 1;
+const REDACTED: &str = "[REDACTED]";
+/// Status returned for requests that have no recorded interaction
+pub const CASSETTE_MISS_STATUS: u16 = 599;
+/// Headers that always carry credentials
+const SECRET_HEADERS: &[&str] = &["authorization", "x-api-key", "cookie", "set-cookie", "proxy-authorization"];
+/// Headers not worth storing or replaying
+const SKIPPED_HEADERS: &[&str] = &["host", "content-length", "connection", "transfer-encoding", "accept-encoding", "date"];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedExchange {
+    pub request: CassetteRequest,
+    pub response: CassetteResponse,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CassetteRequest {
+    pub method: String,
+    pub path: String, // Including the query string
+    pub headers: HashMap<String, String>,
+    pub body: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CassetteResponse {
+    pub status: u16,
+    pub headers: HashMap<String, String>,
+    pub body: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Cassette {
+    pub version: u32,
+    pub upstream: String,
+    pub interactions: Vec<RecordedExchange>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Mode {
+    Record,
+    Replay,
+}
+
+struct CassetteState {
+    mode: Mode,
+    path: PathBuf,
+    cassette: Cassette,
+    used: Vec<bool>, // Replay: which interactions were already served
+    secrets: Vec<String>,
+    ignored_body_fields: Vec<String>,
+    match_body: bool,
+    unmatched: Vec<String>,
+    http: reqwest::Client,
+}
+
+pub struct CassetteServer {
+    addr: SocketAddr,
+    state: Arc<Mutex<CassetteState>>,
+    shutdown: Option<oneshot::Sender<()>>,
+}
+
+impl CassetteServer {
+    /// Forward to `upstream` (e.g. `https://api.openai.com`) and write a fresh cassette at `path`
+    pub async fn record(path: impl AsRef<Path>, upstream: &str) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let cassette = Cassette {
+            version: CASSETTE_VERSION,
+            upstream: upstream.trim_end_matches('/').to_string(),
+            interactions: Vec::new(),
+        };
+        Ok(Self::start(Mode::Record, path.as_ref().to_path_buf(), cassette))
+    }
+
+    /// Serve the interactions stored at `path`
+    pub async fn replay(path: impl AsRef<Path>) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let content = std::fs::read_to_string(path.as_ref())
+            .map_err(|e| format!("Failed to read cassette {:?}: {}", path.as_ref(), e))?;
+        let cassette: Cassette = serde_json::from_str(&content)
+            .map_err(|e| format!("Failed to parse cassette {:?}: {}", path.as_ref(), e))?;
+        Ok(Self::start(Mode::Replay, path.as_ref().to_path_buf(), cassette))
+    }
+
+    /// Record when `APIAI_CASSETTE=record`, otherwise replay
+    pub async fn from_env(path: impl AsRef<Path>, upstream: &str) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        match std::env::var("APIAI_CASSETTE").as_deref() {
+            Ok("record") => Self::record(path, upstream).await,
+            _ => Self::replay(path).await,
+        }
+    }
+
+    fn start(mode: Mode, path: PathBuf, cassette: Cassette) -> Self {
+        let used = vec![false; cassette.interactions.len()];
+        let state = Arc::new(Mutex::new(CassetteState {
+            mode,
+            path,
+            cassette,
+            used,
+            secrets: Vec::new(),
+            ignored_body_fields: vec!["request_id".to_string()],
+            match_body: true,
+            unmatched: Vec::new(),
+            http: reqwest::Client::new(),
+        }));
+
+        let service_state = state.clone();
+        let make_svc = make_service_fn(move |_conn| {
+            let state = service_state.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |req| handle(state.clone(), req)))
+            }
+        });
+
+        let server = Server::try_bind(&SocketAddr::from(([127, 0, 0, 1], 0)))
+            .expect("cassette server: failed to bind")
+            .serve(make_svc);
+        let addr = server.local_addr();
+
+        let (shutdown, stopped) = oneshot::channel::<()>();
+        tokio::spawn(server.with_graceful_shutdown(async {
+            stopped.await.ok();
+        }));
+
+        Self {
+            addr,
+            state,
+            shutdown: Some(shutdown),
+        }
+    }
+
+    /// Base URL for clients
+    pub fn url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    /// Replace `secret` (an encryption key, a token in a URL, ...) wherever it
+    /// appears in recorded traffic. Credential headers are always redacted.
+    pub fn redact(&self, secret: &str) {
+        if !secret.is_empty() {
+            self.state.lock().unwrap().secrets.push(secret.to_string());
+        }
+    }
+
+    /// Top-level JSON body field that may differ between recording and replay
+    /// (`request_id` is ignored by default)
+    pub fn ignore_body_field(&self, field: &str) {
+        self.state.lock().unwrap().ignored_body_fields.push(field.to_string());
+    }
+
+    /// Match on method and path only, serving interactions in recorded order.
+    /// Needed for bodies that are never the same twice, like encrypted envelopes.
+    pub fn match_in_order(&self) {
+        self.state.lock().unwrap().match_body = false;
+    }
+
+    /// Requests that had no recorded interaction ("METHOD /path")
+    pub fn unmatched(&self) -> Vec<String> {
+        self.state.lock().unwrap().unmatched.clone()
+    }
+
+    /// Interactions recorded so far (record) or loaded (replay)
+    pub fn interactions(&self) -> Vec<RecordedExchange> {
+        self.state.lock().unwrap().cassette.interactions.clone()
+    }
+}
+
+impl Drop for CassetteServer {
+    fn drop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+    }
+}
+
+impl CassetteState {
+    fn scrub(&self, text: &str) -> String {
+        self.secrets.iter().fold(text.to_string(), |text, secret| text.replace(secret, REDACTED))
+    }
+
+    fn scrub_headers(&self, headers: &HashMap<String, String>) -> HashMap<String, String> {
+        headers.iter()
+            .filter(|(name, _)| !SKIPPED_HEADERS.contains(&name.as_str()))
+            .map(|(name, value)| {
+                let value = if SECRET_HEADERS.contains(&name.as_str()) {
+                    REDACTED.to_string()
+                } else {
+                    self.scrub(value)
+                };
+                (name.clone(), value)
+            })
+            .collect()
+    }
+
+    /// Body as compared during replay: JSON without ignored fields, else the raw text
+    fn comparable_body(&self, body: &str) -> serde_json::Value {
+        match serde_json::from_str::<serde_json::Value>(body) {
+            Ok(serde_json::Value::Object(mut map)) => {
+                for field in &self.ignored_body_fields {
+                    map.remove(field);
+                }
+                serde_json::Value::Object(map)
+            }
+            Ok(value) => value,
+            Err(_) => serde_json::Value::String(body.to_string()),
+        }
+    }
+
+    fn find_match(&self, method: &str, path: &str, body: &str) -> Option<usize> {
+        let wanted = self.comparable_body(&self.scrub(body));
+        self.cassette.interactions.iter().enumerate().position(|(i, exchange)| {
+            !self.used[i]
+                && exchange.request.method == method
+                && exchange.request.path == path
+                && (!self.match_body || self.comparable_body(&exchange.request.body) == wanted)
+        })
+    }
+
+    fn save(&self) -> Result<(), String> {
+        if let Some(dir) = self.path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create {:?}: {}", dir, e))?;
+        }
+        let content = serde_json::to_string_pretty(&self.cassette)
+            .map_err(|e| format!("Failed to serialize cassette: {}", e))?;
+        std::fs::write(&self.path, content)
+            .map_err(|e| format!("Failed to write cassette {:?}: {}", self.path, e))
+    }
+}
+
+async fn handle(state: Arc<Mutex<CassetteState>>, req: Request<Body>) -> Result<Response<Body>, Infallible> {
+    let method = req.method().to_string();
+    let path = req.uri().path_and_query().map(|p| p.as_str().to_string()).unwrap_or_else(|| "/".to_string());
+    let headers: HashMap<String, String> = req.headers()
+        .iter()
+        .map(|(name, value)| (name.as_str().to_lowercase(), value.to_str().unwrap_or("").to_string()))
+        .collect();
+    let body_bytes = hyper::body::to_bytes(req.into_body()).await.unwrap_or_default();
+    let body = String::from_utf8_lossy(&body_bytes).to_string();
+
+    let mode = state.lock().unwrap().mode.clone();
+    let response = match mode {
+        Mode::Replay => replay(&state, &method, &path, &body),
+        Mode::Record => record(&state, &method, &path, headers, body).await,
+    };
+
+    Ok(to_hyper(response))
+}
+
+fn replay(state: &Mutex<CassetteState>, method: &str, path: &str, body: &str) -> CassetteResponse {
+    let mut state = state.lock().unwrap();
+    match state.find_match(method, path, body) {
+        Some(i) => {
+            state.used[i] = true;
+            state.cassette.interactions[i].response.clone()
+        }
+        None => {
+            let miss = format!("{} {}", method, path);
+            tracing::warn!(path = %state.path.display(), miss = %miss, "cassette has no recorded interaction");
+            state.unmatched.push(miss.clone());
+            CassetteResponse {
+                status: CASSETTE_MISS_STATUS,
+                headers: HashMap::from([("content-type".to_string(), "application/json".to_string())]),
+                body: serde_json::json!({
+                    "error": format!("cassette miss: no recorded interaction for {}", miss)
+                }).to_string(),
+            }
+        }
+    }
+}
+
+async fn record(
+    state: &Mutex<CassetteState>,
+    method: &str,
+    path: &str,
+    headers: HashMap<String, String>,
+    body: String,
+) -> CassetteResponse {
+    let (http, upstream) = {
+        let state = state.lock().unwrap();
+        (state.http.clone(), state.cassette.upstream.clone())
+    };
+
+    let mut request = http.request(
+        reqwest::Method::from_bytes(method.as_bytes()).unwrap_or(reqwest::Method::POST),
+        format!("{}{}", upstream, path),
+    );
+    for (name, value) in &headers {
+        if !SKIPPED_HEADERS.contains(&name.as_str()) {
+            request = request.header(name.as_str(), value.as_str());
+        }
+    }
+
+    let response = match request.body(body.clone()).send().await {
+        Ok(upstream_response) => {
+            let status = upstream_response.status().as_u16();
+            let response_headers = upstream_response.headers()
+                .iter()
+                .map(|(name, value)| (name.as_str().to_lowercase(), value.to_str().unwrap_or("").to_string()))
+                .collect();
+            let response_body = upstream_response.text().await.unwrap_or_default();
+            CassetteResponse {
+                status,
+                headers: response_headers,
+                body: response_body,
+            }
+        }
+        Err(e) => CassetteResponse {
+            status: 502,
+            headers: HashMap::new(),
+            body: serde_json::json!({"error": format!("cassette upstream request failed: {}", e)}).to_string(),
+        },
+    };
+
+    let mut state = state.lock().unwrap();
+    let exchange = RecordedExchange {
+        request: CassetteRequest {
+            method: method.to_string(),
+            path: state.scrub(path),
+            headers: state.scrub_headers(&headers),
+            body: state.scrub(&body),
+        },
+        response: CassetteResponse {
+            status: response.status,
+            headers: state.scrub_headers(&response.headers),
+            body: state.scrub(&response.body),
+        },
+    };
+    state.cassette.interactions.push(exchange);
+    state.used.push(true);
+    // Write after every interaction so an aborted test run still leaves a usable cassette
+    if let Err(e) = state.save() {
+        tracing::error!(path = %state.path.display(), error = %e, "failed to save cassette");
+    }
+
+    response
+}
+
+fn to_hyper(response: CassetteResponse) -> Response<Body> {
+    let status = StatusCode::from_u16(response.status).unwrap_or(StatusCode::BAD_GATEWAY);
+    let mut builder = Response::builder().status(status);
+    for (name, value) in &response.headers {
+        if !SKIPPED_HEADERS.contains(&name.as_str()) {
+            builder = builder.header(name.as_str(), value.as_str());
+        }
+    }
+    builder
+        .body(Body::from(response.body))
+        .expect("cassette server: invalid response")
+}
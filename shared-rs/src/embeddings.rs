@@ -0,0 +1,274 @@
+// Embeddings
+// Clients for computing text embeddings (OpenAI, OpenAI-compatible servers, Ollama)
+
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+
+use crate::api::OPENAI_BASE_URL;
+
+const OPENAI_DEFAULT_MODEL: &str = "text-embedding-3-small";
+const OLLAMA_BASE_URL: &str = "http://localhost:11434";
+const OLLAMA_DEFAULT_MODEL: &str = "nomic-embed-text";
+const DEFAULT_BATCH_SIZE: usize = 96;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EmbeddingUsage {
+    pub prompt_tokens: u64,
+    pub total_tokens: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmbeddingResult {
+    /// One vector per input, in input order
+    pub embeddings: Vec<Vec<f32>>,
+    pub model: String,
+    /// Summed over all batches; `None` if the server does not report usage
+    pub usage: Option<EmbeddingUsage>,
+}
+
+#[async_trait::async_trait]
+pub trait EmbeddingClient: Send + Sync {
+    /// Embed all inputs, splitting them into batches as needed
+    async fn embed(&self, inputs: &[String]) -> Result<EmbeddingResult, Box<dyn Error + Send + Sync>>;
+
+    fn model(&self) -> &str;
+
+    /// Requested vector size, `None` for the model's default
+    fn dimensions(&self) -> Option<u32>;
+}
+
+/// Runs `embed_batch` over `inputs` in chunks and joins the results
+async fn embed_in_batches<'a, F, Fut>(
+    inputs: &'a [String],
+    batch_size: usize,
+    model: &str,
+    mut embed_batch: F,
+) -> Result<EmbeddingResult, Box<dyn Error + Send + Sync>>
+where
+    F: FnMut(&'a [String]) -> Fut,
+    Fut: std::future::Future<Output = Result<EmbeddingResult, Box<dyn Error + Send + Sync>>>,
+{
+    let mut result = EmbeddingResult {
+        embeddings: Vec::with_capacity(inputs.len()),
+        model: model.to_string(),
+        usage: None,
+    };
+
+    for batch in inputs.chunks(batch_size.max(1)) {
+        let part = embed_batch(batch).await?;
+        if part.embeddings.len() != batch.len() {
+            return Err(format!(
+                "Embedding server returned {} vectors for {} inputs",
+                part.embeddings.len(),
+                batch.len()
+            ).into());
+        }
+        result.embeddings.extend(part.embeddings);
+        result.model = part.model;
+        if let Some(usage) = part.usage {
+            let total = result.usage.get_or_insert_with(EmbeddingUsage::default);
+            total.prompt_tokens += usage.prompt_tokens;
+            total.total_tokens += usage.total_tokens;
+        }
+    }
+
+    Ok(result)
+}
+
+/// OpenAI `/v1/embeddings`, or any server implementing the same API
+/// (LM Studio, vLLM, LocalAI, ...)
+pub struct OpenAIEmbeddingClient {
+    base_url: String,
+    api_key: String,
+    model: String,
+    dimensions: Option<u32>,
+    batch_size: usize,
+    client: reqwest::Client,
+}
+
+impl OpenAIEmbeddingClient {
+    pub fn new(api_key: String) -> Self {
+        Self::compatible(OPENAI_BASE_URL.to_string(), api_key)
+    }
+
+    /// Client for an OpenAI-compatible server; `base_url` is the part before `/embeddings`
+    /// (e.g. `http://localhost:1234/v1`). The API key may be empty.
+    pub fn compatible(base_url: String, api_key: String) -> Self {
+        Self {
+            base_url,
+            api_key,
+            model: OPENAI_DEFAULT_MODEL.to_string(),
+            dimensions: None,
+            batch_size: DEFAULT_BATCH_SIZE,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    pub fn with_model(mut self, model: String) -> Self {
+        self.model = model;
+        self
+    }
+
+    /// Shorten vectors to this many dimensions (text-embedding-3 models and later)
+    pub fn with_dimensions(mut self, dimensions: u32) -> Self {
+        self.dimensions = Some(dimensions);
+        self
+    }
+
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size;
+        self
+    }
+
+    async fn embed_batch(&self, inputs: &[String]) -> Result<EmbeddingResult, Box<dyn Error + Send + Sync>> {
+        #[derive(Deserialize)]
+        struct Item {
+            index: usize,
+            embedding: Vec<f32>,
+        }
+        #[derive(Deserialize)]
+        struct Response {
+            data: Vec<Item>,
+            model: String,
+            usage: Option<EmbeddingUsage>,
+        }
+
+        let mut body = serde_json::json!({
+            "model": self.model,
+            "input": inputs,
+            "encoding_format": "float"
+        });
+        if let Some(dimensions) = self.dimensions {
+            body["dimensions"] = serde_json::json!(dimensions);
+        }
+
+        let mut request = self.client
+            .post(format!("{}/embeddings", self.base_url.trim_end_matches('/')))
+            .json(&body);
+        if !self.api_key.is_empty() {
+            request = request.bearer_auth(&self.api_key);
+        }
+        let response = request.send().await?;
+
+        if !response.status().is_success() {
+            let error_text = response.text().await?;
+            return Err(format!("Embeddings API error: {}", error_text).into());
+        }
+
+        let mut parsed: Response = response.json().await?;
+        // The API does not promise to keep input order
+        parsed.data.sort_by_key(|item| item.index);
+
+        Ok(EmbeddingResult {
+            embeddings: parsed.data.into_iter().map(|item| item.embedding).collect(),
+            model: parsed.model,
+            usage: parsed.usage,
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl EmbeddingClient for OpenAIEmbeddingClient {
+    async fn embed(&self, inputs: &[String]) -> Result<EmbeddingResult, Box<dyn Error + Send + Sync>> {
+        embed_in_batches(inputs, self.batch_size, &self.model, |batch| self.embed_batch(batch)).await
+    }
+
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    fn dimensions(&self) -> Option<u32> {
+        self.dimensions
+    }
+}
+
+/// Ollama `/api/embed`
+pub struct OllamaEmbeddingClient {
+    base_url: String,
+    model: String,
+    dimensions: Option<u32>,
+    batch_size: usize,
+    client: reqwest::Client,
+}
+
+impl OllamaEmbeddingClient {
+    /// `base_url` is the Ollama server root; `None` means `http://localhost:11434`
+    pub fn new(base_url: Option<String>) -> Self {
+        Self {
+            base_url: base_url.unwrap_or_else(|| OLLAMA_BASE_URL.to_string()),
+            model: OLLAMA_DEFAULT_MODEL.to_string(),
+            dimensions: None,
+            batch_size: DEFAULT_BATCH_SIZE,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    pub fn with_model(mut self, model: String) -> Self {
+        self.model = model;
+        self
+    }
+
+    /// Truncate vectors to this many dimensions (models that support it)
+    pub fn with_dimensions(mut self, dimensions: u32) -> Self {
+        self.dimensions = Some(dimensions);
+        self
+    }
+
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size;
+        self
+    }
+
+    async fn embed_batch(&self, inputs: &[String]) -> Result<EmbeddingResult, Box<dyn Error + Send + Sync>> {
+        #[derive(Deserialize)]
+        struct Response {
+            model: String,
+            embeddings: Vec<Vec<f32>>,
+            prompt_eval_count: Option<u64>,
+        }
+
+        let mut body = serde_json::json!({
+            "model": self.model,
+            "input": inputs
+        });
+        if let Some(dimensions) = self.dimensions {
+            body["dimensions"] = serde_json::json!(dimensions);
+        }
+
+        let response = self.client
+            .post(format!("{}/api/embed", self.base_url.trim_end_matches('/')))
+            .json(&body)
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            let error_text = response.text().await?;
+            return Err(format!("Ollama embeddings error: {}", error_text).into());
+        }
+
+        let parsed: Response = response.json().await?;
+        Ok(EmbeddingResult {
+            embeddings: parsed.embeddings,
+            model: parsed.model,
+            usage: parsed.prompt_eval_count.map(|tokens| EmbeddingUsage {
+                prompt_tokens: tokens,
+                total_tokens: tokens,
+            }),
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl EmbeddingClient for OllamaEmbeddingClient {
+    async fn embed(&self, inputs: &[String]) -> Result<EmbeddingResult, Box<dyn Error + Send + Sync>> {
+        embed_in_batches(inputs, self.batch_size, &self.model, |batch| self.embed_batch(batch)).await
+    }
+
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    fn dimensions(&self) -> Option<u32> {
+        self.dimensions
+    }
+}
@@ -2,8 +2,42 @@
 // Common API clients and encryption utilities
 
 pub mod api;
+pub mod batch;
+pub mod bridge_conversations;
+pub mod bridge_health;
+pub mod bridge_jobs;
+pub mod bridge_stream;
+pub mod bridge_transport;
+#[cfg(feature = "cassette")]
+pub mod cassette;
+pub mod embeddings;
 pub mod encryption;
+pub mod logging;
+#[cfg(feature = "mock-server")]
+pub mod mock_server;
+pub mod responses;
+pub mod signing;
+pub mod sse;
+pub mod structured;
+pub mod wire;
 
 // Re-export commonly used types
-pub use api::{ApiClient, AnthropicClient, OpenAIClient, TelegramClient, SearchResult};
+pub use api::{ApiClient, ApiError, AnthropicClient, OpenAIClient, PromptBlock, TelegramClient, SearchResult, TokenUsage, BRIDGE_PROTOCOL_VERSION};
+pub use batch::{match_results, BatchClient, BatchItemResult, BatchJob, BatchRequest, BatchStatus};
+pub use bridge_health::{HealthFailure, HealthReport};
+pub use bridge_jobs::JobProgress;
+pub use bridge_stream::BridgeStream;
+pub use bridge_transport::{
+    AesGcmEnvelope, EncryptionPolicy, EncryptionPolicyError, PlainJson, Transport, TransportSecurity,
+};
+pub use embeddings::{EmbeddingClient, EmbeddingResult, EmbeddingUsage, OllamaEmbeddingClient, OpenAIEmbeddingClient};
 pub use encryption::SecureMessenger;
+pub use responses::{OpenAIResponsesClient, ResponseEvent, ResponseStream};
+pub use signing::{NonceCache, RequestSigner, SignatureError};
+pub use structured::{StructuredClient, StructuredError, StructuredOutput, StructuredResult};
+pub use wire::anthropic::ContentBlock;
+pub use wire::bridge::{
+    Capabilities, ConversationMessage, ConversationSummary, EncryptionRequirement, JobState, JobStatus, UpstreamProvider,
+};
+pub use wire::openai::ReasoningEffort;
+pub use wire::DecodeError;
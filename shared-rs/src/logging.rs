@@ -0,0 +1,29 @@
+// Logging
+// The library logs through `tracing` and leaves levels and output to the
+// application. Bridge calls run in a span carrying the request id, upstream
+// provider and transport; events report URLs, statuses, sizes and timings.
+// Prompts and answers are what encryption protects, so log fields only show
+// their size unless `set_log_bodies(true)` was called, e.g. while debugging a
+// local bridge.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static LOG_BODIES: AtomicBool = AtomicBool::new(false);
+
+/// Log prompts and answers in full instead of redacting them
+pub fn set_log_bodies(enabled: bool) {
+    LOG_BODIES.store(enabled, Ordering::Relaxed);
+}
+
+pub fn log_bodies() -> bool {
+    LOG_BODIES.load(Ordering::Relaxed)
+}
+
+/// Log field for a prompt or answer: the text when bodies are logged, its size otherwise
+pub fn body(text: &str) -> String {
+    if log_bodies() {
+        text.to_string()
+    } else {
+        format!("<redacted {} bytes>", text.len())
+    }
+}
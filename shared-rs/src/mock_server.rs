@@ -0,0 +1,636 @@
+// Local stand-in for the Anthropic Messages API, OpenAI Chat Completions,
+// Responses and Embeddings, Ollama embeddings, and the Telegram bridge, for
+// integration tests (feature "mock-server").
+//
+// Every endpoint answers with a plausible default response. Tests can queue
+// scripted responses per path (errors, delays, SSE streams) and inspect the
+// requests the server received:
+//
+//     let server = MockServer::start().await;
+//     server.enqueue("/v1/messages", MockResponse::error(529, json!({"error": "overloaded"})));
+//     let client = AnthropicClient::new(key).with_base_url(server.url());
+
+use hyper::body::Bytes;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Request, Response, Server, StatusCode};
+use std::collections::{HashMap, VecDeque};
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::oneshot;
+
+use crate::encryption::SecureMessenger;
+use crate::signing::{NonceCache, RequestSigner};
+
+pub const ANTHROPIC_MESSAGES_PATH: &str = "/v1/messages";
+pub const OPENAI_CHAT_PATH: &str = "/v1/chat/completions";
+pub const OPENAI_RESPONSES_PATH: &str = "/v1/responses";
+pub const ANTHROPIC_BATCHES_PATH: &str = "/v1/messages/batches";
+pub const OPENAI_FILES_PATH: &str = "/v1/files";
+pub const OPENAI_BATCHES_PATH: &str = "/v1/batches";
+pub const OPENAI_EMBEDDINGS_PATH: &str = "/v1/embeddings";
+pub const OLLAMA_EMBED_PATH: &str = "/api/embed";
+pub const BRIDGE_QUERY_PATH: &str = "/ai_query";
+pub const BRIDGE_SECURE_QUERY_PATH: &str = "/ai_query/secure";
+pub const BRIDGE_CANCEL_PATH: &str = "/cancel_request";
+pub const BRIDGE_PROVIDERS_PATH: &str = "/providers";
+pub const BRIDGE_CAPABILITIES_PATH: &str = "/capabilities";
+pub const BRIDGE_HEALTH_PATH: &str = "/health";
+pub const BRIDGE_SECURE_HEALTH_PATH: &str = "/health/secure";
+/// Single conversations live under this path as `/conversations/{id}[/messages|/reset]`
+pub const BRIDGE_CONVERSATIONS_PATH: &str = "/conversations";
+pub const BRIDGE_SECURE_CONVERSATIONS_PATH: &str = "/conversations/secure";
+/// Job status is polled at `/jobs/{id}`
+pub const BRIDGE_JOBS_PATH: &str = "/jobs";
+pub const BRIDGE_SECURE_JOBS_PATH: &str = "/jobs/secure";
+pub const BRIDGE_SECURE_JOB_STATUS_PATH: &str = "/jobs/status/secure";
+pub const BRIDGE_JOB_CANCEL_PATH: &str = "/jobs/cancel";
+
+#[derive(Debug, Clone)]
+pub enum MockBody {
+    Json(serde_json::Value),
+    Text(String),
+    /// Server-sent events; each entry is sent as one `data:` event
+    Sse(Vec<String>),
+}
+
+/// A scripted answer for one request
+#[derive(Debug, Clone)]
+pub struct MockResponse {
+    pub status: u16,
+    pub body: MockBody,
+    /// Wait before sending the response head
+    pub delay: Duration,
+    /// Wait before each SSE event
+    pub chunk_delay: Duration,
+}
+
+impl MockResponse {
+    pub fn json(body: serde_json::Value) -> Self {
+        Self::with_body(200, MockBody::Json(body))
+    }
+
+    pub fn text(body: impl Into<String>) -> Self {
+        Self::with_body(200, MockBody::Text(body.into()))
+    }
+
+    pub fn error(status: u16, body: serde_json::Value) -> Self {
+        Self::with_body(status, MockBody::Json(body))
+    }
+
+    pub fn sse<I: IntoIterator<Item = S>, S: Into<String>>(events: I) -> Self {
+        Self::with_body(200, MockBody::Sse(events.into_iter().map(Into::into).collect()))
+    }
+
+    fn with_body(status: u16, body: MockBody) -> Self {
+        Self {
+            status,
+            body,
+            delay: Duration::ZERO,
+            chunk_delay: Duration::ZERO,
+        }
+    }
+
+    pub fn with_delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    pub fn with_chunk_delay(mut self, chunk_delay: Duration) -> Self {
+        self.chunk_delay = chunk_delay;
+        self
+    }
+}
+
+/// A request as received by the mock server
+#[derive(Debug, Clone)]
+pub struct RecordedRequest {
+    pub method: String,
+    pub path: String,
+    pub headers: HashMap<String, String>, // Lower-case names
+    pub body: String,
+}
+
+impl RecordedRequest {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(&name.to_lowercase()).map(String::as_str)
+    }
+
+    pub fn json(&self) -> serde_json::Value {
+        serde_json::from_str(&self.body).unwrap_or(serde_json::Value::Null)
+    }
+}
+
+#[derive(Default)]
+struct MockState {
+    scripted: HashMap<String, VecDeque<MockResponse>>,
+    requests: Vec<RecordedRequest>,
+    encryption_key: Option<String>,
+    signer: Option<RequestSigner>,
+    nonces: NonceCache,
+    /// Submitted bridge jobs by id: the query and how often it was polled
+    jobs: HashMap<String, (serde_json::Value, u32)>,
+}
+
+pub struct MockServer {
+    addr: SocketAddr,
+    state: Arc<Mutex<MockState>>,
+    shutdown: Option<oneshot::Sender<()>>,
+}
+
+impl MockServer {
+    /// Start on a random local port; the server stops when dropped
+    pub async fn start() -> Self {
+        let state = Arc::new(Mutex::new(MockState::default()));
+
+        let service_state = state.clone();
+        let make_svc = make_service_fn(move |_conn| {
+            let state = service_state.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |req| handle(state.clone(), req)))
+            }
+        });
+
+        let server = Server::try_bind(&SocketAddr::from(([127, 0, 0, 1], 0)))
+            .expect("mock server: failed to bind")
+            .serve(make_svc);
+        let addr = server.local_addr();
+
+        let (shutdown, stopped) = oneshot::channel::<()>();
+        tokio::spawn(server.with_graceful_shutdown(async {
+            stopped.await.ok();
+        }));
+
+        Self {
+            addr,
+            state,
+            shutdown: Some(shutdown),
+        }
+    }
+
+    /// Root URL; use as `AnthropicClient` base URL
+    pub fn url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    /// Use as `OpenAIClient` base URL
+    pub fn openai_url(&self) -> String {
+        format!("{}/v1", self.url())
+    }
+
+    /// Plaintext bridge endpoint for `TelegramClient`
+    pub fn bridge_url(&self) -> String {
+        format!("{}{}", self.url(), BRIDGE_QUERY_PATH)
+    }
+
+    /// Encrypted bridge endpoint for `TelegramClient`
+    pub fn bridge_secure_url(&self) -> String {
+        format!("{}{}", self.url(), BRIDGE_SECURE_QUERY_PATH)
+    }
+
+    /// Key the secure bridge endpoint uses to decrypt requests and encrypt responses
+    pub fn set_encryption_key(&self, key: &str) {
+        self.state.lock().unwrap().encryption_key = Some(key.to_string());
+    }
+
+    /// Require signed requests on the bridge endpoints; failures get a 401
+    pub fn set_request_signer(&self, signer: RequestSigner) {
+        self.state.lock().unwrap().signer = Some(signer);
+    }
+
+    /// Queue a response for the next request to `path`. Responses are used
+    /// in order; once the queue is empty the default emulation answers.
+    /// JSON responses to the secure bridge endpoints are encrypted automatically.
+    pub fn enqueue(&self, path: &str, response: MockResponse) {
+        self.state.lock().unwrap()
+            .scripted
+            .entry(path.to_string())
+            .or_default()
+            .push_back(response);
+    }
+
+    /// All requests received so far, in arrival order
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        self.state.lock().unwrap().requests.clone()
+    }
+
+    /// Requests received for one path
+    pub fn requests_to(&self, path: &str) -> Vec<RecordedRequest> {
+        self.requests().into_iter().filter(|r| r.path == path).collect()
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+    }
+}
+
+async fn handle(state: Arc<Mutex<MockState>>, req: Request<Body>) -> Result<Response<Body>, Infallible> {
+    let method = req.method().to_string();
+    let path = req.uri().path().to_string();
+    let req_query = req.uri().query().map(str::to_string);
+    let path_and_query = req.uri().path_and_query().map(|p| p.to_string()).unwrap_or_else(|| path.clone());
+    let headers = req.headers()
+        .iter()
+        .map(|(name, value)| (name.as_str().to_lowercase(), value.to_str().unwrap_or("").to_string()))
+        .collect();
+    let body_bytes = hyper::body::to_bytes(req.into_body()).await.unwrap_or_default();
+    let body = String::from_utf8_lossy(&body_bytes).to_string();
+
+    let (scripted, encryption_key) = {
+        let mut state = state.lock().unwrap();
+        let request = RecordedRequest {
+            method,
+            path: path.clone(),
+            headers,
+            body: body.clone(),
+        };
+
+        // Provider APIs live under /v1; everything else is the bridge
+        if let Some(signer) = state.signer.clone().filter(|_| !path.starts_with("/v1/")) {
+            let verified = signer.verify(&request.method, &path_and_query, &body_bytes, |name| request.header(name), &mut state.nonces);
+            if let Err(e) = verified {
+                state.requests.push(request);
+                let detail = format!("Invalid signature: {}", e);
+                return Ok(build_response(MockResponse::error(401, serde_json::json!({"detail": detail}))));
+            }
+        }
+
+        state.requests.push(request);
+        let scripted = state.scripted.get_mut(&path).and_then(VecDeque::pop_front);
+        (scripted, state.encryption_key.clone())
+    };
+
+    let request_json: serde_json::Value = serde_json::from_str(&body).unwrap_or(serde_json::Value::Null);
+    let mut response = match scripted {
+        Some(response) => response,
+        None => {
+            let mut state = state.lock().unwrap();
+            bridge_job(&path, req_query.as_deref(), &request_json, encryption_key.as_deref(), &mut state.jobs)
+                .unwrap_or_else(|| default_response(&path, &request_json, encryption_key.as_deref()))
+        }
+    };
+
+    // Secure endpoints speak the encrypted envelope; wrap scripted success bodies
+    let secure = [
+        BRIDGE_SECURE_QUERY_PATH,
+        BRIDGE_SECURE_HEALTH_PATH,
+        BRIDGE_SECURE_CONVERSATIONS_PATH,
+        BRIDGE_SECURE_JOBS_PATH,
+        BRIDGE_SECURE_JOB_STATUS_PATH,
+    ]
+    .contains(&path.as_str());
+    if secure && response.status < 400 {
+        if let (MockBody::Json(ref value), Some(ref key)) = (&response.body, &encryption_key) {
+            response.body = MockBody::Json(encrypt_envelope(key, value));
+        }
+    }
+
+    if !response.delay.is_zero() {
+        tokio::time::sleep(response.delay).await;
+    }
+
+    Ok(build_response(response))
+}
+
+fn build_response(response: MockResponse) -> Response<Body> {
+    let status = StatusCode::from_u16(response.status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+    let builder = Response::builder().status(status);
+
+    match response.body {
+        MockBody::Json(value) => builder
+            .header("content-type", "application/json")
+            .body(Body::from(value.to_string())),
+        MockBody::Text(text) => builder
+            .header("content-type", "text/plain")
+            .body(Body::from(text)),
+        MockBody::Sse(events) => {
+            let (mut sender, body) = Body::channel();
+            let chunk_delay = response.chunk_delay;
+            tokio::spawn(async move {
+                for event in events {
+                    if !chunk_delay.is_zero() {
+                        tokio::time::sleep(chunk_delay).await;
+                    }
+                    if sender.send_data(Bytes::from(format!("data: {}\n\n", event))).await.is_err() {
+                        break; // Client went away
+                    }
+                }
+            });
+            builder
+                .header("content-type", "text/event-stream")
+                .body(body)
+        }
+    }
+    .expect("mock server: invalid response")
+}
+
+fn encrypt_envelope(key: &str, value: &serde_json::Value) -> serde_json::Value {
+    let messenger = SecureMessenger::new(key).expect("mock server: invalid encryption key");
+    let data = messenger.encrypt_json(value).expect("mock server: encryption failed");
+    serde_json::json!({ "data": data })
+}
+
+/// Text of the last user message in an Anthropic/OpenAI style `messages` array
+fn last_user_text(request: &serde_json::Value) -> String {
+    request["messages"]
+        .as_array()
+        .and_then(|messages| messages.iter().rev().find(|m| m["role"] == "user"))
+        .map(|m| match &m["content"] {
+            serde_json::Value::String(text) => text.clone(),
+            // Content blocks: join the text parts
+            serde_json::Value::Array(blocks) => blocks.iter()
+                .filter_map(|b| b["text"].as_str())
+                .collect::<Vec<_>>()
+                .join("\n"),
+            _ => String::new(),
+        })
+        .unwrap_or_default()
+}
+
+fn bridge_reply(request: &serde_json::Value) -> serde_json::Value {
+    let prompt = request["prompt"].as_str().unwrap_or_default();
+    serde_json::json!({
+        "response": format!("Mock reply to: {}", prompt),
+        "provider": request["provider"].as_str().unwrap_or("anthropic"),
+        "model": request["model"].as_str().unwrap_or("mock-model"),
+        "conversation_id": request["conversation_id"].as_str().unwrap_or("mock-conversation"),
+        "request_id": request["request_id"],
+    })
+}
+
+/// Streamed bridge answer: one delta per word, then the full reply
+fn bridge_stream(request: &serde_json::Value) -> Vec<serde_json::Value> {
+    let reply = bridge_reply(request);
+    let mut chunks: Vec<serde_json::Value> = reply["response"].as_str().unwrap_or_default()
+        .split_inclusive(' ')
+        .map(|word| serde_json::json!({"type": "delta", "text": word}))
+        .collect();
+    let mut done = reply;
+    done["type"] = "done".into();
+    chunks.push(done);
+    chunks
+}
+
+/// Upstream providers the mock bridge advertises
+fn bridge_providers() -> serde_json::Value {
+    serde_json::json!([
+        {
+            "name": "anthropic",
+            "models": ["claude-3-5-sonnet-20241022", "claude-3-5-haiku-20241022"],
+            "default_model": "claude-3-5-sonnet-20241022",
+            "max_tokens": 8192
+        },
+        {"name": "openai", "models": ["gpt-4o", "gpt-4o-mini"], "default_model": "gpt-4o"}
+    ])
+}
+
+/// Conversation management: one known conversation, "mock-conversation";
+/// `action` is a `ConversationAction` as sent to the secure endpoint
+fn conversation_reply(action: &serde_json::Value) -> MockResponse {
+    let conversation_id = action["conversation_id"].as_str().unwrap_or_default();
+    match action["action"].as_str() {
+        Some("list") => MockResponse::json(serde_json::json!({"conversations": [{
+            "id": "mock-conversation",
+            "title": "Mock chat",
+            "created_at": "2025-01-01T00:00:00Z",
+            "updated_at": "2025-01-01T00:01:00Z",
+            "message_count": 2,
+            "provider": "anthropic"
+        }]})),
+        Some(_) if conversation_id != "mock-conversation" => {
+            MockResponse::error(404, serde_json::json!({"detail": "Conversation not found"}))
+        }
+        Some("messages") => MockResponse::json(serde_json::json!({
+            "conversation_id": conversation_id,
+            "messages": [
+                {"role": "user", "content": "Hello", "created_at": "2025-01-01T00:00:00Z"},
+                {"role": "assistant", "content": "Mock reply to: Hello", "created_at": "2025-01-01T00:01:00Z"}
+            ]
+        })),
+        Some(action) => MockResponse::json(serde_json::json!({"status": "ok", "action": action})),
+        None => MockResponse::error(400, serde_json::json!({"detail": "Unknown action"})),
+    }
+}
+
+/// Bridge job endpoints, which keep state between requests. A job runs until
+/// its first plain poll and is done on the next; a long poll (`wait`) finishes
+/// it at once. `None` for other paths.
+fn bridge_job(
+    path: &str,
+    query: Option<&str>,
+    request: &serde_json::Value,
+    encryption_key: Option<&str>,
+    jobs: &mut HashMap<String, (serde_json::Value, u32)>,
+) -> Option<MockResponse> {
+    let decrypt = || {
+        let key = encryption_key?;
+        SecureMessenger::new(key).ok()?.decrypt_json::<serde_json::Value>(request["data"].as_str()?).ok()
+    };
+    let (job_id, wait) = match path {
+        BRIDGE_JOBS_PATH | BRIDGE_SECURE_JOBS_PATH => {
+            let query = if path == BRIDGE_JOBS_PATH { Some(request.clone()) } else { decrypt() };
+            let Some(query) = query else {
+                return Some(MockResponse::error(400, serde_json::json!({"detail": "Decryption failed"})));
+            };
+            let job_id = query["request_id"].as_str().unwrap_or("mock-job").to_string();
+            jobs.insert(job_id.clone(), (query, 0));
+            let queued = MockResponse::json(serde_json::json!({"job_id": job_id, "status": "queued"}));
+            return Some(MockResponse { status: 202, ..queued });
+        }
+        BRIDGE_JOB_CANCEL_PATH => {
+            let job_id = request["job_id"].as_str().unwrap_or_default();
+            return Some(match jobs.remove(job_id) {
+                Some(_) => MockResponse::json(serde_json::json!({"job_id": job_id, "status": "cancelled"})),
+                None => MockResponse::error(404, serde_json::json!({"detail": "Job not found"})),
+            });
+        }
+        BRIDGE_SECURE_JOB_STATUS_PATH => match decrypt() {
+            Some(poll) => (poll["job_id"].as_str().unwrap_or_default().to_string(), poll["wait"].is_u64()),
+            None => return Some(MockResponse::error(400, serde_json::json!({"detail": "Decryption failed"}))),
+        },
+        path if path.starts_with("/jobs/") => {
+            let wait = query.is_some_and(|query| query.split('&').any(|pair| pair.starts_with("wait=")));
+            (path["/jobs/".len()..].to_string(), wait)
+        }
+        _ => return None,
+    };
+
+    let Some((query, polls)) = jobs.get_mut(&job_id) else {
+        return Some(MockResponse::error(404, serde_json::json!({"detail": "Job not found"})));
+    };
+    *polls += 1;
+    Some(if wait || *polls > 1 {
+        MockResponse::json(serde_json::json!({"job_id": job_id, "status": "done", "result": bridge_reply(query)}))
+    } else {
+        MockResponse::json(serde_json::json!({"job_id": job_id, "status": "running"}))
+    })
+}
+
+/// One vector per input: `[position in the batch, input length]`
+fn mock_embeddings(request: &serde_json::Value) -> Vec<serde_json::Value> {
+    request["input"]
+        .as_array()
+        .map(|inputs| inputs.iter()
+            .enumerate()
+            .map(|(i, text)| serde_json::json!([i as f32, text.as_str().unwrap_or_default().len() as f32]))
+            .collect())
+        .unwrap_or_default()
+}
+
+/// Responses API answer; the id tells which response it follows, if any
+fn responses_reply(request: &serde_json::Value) -> serde_json::Value {
+    let id = match request["previous_response_id"].as_str() {
+        Some(previous) => format!("{}_next", previous),
+        None => "resp_mock".to_string(),
+    };
+    serde_json::json!({
+        "id": id,
+        "object": "response",
+        "status": "completed",
+        "model": request["model"],
+        "output": [{
+            "type": "message",
+            "role": "assistant",
+            "content": [{
+                "type": "output_text",
+                "text": format!("Mock reply to: {}", request["input"].as_str().unwrap_or_default())
+            }]
+        }],
+        "usage": {"input_tokens": 10, "output_tokens": 5}
+    })
+}
+
+/// What each endpoint answers when nothing is scripted
+fn default_response(path: &str, request: &serde_json::Value, encryption_key: Option<&str>) -> MockResponse {
+    match path {
+        ANTHROPIC_MESSAGES_PATH => MockResponse::json(serde_json::json!({
+            "id": "msg_mock",
+            "type": "message",
+            "role": "assistant",
+            "model": request["model"],
+            "content": [
+                {"type": "text", "text": format!("Mock reply to: {}", last_user_text(request))}
+            ],
+            "stop_reason": "end_turn",
+            "usage": {"input_tokens": 10, "output_tokens": 5}
+        })),
+        OPENAI_CHAT_PATH => MockResponse::json(serde_json::json!({
+            "id": "chatcmpl-mock",
+            "object": "chat.completion",
+            "model": request["model"],
+            "choices": [{
+                "index": 0,
+                "message": {"role": "assistant", "content": format!("Mock reply to: {}", last_user_text(request))},
+                "finish_reason": "stop"
+            }],
+            "usage": {"prompt_tokens": 10, "completion_tokens": 5, "total_tokens": 15}
+        })),
+        OPENAI_RESPONSES_PATH => {
+            let response = responses_reply(request);
+            if request["stream"] == true {
+                // One delta per word, then the finished response
+                let text = format!("Mock reply to: {}", request["input"].as_str().unwrap_or_default());
+                let mut events: Vec<String> = text.split_inclusive(' ')
+                    .map(|word| serde_json::json!({"type": "response.output_text.delta", "delta": word}).to_string())
+                    .collect();
+                events.push(serde_json::json!({"type": "response.completed", "response": response}).to_string());
+                MockResponse::sse(events)
+            } else {
+                MockResponse::json(response)
+            }
+        }
+        OPENAI_EMBEDDINGS_PATH => {
+            let embeddings = mock_embeddings(request);
+            let tokens = embeddings.len() * 3;
+            let data: Vec<serde_json::Value> = embeddings.into_iter()
+                .enumerate()
+                .map(|(i, embedding)| serde_json::json!({"object": "embedding", "index": i, "embedding": embedding}))
+                .collect();
+            MockResponse::json(serde_json::json!({
+                "object": "list",
+                "data": data,
+                "model": request["model"],
+                "usage": {"prompt_tokens": tokens, "total_tokens": tokens}
+            }))
+        }
+        OLLAMA_EMBED_PATH => {
+            let embeddings = mock_embeddings(request);
+            MockResponse::json(serde_json::json!({
+                "model": request["model"],
+                "prompt_eval_count": embeddings.len() * 10,
+                "embeddings": embeddings
+            }))
+        }
+        BRIDGE_QUERY_PATH if request["stream"] == true => {
+            MockResponse::sse(bridge_stream(request).iter().map(|chunk| chunk.to_string()))
+        }
+        BRIDGE_QUERY_PATH => MockResponse::json(bridge_reply(request)),
+        BRIDGE_SECURE_QUERY_PATH => {
+            let Some(key) = encryption_key else {
+                return MockResponse::error(500, serde_json::json!({"detail": "mock server has no encryption key"}));
+            };
+            let decrypted = request["data"]
+                .as_str()
+                .and_then(|data| SecureMessenger::new(key).ok()?.decrypt_json::<serde_json::Value>(data).ok());
+            match decrypted {
+                // Streams encrypt every event on their own
+                Some(payload) if payload["stream"] == true => MockResponse::sse(
+                    bridge_stream(&payload).iter().map(|chunk| encrypt_envelope(key, chunk).to_string()),
+                ),
+                // Encrypted by `handle` like any other JSON answer of this endpoint
+                Some(payload) => MockResponse::json(bridge_reply(&payload)),
+                None => MockResponse::error(400, serde_json::json!({"detail": "Decryption failed"})),
+            }
+        }
+        BRIDGE_PROVIDERS_PATH => MockResponse::json(serde_json::json!({"providers": bridge_providers()})),
+        BRIDGE_CAPABILITIES_PATH => MockResponse::json(serde_json::json!({
+            "protocol_version": crate::api::BRIDGE_PROTOCOL_VERSION,
+            "server_version": "mock",
+            "providers": bridge_providers(),
+            "encryption": if encryption_key.is_some() { "optional" } else { "unsupported" },
+            "streaming": true,
+            "jobs": true
+        })),
+        BRIDGE_HEALTH_PATH => MockResponse::json(serde_json::json!({"status": "ok", "version": "mock"})),
+        // Echoes the probe; `handle` encrypts it again
+        BRIDGE_SECURE_HEALTH_PATH => {
+            let decrypted = encryption_key.and_then(|key| {
+                SecureMessenger::new(key).ok()?.decrypt_json::<serde_json::Value>(request["data"].as_str()?).ok()
+            });
+            match decrypted {
+                Some(probe) => MockResponse::json(probe),
+                None => MockResponse::error(400, serde_json::json!({"detail": "Decryption failed"})),
+            }
+        }
+        BRIDGE_CONVERSATIONS_PATH => conversation_reply(&serde_json::json!({"action": "list"})),
+        BRIDGE_SECURE_CONVERSATIONS_PATH => {
+            let decrypted = encryption_key.and_then(|key| {
+                SecureMessenger::new(key).ok()?.decrypt_json::<serde_json::Value>(request["data"].as_str()?).ok()
+            });
+            match decrypted {
+                // Encrypted by `handle` when successful
+                Some(action) => conversation_reply(&action),
+                None => MockResponse::error(400, serde_json::json!({"detail": "Decryption failed"})),
+            }
+        }
+        path if path.starts_with("/conversations/") => {
+            // DELETE and PATCH both go to /conversations/{id}; the answer is the same
+            let rest = &path["/conversations/".len()..];
+            let (conversation_id, action) = match rest.split_once('/') {
+                Some((id, action @ ("messages" | "reset"))) => (id, action),
+                _ => (rest, "update"),
+            };
+            conversation_reply(&serde_json::json!({"action": action, "conversation_id": conversation_id}))
+        }
+        BRIDGE_CANCEL_PATH => MockResponse::json(serde_json::json!({
+            "status": "cancelled",
+            "request_id": request["request_id"]
+        })),
+        _ => MockResponse::error(404, serde_json::json!({"detail": "Not Found"})),
+    }
+}
@@ -0,0 +1,232 @@
+// OpenAI Responses API
+// Client for `/v1/responses`, which offers built-in tools, reasoning summaries
+// and conversations stored server-side. The id of every answer is returned as
+// `SearchResult.conversation_id`; pass it back with `with_previous_response_id`
+// to continue the conversation, just like the bridge's conversation ids.
+
+use std::collections::VecDeque;
+use std::error::Error;
+
+use crate::api::{ApiClient, ApiError, SearchResult, TokenUsage, OPENAI_BASE_URL, OPENAI_DEFAULT_MODEL};
+use crate::sse::{SseEvent, SseParser};
+use crate::wire::openai::{self, ReasoningEffort};
+use crate::wire::responses::{self, StreamEvent};
+use crate::wire;
+
+pub struct OpenAIResponsesClient {
+    api_key: String,
+    base_url: String,
+    model: String,
+    system_prompt: Option<String>,
+    previous_response_id: Option<String>,
+    reasoning_effort: Option<ReasoningEffort>,
+    reasoning_summary: Option<String>,
+    tools: Vec<serde_json::Value>,
+    max_output_tokens: Option<u32>,
+}
+
+impl OpenAIResponsesClient {
+    pub fn new(api_key: String) -> Self {
+        Self {
+            api_key,
+            base_url: OPENAI_BASE_URL.to_string(),
+            model: OPENAI_DEFAULT_MODEL.to_string(),
+            system_prompt: None,
+            previous_response_id: None,
+            reasoning_effort: None,
+            reasoning_summary: None,
+            tools: Vec::new(),
+            max_output_tokens: None,
+        }
+    }
+
+    /// Send requests to another server instead of `https://api.openai.com/v1`.
+    /// The `/responses` path is appended.
+    pub fn with_base_url(mut self, base_url: String) -> Self {
+        self.base_url = base_url;
+        self
+    }
+
+    pub fn with_model(mut self, model: String) -> Self {
+        self.model = model;
+        self
+    }
+
+    /// Sent as `instructions`; unlike a system message it is not carried over
+    /// to follow-up responses, so it is sent with every request
+    pub fn with_system_prompt(mut self, system_prompt: String) -> Self {
+        self.system_prompt = Some(system_prompt);
+        self
+    }
+
+    /// Continue the conversation that produced this response id
+    pub fn with_previous_response_id(mut self, response_id: String) -> Self {
+        self.previous_response_id = Some(response_id);
+        self
+    }
+
+    pub fn with_reasoning_effort(mut self, effort: ReasoningEffort) -> Self {
+        self.reasoning_effort = Some(effort);
+        self
+    }
+
+    /// Ask for a reasoning summary ("auto", "concise" or "detailed"),
+    /// returned in `SearchResult.thinking`
+    pub fn with_reasoning_summary(mut self, summary: impl Into<String>) -> Self {
+        self.reasoning_summary = Some(summary.into());
+        self
+    }
+
+    /// Tools as the API describes them, e.g. `json!({"type": "web_search_preview"})`
+    pub fn with_tools(mut self, tools: Vec<serde_json::Value>) -> Self {
+        self.tools = tools;
+        self
+    }
+
+    pub fn with_max_output_tokens(mut self, max_output_tokens: u32) -> Self {
+        self.max_output_tokens = Some(max_output_tokens);
+        self
+    }
+
+    fn request_body(&self, query: &str, stream: bool) -> responses::ResponsesRequest {
+        let reasoning = (self.reasoning_effort.is_some() || self.reasoning_summary.is_some())
+            .then(|| responses::ReasoningConfig {
+                effort: self.reasoning_effort,
+                summary: self.reasoning_summary.clone(),
+            });
+
+        responses::ResponsesRequest {
+            model: self.model.clone(),
+            input: query.to_string(),
+            instructions: self.system_prompt.clone(),
+            previous_response_id: self.previous_response_id.clone(),
+            reasoning,
+            tools: self.tools.clone(),
+            max_output_tokens: self.max_output_tokens,
+            stream,
+        }
+    }
+
+    /// POST to `/responses`; non-2xx statuses become `ApiError::Http`
+    async fn post(&self, body: &responses::ResponsesRequest) -> Result<reqwest::Response, Box<dyn Error + Send + Sync>> {
+        if self.api_key.is_empty() {
+            return Err("OpenAI API key is missing".into());
+        }
+
+        let client = reqwest::Client::new();
+        let response = client
+            .post(format!("{}/responses", self.base_url.trim_end_matches('/')))
+            .header("Authorization", format!("Bearer {}", self.api_key))
+            .header("Content-Type", "application/json")
+            .json(body)
+            .send()
+            .await?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let text = response.text().await?;
+            let error = match wire::decode::<openai::ErrorResponse>("OpenAI", &text) {
+                Ok(body) => ApiError::http("OpenAI", status, body.error.kind, body.error.message),
+                Err(_) => ApiError::http("OpenAI", status, None, text),
+            };
+            return Err(error.into());
+        }
+
+        Ok(response)
+    }
+
+    /// Stream the answer as it is generated
+    pub async fn search_stream(&self, query: &str) -> Result<ResponseStream, Box<dyn Error + Send + Sync>> {
+        let response = self.post(&self.request_body(query, true)).await?;
+        Ok(ResponseStream {
+            response,
+            parser: SseParser::new(),
+            pending: VecDeque::new(),
+            done: false,
+        })
+    }
+}
+
+fn search_result(response: responses::Response) -> Result<SearchResult, ApiError> {
+    Ok(SearchResult {
+        text: response.answer_text()?,
+        provider: "OpenAI".to_string(),
+        model: response.model.clone(),
+        conversation_id: Some(response.id.clone()),
+        request_id: None,
+        usage: response.usage.as_ref().map(TokenUsage::from_responses),
+        thinking: response.reasoning_summary(),
+        candidates: Vec::new(),
+    })
+}
+
+#[async_trait::async_trait]
+impl ApiClient for OpenAIResponsesClient {
+    async fn search(&self, query: &str) -> Result<SearchResult, Box<dyn Error + Send + Sync>> {
+        let response = self.post(&self.request_body(query, false)).await?;
+        let response: responses::Response = wire::decode("OpenAI", &response.text().await?)?;
+        Ok(search_result(response)?)
+    }
+}
+
+/// What a streamed response produced so far
+#[derive(Debug)]
+pub enum ResponseEvent {
+    /// Next piece of the answer text
+    TextDelta(String),
+    /// Next piece of the reasoning summary
+    ReasoningDelta(String),
+    /// The finished answer; always the last event
+    Completed(Box<SearchResult>),
+}
+
+/// Events of a streamed response, read with `next_event`
+pub struct ResponseStream {
+    response: reqwest::Response,
+    parser: SseParser,
+    pending: VecDeque<SseEvent>,
+    done: bool,
+}
+
+impl ResponseStream {
+    /// Next event, or `None` after `Completed`
+    pub async fn next_event(&mut self) -> Result<Option<ResponseEvent>, Box<dyn Error + Send + Sync>> {
+        loop {
+            if self.done {
+                return Ok(None);
+            }
+            let Some(sse) = self.pending.pop_front() else {
+                match self.response.chunk().await? {
+                    Some(chunk) => self.pending.extend(self.parser.push(&chunk)),
+                    None => {
+                        self.pending.extend(self.parser.finish());
+                        if self.pending.is_empty() {
+                            self.done = true;
+                            return Err("OpenAI stream ended before the response was completed".into());
+                        }
+                    }
+                }
+                continue;
+            };
+            if sse.data == "[DONE]" {
+                continue;
+            }
+
+            match wire::decode::<StreamEvent>("OpenAI", &sse.data)? {
+                StreamEvent::OutputTextDelta { delta } => return Ok(Some(ResponseEvent::TextDelta(delta))),
+                StreamEvent::ReasoningSummaryTextDelta { delta } => return Ok(Some(ResponseEvent::ReasoningDelta(delta))),
+                StreamEvent::Completed { response }
+                | StreamEvent::Incomplete { response }
+                | StreamEvent::Failed { response } => {
+                    self.done = true;
+                    return Ok(Some(ResponseEvent::Completed(Box::new(search_result(response)?))));
+                }
+                StreamEvent::Error { code, message } => {
+                    self.done = true;
+                    return Err(ApiError::Failed { provider: "OpenAI".to_string(), code, message }.into());
+                }
+                StreamEvent::Other => continue,
+            }
+        }
+    }
+}
@@ -0,0 +1,79 @@
+// Server-sent events
+// Incremental parser for `text/event-stream` bodies: feed it chunks as they
+// arrive from the network and take out the events that are complete.
+
+/// One dispatched event; `data` lines are joined with '\n'
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SseEvent {
+    pub event: Option<String>,
+    pub data: String,
+    pub id: Option<String>,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct SseParser {
+    buffer: Vec<u8>, // Bytes of an unfinished line (may end inside a UTF-8 sequence)
+    event: Option<String>,
+    data: Vec<String>,
+    id: Option<String>,
+}
+
+impl SseParser {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a chunk of the body and return the events it completed
+    pub(crate) fn push(&mut self, chunk: &[u8]) -> Vec<SseEvent> {
+        self.buffer.extend_from_slice(chunk);
+
+        let mut events = Vec::new();
+        while let Some(end) = self.buffer.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = self.buffer.drain(..=end).collect();
+            let line = String::from_utf8_lossy(&line);
+            let line = line.trim_end_matches('\n').trim_end_matches('\r');
+
+            if line.is_empty() {
+                if let Some(event) = self.dispatch() {
+                    events.push(event);
+                }
+                continue;
+            }
+            if line.starts_with(':') {
+                continue; // Comment / keep-alive
+            }
+
+            let (field, value) = match line.split_once(':') {
+                Some((field, value)) => (field, value.strip_prefix(' ').unwrap_or(value)),
+                None => (line, ""),
+            };
+            match field {
+                "event" => self.event = Some(value.to_string()),
+                "data" => self.data.push(value.to_string()),
+                "id" => self.id = Some(value.to_string()),
+                _ => {} // "retry" and unknown fields are ignored
+            }
+        }
+        events
+    }
+
+    /// Flush an event not terminated by a blank line when the body ends
+    pub(crate) fn finish(&mut self) -> Option<SseEvent> {
+        if !self.buffer.is_empty() {
+            self.push(b"\n");
+        }
+        self.dispatch()
+    }
+
+    fn dispatch(&mut self) -> Option<SseEvent> {
+        if self.data.is_empty() {
+            self.event = None;
+            return None;
+        }
+        Some(SseEvent {
+            event: self.event.take(),
+            data: std::mem::take(&mut self.data).join("\n"),
+            id: self.id.clone(), // The last event id persists across events
+        })
+    }
+}
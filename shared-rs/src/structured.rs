@@ -0,0 +1,235 @@
+// Structured (JSON) output
+// Ask a provider for JSON matching a JSON Schema, validate it, and optionally
+// retry with the validation errors when the answer does not match.
+
+use serde::de::DeserializeOwned;
+use std::error::Error;
+use thiserror::Error;
+
+use crate::api::{AnthropicClient, OpenAIClient, SearchResult, TokenUsage};
+use crate::wire::{anthropic, openai};
+
+/// Schema the answer must follow, plus how hard to try to get it.
+#[derive(Debug, Clone)]
+pub struct StructuredOutput {
+    /// Identifier sent to the provider (OpenAI schema name / Anthropic tool name)
+    pub name: String,
+    pub schema: serde_json::Value,
+    /// Extra attempts after the first answer fails validation
+    pub max_retries: u32,
+    /// Ask OpenAI for strict schema adherence (schema must meet its strict-mode rules)
+    pub strict: bool,
+}
+
+impl StructuredOutput {
+    pub fn new(name: impl Into<String>, schema: serde_json::Value) -> Self {
+        Self {
+            name: name.into(),
+            schema,
+            max_retries: 0,
+            strict: false,
+        }
+    }
+
+    pub fn with_max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    pub fn with_strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+}
+
+#[derive(Debug)]
+pub struct StructuredResult {
+    /// Validated JSON value
+    pub value: serde_json::Value,
+    /// Raw answer (`text` is the JSON as returned by the provider); `usage`
+    /// covers every attempt
+    pub result: SearchResult,
+    /// Number of requests made, including retries
+    pub attempts: u32,
+}
+
+impl StructuredResult {
+    /// Deserialize the validated value into a concrete type
+    pub fn parse<T: DeserializeOwned>(&self) -> Result<T, serde_json::Error> {
+        serde_json::from_value(self.value.clone())
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum StructuredError {
+    #[error("Invalid JSON Schema: {0}")]
+    InvalidSchema(String),
+    #[error("Response is not valid JSON after {attempts} attempt(s): {message}")]
+    InvalidJson { attempts: u32, message: String },
+    #[error("Response does not match the schema after {attempts} attempt(s): {}", errors.join("; "))]
+    SchemaMismatch { attempts: u32, errors: Vec<String> },
+    #[error("Response did not call the '{tool}' tool after {attempts} attempt(s)")]
+    MissingToolCall { tool: String, attempts: u32 },
+}
+
+/// An answer without the structured output tool call. Anthropic can do this
+/// when extended thinking is on, because the tool cannot be forced then.
+#[derive(Debug, Error)]
+#[error("Anthropic response contains no tool_use block for '{tool}'")]
+struct NoToolUse {
+    tool: String,
+    usage: Option<TokenUsage>,
+}
+
+/// Providers that can be asked for JSON conforming to a schema.
+#[async_trait::async_trait]
+pub trait StructuredClient: Send + Sync {
+    /// Make one request for JSON following `output.schema`; `text` of the
+    /// result holds the JSON document. No validation happens here.
+    async fn request_json(&self, query: &str, output: &StructuredOutput) -> Result<SearchResult, Box<dyn Error + Send + Sync>>;
+
+    /// Request JSON, validate it against the schema and retry on failure.
+    async fn search_structured(&self, query: &str, output: &StructuredOutput) -> Result<StructuredResult, Box<dyn Error + Send + Sync>> {
+        // Anthropic takes the schema as a tool's `input_schema` and OpenAI as a
+        // response format; both only accept an object at the top
+        if output.schema.get("type").and_then(|t| t.as_str()) != Some("object") {
+            return Err(StructuredError::InvalidSchema("the top-level schema must have \"type\": \"object\"".to_string()).into());
+        }
+        let validator = jsonschema::JSONSchema::compile(&output.schema)
+            .map_err(|e| StructuredError::InvalidSchema(e.to_string()))?;
+
+        let mut prompt = query.to_string();
+        let mut attempts = 0;
+        let mut usages = Vec::new();
+        loop {
+            attempts += 1;
+            let rejection = match self.request_json(&prompt, output).await {
+                Ok(mut result) => {
+                    usages.extend(result.usage.clone());
+                    match check_json(&validator, &result.text) {
+                        Ok(value) => {
+                            result.usage = TokenUsage::total(&usages);
+                            return Ok(StructuredResult { value, result, attempts });
+                        }
+                        Err(rejection) => rejection,
+                    }
+                }
+                Err(e) => match e.downcast::<NoToolUse>() {
+                    Ok(missing) => {
+                        usages.extend(missing.usage);
+                        Rejection::MissingToolCall(missing.tool)
+                    }
+                    Err(e) => return Err(e),
+                },
+            };
+
+            if attempts > output.max_retries {
+                return Err(match rejection {
+                    Rejection::InvalidJson(message) => StructuredError::InvalidJson { attempts, message },
+                    Rejection::SchemaMismatch(errors) => StructuredError::SchemaMismatch { attempts, errors },
+                    Rejection::MissingToolCall(tool) => StructuredError::MissingToolCall { tool, attempts },
+                }.into());
+            }
+
+            // Retry with the original query plus what was wrong with the last answer
+            let feedback = match rejection {
+                Rejection::InvalidJson(message) => format!("it was not valid JSON ({})", message),
+                Rejection::SchemaMismatch(errors) => format!("it did not match the schema: {}", errors.join("; ")),
+                Rejection::MissingToolCall(tool) => format!("it did not call the '{}' tool; answer by calling it", tool),
+            };
+            prompt = format!(
+                "{}\n\nYour previous answer was rejected because {}. Reply again with JSON that matches the schema exactly.",
+                query, feedback
+            );
+        }
+    }
+}
+
+/// Why an answer was not accepted
+enum Rejection {
+    InvalidJson(String),
+    SchemaMismatch(Vec<String>),
+    MissingToolCall(String),
+}
+
+/// Parse an answer and validate it against the schema
+fn check_json(validator: &jsonschema::JSONSchema, text: &str) -> Result<serde_json::Value, Rejection> {
+    let value = serde_json::from_str::<serde_json::Value>(text).map_err(|e| Rejection::InvalidJson(e.to_string()))?;
+    let errors: Vec<String> = match validator.validate(&value) {
+        Ok(()) => Vec::new(),
+        Err(errors) => errors.map(|e| format!("{} (at '{}')", e, e.instance_path)).collect(),
+    };
+    if errors.is_empty() {
+        Ok(value)
+    } else {
+        Err(Rejection::SchemaMismatch(errors))
+    }
+}
+
+/// Anthropic has no JSON mode, so force a single tool whose input schema is
+/// the requested schema; the tool input is the structured answer.
+#[async_trait::async_trait]
+impl StructuredClient for AnthropicClient {
+    async fn request_json(&self, query: &str, output: &StructuredOutput) -> Result<SearchResult, Box<dyn Error + Send + Sync>> {
+        let mut body = self.request_body(query);
+        body.tools = vec![anthropic::Tool {
+            name: output.name.clone(),
+            description: "Respond with data matching this schema".to_string(),
+            input_schema: output.schema.clone(),
+        }];
+        // Forcing a specific tool is not allowed together with extended thinking
+        body.tool_choice = Some(if self.thinking_enabled() {
+            anthropic::ToolChoice::Auto
+        } else {
+            anthropic::ToolChoice::Tool { name: output.name.clone() }
+        });
+
+        let response = self.send(&body).await?;
+        response.check_refusal()?;
+        let input = response.tool_use(&output.name)
+            .cloned()
+            .ok_or_else(|| NoToolUse {
+                tool: output.name.clone(),
+                usage: response.usage.as_ref().map(TokenUsage::from_anthropic),
+            })?;
+
+        Ok(SearchResult {
+            text: input.to_string(),
+            provider: "Anthropic".to_string(),
+            model: Some(self.model().to_string()),
+            conversation_id: None,
+            request_id: None,
+            usage: response.usage.as_ref().map(TokenUsage::from_anthropic),
+            thinking: response.thinking(),
+            candidates: Vec::new(),
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl StructuredClient for OpenAIClient {
+    async fn request_json(&self, query: &str, output: &StructuredOutput) -> Result<SearchResult, Box<dyn Error + Send + Sync>> {
+        let mut body = self.request_body(query);
+        body.response_format = Some(openai::ResponseFormat::JsonSchema {
+            json_schema: openai::JsonSchemaFormat {
+                name: output.name.clone(),
+                schema: output.schema.clone(),
+                strict: output.strict,
+            },
+        });
+
+        let response = self.send(&body).await?;
+        let text = response.answer_text()?;
+
+        Ok(SearchResult {
+            text,
+            provider: "OpenAI".to_string(),
+            model: Some(self.model().to_string()),
+            conversation_id: None,
+            request_id: None,
+            usage: response.usage.as_ref().map(TokenUsage::from_openai),
+            thinking: response.reasoning(),
+            candidates: Vec::new(),
+        })
+    }
+}
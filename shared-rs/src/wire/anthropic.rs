@@ -0,0 +1,259 @@
+// Anthropic Messages API (`POST /v1/messages`)
+
+use serde::{Deserialize, Serialize};
+
+use crate::api::ApiError;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MessagesRequest {
+    pub model: String,
+    pub max_tokens: u32,
+    pub messages: Vec<Message>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub system: Option<MessageContent>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tools: Vec<Tool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tool_choice: Option<ToolChoice>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub thinking: Option<ThinkingConfig>,
+}
+
+/// Extended thinking; `budget_tokens` counts towards `max_tokens`
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ThinkingConfig {
+    Enabled { budget_tokens: u32 },
+    Disabled,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Message {
+    pub role: String,
+    pub content: MessageContent,
+}
+
+/// A plain string, or text blocks when some of them carry `cache_control`
+#[derive(Debug, Clone, Serialize)]
+#[serde(untagged)]
+pub enum MessageContent {
+    Text(String),
+    Blocks(Vec<TextBlock>),
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TextBlock {
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+    pub text: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cache_control: Option<CacheControl>,
+}
+
+impl TextBlock {
+    pub fn new(text: String, cache: bool) -> Self {
+        Self {
+            kind: "text",
+            text,
+            cache_control: cache.then(CacheControl::ephemeral),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CacheControl {
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+}
+
+impl CacheControl {
+    pub fn ephemeral() -> Self {
+        Self { kind: "ephemeral" }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Tool {
+    pub name: String,
+    pub description: String,
+    pub input_schema: serde_json::Value,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ToolChoice {
+    Auto,
+    Any,
+    Tool { name: String },
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct MessagesResponse {
+    #[serde(default)]
+    pub id: Option<String>,
+    #[serde(default)]
+    pub model: Option<String>,
+    pub content: Vec<ContentBlock>,
+    #[serde(default)]
+    pub stop_reason: Option<String>,
+    #[serde(default)]
+    pub usage: Option<Usage>,
+}
+
+/// One element of the `content` array of a Messages response
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ContentBlock {
+    Text {
+        text: String,
+    },
+    Thinking {
+        thinking: String,
+        #[serde(default)]
+        signature: String,
+    },
+    /// Thinking encrypted by the safety systems; only meaningful to the API
+    RedactedThinking {
+        data: String,
+    },
+    ToolUse {
+        id: String,
+        name: String,
+        input: serde_json::Value,
+    },
+    /// Block types this client does not know yet
+    #[serde(other)]
+    Unknown,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Usage {
+    pub input_tokens: u64,
+    #[serde(default)]
+    pub output_tokens: u64,
+    #[serde(default)]
+    pub cache_creation_input_tokens: Option<u64>,
+    #[serde(default)]
+    pub cache_read_input_tokens: Option<u64>,
+}
+
+/// Body of a non-2xx response
+#[derive(Debug, Clone, Deserialize)]
+pub struct ErrorResponse {
+    pub error: ErrorDetail,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ErrorDetail {
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub message: String,
+}
+
+impl MessagesResponse {
+    /// All text blocks in order. Blocks are joined without a separator: the API
+    /// splits one answer into several blocks, e.g. around citations.
+    pub fn text(&self) -> String {
+        self.content.iter()
+            .filter_map(|block| match block {
+                ContentBlock::Text { text } => Some(text.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    pub fn thinking(&self) -> Option<String> {
+        let parts: Vec<&str> = self.content.iter()
+            .filter_map(|block| match block {
+                ContentBlock::Thinking { thinking, .. } => Some(thinking.as_str()),
+                _ => None,
+            })
+            .collect();
+        (!parts.is_empty()).then(|| parts.join("\n\n"))
+    }
+
+    pub fn tool_use(&self, name: &str) -> Option<&serde_json::Value> {
+        self.content.iter().find_map(|block| match block {
+            ContentBlock::ToolUse { name: tool, input, .. } if tool == name => Some(input),
+            _ => None,
+        })
+    }
+
+    /// A `refusal` stop reason becomes an error carrying whatever text was produced
+    pub fn check_refusal(&self) -> Result<(), ApiError> {
+        if self.stop_reason.as_deref() == Some("refusal") {
+            return Err(ApiError::Refused {
+                provider: "Anthropic".to_string(),
+                message: Some(self.text()).filter(|t| !t.trim().is_empty()),
+            });
+        }
+        Ok(())
+    }
+
+    /// Answer text; refusals and answers without text become errors
+    pub fn answer_text(&self) -> Result<String, ApiError> {
+        self.check_refusal()?;
+        let text = self.text();
+        if text.trim().is_empty() {
+            return Err(ApiError::EmptyAnswer {
+                provider: "Anthropic".to_string(),
+                stop_reason: self.stop_reason.clone(),
+            });
+        }
+        Ok(text)
+    }
+}
+
+// --- Message Batches (`/v1/messages/batches`) --------------------------------
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BatchCreateRequest {
+    pub requests: Vec<BatchRequestItem>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BatchRequestItem {
+    pub custom_id: String,
+    pub params: MessagesRequest,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct MessageBatch {
+    pub id: String,
+    /// "in_progress", "canceling" or "ended"
+    pub processing_status: String,
+    pub request_counts: BatchRequestCounts,
+    /// JSONL with one result per request, set once the batch has ended
+    #[serde(default)]
+    pub results_url: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct BatchRequestCounts {
+    #[serde(default)]
+    pub processing: u64,
+    #[serde(default)]
+    pub succeeded: u64,
+    #[serde(default)]
+    pub errored: u64,
+    #[serde(default)]
+    pub canceled: u64,
+    #[serde(default)]
+    pub expired: u64,
+}
+
+/// One line of the results file
+#[derive(Debug, Clone, Deserialize)]
+pub struct BatchResultLine {
+    pub custom_id: String,
+    pub result: BatchResult,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BatchResult {
+    Succeeded { message: MessagesResponse },
+    Errored { error: ErrorResponse },
+    Canceled,
+    Expired,
+}
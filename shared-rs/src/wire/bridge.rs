@@ -0,0 +1,263 @@
+// Bridge protocol (`/ai_query`, `/ai_query/secure`, `/cancel_request`, `/providers`, `/capabilities`, `/health`, `/conversations`, `/jobs`)
+
+use serde::{Deserialize, Serialize};
+
+use super::DecodeError;
+
+/// Query payload; sent as-is to `/ai_query`, encrypted inside an envelope to `/ai_query/secure`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueryRequest {
+    pub prompt: String,
+    /// Upstream provider the bridge forwards to ("anthropic", "openai", ...)
+    pub provider: String,
+    /// Upstream model; the bridge picks its default when absent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    pub max_tokens: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    /// Ask for server-sent events instead of a single JSON answer
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub stream: bool,
+    pub chat_mode: bool,
+    pub request_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub conversation_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueryResponse {
+    /// Answer text (older bridges send it as `content`)
+    #[serde(default)]
+    pub response: Option<String>,
+    #[serde(default)]
+    pub content: Option<String>,
+    #[serde(default)]
+    pub conversation_id: Option<String>,
+    #[serde(default)]
+    pub provider: Option<String>,
+    #[serde(default)]
+    pub model: Option<String>,
+    #[serde(default)]
+    pub request_id: Option<String>,
+}
+
+impl QueryResponse {
+    pub fn text(&self) -> Result<String, DecodeError> {
+        self.response.clone()
+            .or_else(|| self.content.clone())
+            .ok_or_else(|| DecodeError::missing_field("bridge", ".", "response"))
+    }
+}
+
+/// One event of a streamed answer. Sent as the SSE `data` as-is, or encrypted
+/// into a `SecureEnvelope` per event on the secure endpoint.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum StreamChunk {
+    /// Next piece of the answer text
+    Delta { text: String },
+    /// The finished answer, with the full text
+    Done(QueryResponse),
+    Error {
+        #[serde(alias = "error")]
+        detail: serde_json::Value,
+    },
+}
+
+/// AES-GCM envelope used by `/ai_query/secure` in both directions
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SecureEnvelope {
+    pub data: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CancelRequest {
+    pub request_id: String,
+}
+
+/// `GET /providers`: upstream providers the bridge can forward to
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProvidersResponse {
+    pub providers: Vec<UpstreamProvider>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpstreamProvider {
+    pub name: String,
+    /// Models accepted for this provider; empty means any
+    #[serde(default)]
+    pub models: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_model: Option<String>,
+    /// Largest `max_tokens` the bridge allows for this provider
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u32>,
+}
+
+/// `GET /capabilities`: what the bridge speaks and offers
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Capabilities {
+    /// Protocol version the bridge speaks
+    pub protocol_version: u32,
+    /// Oldest client protocol version the bridge still accepts
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_protocol_version: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub server_version: Option<String>,
+    #[serde(default)]
+    pub providers: Vec<UpstreamProvider>,
+    #[serde(default)]
+    pub encryption: EncryptionRequirement,
+    #[serde(default)]
+    pub streaming: bool,
+    /// Queries can be submitted as jobs and polled (`/jobs`)
+    #[serde(default)]
+    pub jobs: bool,
+    /// False when the bridge has no `/capabilities` endpoint and the values are assumed
+    #[serde(default)]
+    pub advertised: bool,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EncryptionRequirement {
+    /// Only `/ai_query/secure` is served
+    Required,
+    /// Both the plain and the encrypted endpoint are served
+    #[default]
+    Optional,
+    /// Only the plain endpoint is served
+    Unsupported,
+}
+
+/// `GET /health`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HealthResponse {
+    pub status: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+}
+
+/// `POST /health/secure` payload, encrypted in a `SecureEnvelope` both ways;
+/// the bridge echoes the nonce to prove it decrypted the probe
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HealthProbe {
+    pub nonce: String,
+}
+
+/// Conversation the bridge keeps for chat mode, as listed by `GET /conversations`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConversationSummary {
+    pub id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// ISO 8601 timestamps as sent by the bridge
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_at: Option<String>,
+    #[serde(default)]
+    pub message_count: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provider: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConversationsResponse {
+    pub conversations: Vec<ConversationSummary>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConversationMessage {
+    /// "user" or "assistant"
+    pub role: String,
+    pub content: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<String>,
+}
+
+/// `GET /conversations/{id}/messages`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConversationMessagesResponse {
+    #[serde(default)]
+    pub conversation_id: Option<String>,
+    pub messages: Vec<ConversationMessage>,
+}
+
+/// `PATCH /conversations/{id}`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RenameConversationRequest {
+    pub title: String,
+}
+
+/// Payload of `POST /conversations/secure`, encrypted in a `SecureEnvelope`; the
+/// answer is the same body the plain endpoint returns, encrypted the same way
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum ConversationAction {
+    List,
+    Messages { conversation_id: String },
+    Rename { conversation_id: String, title: String },
+    Reset { conversation_id: String },
+    Delete { conversation_id: String },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobState {
+    Queued,
+    Running,
+    Done,
+    Failed,
+    Cancelled,
+}
+
+impl JobState {
+    pub fn is_finished(self) -> bool {
+        matches!(self, JobState::Done | JobState::Failed | JobState::Cancelled)
+    }
+}
+
+/// Answer to `POST /jobs` (a `QueryRequest`) and `GET /jobs/{id}`; `result`
+/// is set once the job is done, `error` when it failed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobStatus {
+    pub job_id: String,
+    pub status: JobState,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result: Option<QueryResponse>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Payload of `POST /jobs/status/secure`, the encrypted form of `GET /jobs/{id}?wait=`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobPoll {
+    pub job_id: String,
+    /// Seconds the bridge may hold the request open waiting for the job to finish
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wait: Option<u64>,
+}
+
+/// Payload of `POST /jobs/cancel`, by the id `POST /jobs` answered with
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobCancel {
+    pub job_id: String,
+}
+
+/// Body of a non-2xx response (`{"detail": ...}`; `detail` is a list for validation errors)
+#[derive(Debug, Clone, Deserialize)]
+pub struct ErrorResponse {
+    #[serde(alias = "error")]
+    pub detail: serde_json::Value,
+}
+
+impl ErrorResponse {
+    pub fn message(&self) -> String {
+        match &self.detail {
+            serde_json::Value::String(message) => message.clone(),
+            other => other.to_string(),
+        }
+    }
+}
@@ -0,0 +1,53 @@
+// Wire formats
+// Serde models of what goes over HTTP to each upstream: the Anthropic
+// Messages API, OpenAI Chat Completions and Responses, and the bridge protocol.
+// Responses are decoded with the path of the offending field, so a schema
+// change upstream shows up as a precise error instead of an empty answer.
+
+pub mod anthropic;
+pub mod bridge;
+pub mod openai;
+pub mod responses;
+
+use serde::de::DeserializeOwned;
+use thiserror::Error;
+
+/// A body that did not match the expected wire format
+#[derive(Debug, Error)]
+#[error("Unexpected {origin} response format at `{path}`: {message}")]
+pub struct DecodeError {
+    /// Who sent the body ("Anthropic", "OpenAI", "bridge")
+    pub origin: String,
+    /// Path of the offending field, e.g. `choices[0].message`
+    pub path: String,
+    pub message: String,
+}
+
+impl DecodeError {
+    pub(crate) fn missing_field(origin: &str, path: &str, field: &str) -> Self {
+        Self {
+            origin: origin.to_string(),
+            path: path.to_string(),
+            message: format!("missing field `{}`", field),
+        }
+    }
+}
+
+/// Decode a response body, reporting the path of the field that failed
+pub(crate) fn decode<T: DeserializeOwned>(origin: &str, body: &str) -> Result<T, DecodeError> {
+    let deserializer = &mut serde_json::Deserializer::from_str(body);
+    serde_path_to_error::deserialize(deserializer).map_err(|e| DecodeError {
+        origin: origin.to_string(),
+        path: e.path().to_string(),
+        message: e.into_inner().to_string(),
+    })
+}
+
+/// Same as `decode` for a body that was already parsed (e.g. a decrypted payload)
+pub(crate) fn decode_value<T: DeserializeOwned>(origin: &str, value: serde_json::Value) -> Result<T, DecodeError> {
+    serde_path_to_error::deserialize(value).map_err(|e| DecodeError {
+        origin: origin.to_string(),
+        path: e.path().to_string(),
+        message: e.into_inner().to_string(),
+    })
+}
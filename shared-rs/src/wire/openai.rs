@@ -0,0 +1,257 @@
+// OpenAI Chat Completions (`POST /chat/completions`)
+
+use serde::{Deserialize, Serialize};
+
+use crate::api::ApiError;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ChatRequest {
+    pub model: String,
+    pub messages: Vec<ChatMessage>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response_format: Option<ResponseFormat>,
+    /// Reasoning models only
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reasoning_effort: Option<ReasoningEffort>,
+    /// Replaces `max_tokens`, which reasoning models reject; includes reasoning tokens
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_completion_tokens: Option<u32>,
+    /// Number of choices to generate
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub n: Option<u32>,
+}
+
+/// How much a reasoning model thinks before answering
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReasoningEffort {
+    Minimal,
+    Low,
+    Medium,
+    High,
+}
+
+impl std::str::FromStr for ReasoningEffort {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "minimal" => Ok(Self::Minimal),
+            "low" => Ok(Self::Low),
+            "medium" => Ok(Self::Medium),
+            "high" => Ok(Self::High),
+            other => Err(format!("Unknown reasoning effort: {} (expected minimal, low, medium or high)", other)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ChatMessage {
+    pub role: String,
+    pub content: String,
+}
+
+impl ChatMessage {
+    pub fn new(role: &str, content: impl Into<String>) -> Self {
+        Self {
+            role: role.to_string(),
+            content: content.into(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ResponseFormat {
+    Text,
+    JsonObject,
+    JsonSchema { json_schema: JsonSchemaFormat },
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct JsonSchemaFormat {
+    pub name: String,
+    pub schema: serde_json::Value,
+    pub strict: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ChatResponse {
+    #[serde(default)]
+    pub id: Option<String>,
+    #[serde(default)]
+    pub model: Option<String>,
+    pub choices: Vec<Choice>,
+    #[serde(default)]
+    pub usage: Option<Usage>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Choice {
+    #[serde(default)]
+    pub index: u32,
+    pub message: AssistantMessage,
+    #[serde(default)]
+    pub finish_reason: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct AssistantMessage {
+    #[serde(default)]
+    pub content: Option<String>,
+    #[serde(default)]
+    pub refusal: Option<String>,
+    /// Reasoning text; not sent by OpenAI itself, but by compatible servers (DeepSeek, vLLM, ...)
+    #[serde(default)]
+    pub reasoning_content: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Usage {
+    pub prompt_tokens: u64,
+    #[serde(default)]
+    pub completion_tokens: u64,
+    #[serde(default)]
+    pub prompt_tokens_details: Option<PromptTokensDetails>,
+    #[serde(default)]
+    pub completion_tokens_details: Option<CompletionTokensDetails>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct PromptTokensDetails {
+    #[serde(default)]
+    pub cached_tokens: Option<u64>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CompletionTokensDetails {
+    #[serde(default)]
+    pub reasoning_tokens: Option<u64>,
+}
+
+/// Body of a non-2xx response
+#[derive(Debug, Clone, Deserialize)]
+pub struct ErrorResponse {
+    pub error: ErrorDetail,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ErrorDetail {
+    pub message: String,
+    #[serde(default, rename = "type")]
+    pub kind: Option<String>,
+    #[serde(default)]
+    pub code: Option<serde_json::Value>,
+}
+
+impl ChatResponse {
+    pub fn reasoning(&self) -> Option<String> {
+        self.choices.first()
+            .and_then(|c| c.message.reasoning_content.clone())
+            .filter(|text| !text.trim().is_empty())
+    }
+
+    /// Content of the first choice; refusals and empty content become errors
+    pub fn answer_text(&self) -> Result<String, ApiError> {
+        Self::choice_text(self.choices.first())
+    }
+
+    /// Content of every choice that has one; errors only when none does
+    pub fn candidate_texts(&self) -> Result<Vec<String>, ApiError> {
+        let texts: Vec<String> = self.choices.iter()
+            .filter_map(|choice| Self::choice_text(Some(choice)).ok())
+            .collect();
+        if texts.is_empty() {
+            // Report why the first choice has no text
+            return self.answer_text().map(|text| vec![text]);
+        }
+        Ok(texts)
+    }
+
+    fn choice_text(choice: Option<&Choice>) -> Result<String, ApiError> {
+        if let Some(refusal) = choice.and_then(|c| c.message.refusal.as_ref()) {
+            return Err(ApiError::Refused {
+                provider: "OpenAI".to_string(),
+                message: Some(refusal.clone()),
+            });
+        }
+        choice
+            .and_then(|c| c.message.content.clone())
+            .filter(|text| !text.trim().is_empty())
+            .ok_or_else(|| ApiError::EmptyAnswer {
+                provider: "OpenAI".to_string(),
+                stop_reason: choice.and_then(|c| c.finish_reason.clone()),
+            })
+    }
+}
+
+// --- Batch (`/files`, `/batches`) --------------------------------------------
+
+/// One line of the JSONL input file
+#[derive(Debug, Clone, Serialize)]
+pub struct BatchInputLine {
+    pub custom_id: String,
+    pub method: &'static str,
+    pub url: &'static str,
+    pub body: ChatRequest,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct FileObject {
+    pub id: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BatchCreateRequest {
+    pub input_file_id: String,
+    pub endpoint: &'static str,
+    pub completion_window: &'static str,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Batch {
+    pub id: String,
+    /// "validating", "in_progress", "finalizing", "completed", "failed",
+    /// "expired", "cancelling" or "cancelled"
+    pub status: String,
+    #[serde(default)]
+    pub request_counts: Option<BatchRequestCounts>,
+    #[serde(default)]
+    pub output_file_id: Option<String>,
+    #[serde(default)]
+    pub error_file_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct BatchRequestCounts {
+    #[serde(default)]
+    pub total: u64,
+    #[serde(default)]
+    pub completed: u64,
+    #[serde(default)]
+    pub failed: u64,
+}
+
+/// One line of the output or error file
+#[derive(Debug, Clone, Deserialize)]
+pub struct BatchOutputLine {
+    pub custom_id: String,
+    #[serde(default)]
+    pub response: Option<BatchOutputResponse>,
+    #[serde(default)]
+    pub error: Option<BatchOutputError>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct BatchOutputResponse {
+    pub status_code: u16,
+    /// A `ChatResponse` for status 200, an `ErrorResponse` otherwise
+    pub body: serde_json::Value,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct BatchOutputError {
+    #[serde(default)]
+    pub code: Option<String>,
+    pub message: String,
+}
@@ -0,0 +1,222 @@
+// OpenAI Responses API (`POST /responses`)
+
+use serde::{Deserialize, Serialize};
+
+use super::openai::ReasoningEffort;
+use crate::api::ApiError;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ResponsesRequest {
+    pub model: String,
+    pub input: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub instructions: Option<String>,
+    /// Continue the conversation stored server-side under this response id
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub previous_response_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reasoning: Option<ReasoningConfig>,
+    /// Built-in tools (`{"type": "web_search_preview"}`, ...) and function tools
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tools: Vec<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_output_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub stream: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ReasoningConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub effort: Option<ReasoningEffort>,
+    /// "auto", "concise" or "detailed"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Response {
+    pub id: String,
+    #[serde(default)]
+    pub model: Option<String>,
+    /// "completed", "incomplete", "failed", ...
+    #[serde(default)]
+    pub status: Option<String>,
+    pub output: Vec<OutputItem>,
+    #[serde(default)]
+    pub usage: Option<Usage>,
+    #[serde(default)]
+    pub incomplete_details: Option<IncompleteDetails>,
+    #[serde(default)]
+    pub error: Option<ResponseError>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum OutputItem {
+    Message {
+        content: Vec<OutputContent>,
+    },
+    Reasoning {
+        #[serde(default)]
+        summary: Vec<SummaryPart>,
+    },
+    /// Tool calls (web_search_call, function_call, ...) and newer item types
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum OutputContent {
+    OutputText {
+        text: String,
+    },
+    Refusal {
+        refusal: String,
+    },
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SummaryPart {
+    SummaryText {
+        text: String,
+    },
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Usage {
+    pub input_tokens: u64,
+    #[serde(default)]
+    pub output_tokens: u64,
+    #[serde(default)]
+    pub input_tokens_details: Option<InputTokensDetails>,
+    #[serde(default)]
+    pub output_tokens_details: Option<OutputTokensDetails>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct InputTokensDetails {
+    #[serde(default)]
+    pub cached_tokens: Option<u64>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct OutputTokensDetails {
+    #[serde(default)]
+    pub reasoning_tokens: Option<u64>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct IncompleteDetails {
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ResponseError {
+    #[serde(default)]
+    pub code: Option<String>,
+    pub message: String,
+}
+
+/// One `data:` payload of a streamed response
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type")]
+pub enum StreamEvent {
+    #[serde(rename = "response.output_text.delta")]
+    OutputTextDelta { delta: String },
+    #[serde(rename = "response.reasoning_summary_text.delta")]
+    ReasoningSummaryTextDelta { delta: String },
+    #[serde(rename = "response.completed")]
+    Completed { response: Response },
+    #[serde(rename = "response.incomplete")]
+    Incomplete { response: Response },
+    #[serde(rename = "response.failed")]
+    Failed { response: Response },
+    #[serde(rename = "error")]
+    Error {
+        #[serde(default)]
+        code: Option<String>,
+        message: String,
+    },
+    /// Lifecycle and tool events this client does not act on
+    #[serde(other)]
+    Other,
+}
+
+impl Response {
+    pub fn text(&self) -> String {
+        self.output.iter()
+            .filter_map(|item| match item {
+                OutputItem::Message { content } => Some(content),
+                _ => None,
+            })
+            .flatten()
+            .filter_map(|part| match part {
+                OutputContent::OutputText { text } => Some(text.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    pub fn refusal(&self) -> Option<String> {
+        self.output.iter()
+            .filter_map(|item| match item {
+                OutputItem::Message { content } => Some(content),
+                _ => None,
+            })
+            .flatten()
+            .find_map(|part| match part {
+                OutputContent::Refusal { refusal } => Some(refusal.clone()),
+                _ => None,
+            })
+    }
+
+    /// Reasoning summaries, when requested with `reasoning.summary`
+    pub fn reasoning_summary(&self) -> Option<String> {
+        let parts: Vec<&str> = self.output.iter()
+            .filter_map(|item| match item {
+                OutputItem::Reasoning { summary } => Some(summary),
+                _ => None,
+            })
+            .flatten()
+            .filter_map(|part| match part {
+                SummaryPart::SummaryText { text } => Some(text.as_str()),
+                _ => None,
+            })
+            .collect();
+        (!parts.is_empty()).then(|| parts.join("\n\n"))
+    }
+
+    /// Answer text; failures, refusals and answers without text become errors
+    pub fn answer_text(&self) -> Result<String, ApiError> {
+        if let Some(ref error) = self.error {
+            return Err(ApiError::Failed {
+                provider: "OpenAI".to_string(),
+                code: error.code.clone(),
+                message: error.message.clone(),
+            });
+        }
+        if let Some(refusal) = self.refusal() {
+            return Err(ApiError::Refused {
+                provider: "OpenAI".to_string(),
+                message: Some(refusal),
+            });
+        }
+        let text = self.text();
+        if text.trim().is_empty() {
+            return Err(ApiError::EmptyAnswer {
+                provider: "OpenAI".to_string(),
+                stop_reason: self.incomplete_details.as_ref()
+                    .map(|details| details.reason.clone())
+                    .or_else(|| self.status.clone()),
+            });
+        }
+        Ok(text)
+    }
+}
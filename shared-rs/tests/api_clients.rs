@@ -0,0 +1,1110 @@
+// ApiClient implementations against the local mock server
+
+use apiai_shared::mock_server::{
+    MockResponse, MockServer, ANTHROPIC_MESSAGES_PATH, BRIDGE_CANCEL_PATH, BRIDGE_CAPABILITIES_PATH,
+    BRIDGE_HEALTH_PATH, BRIDGE_JOBS_PATH, BRIDGE_JOB_CANCEL_PATH, BRIDGE_PROVIDERS_PATH, BRIDGE_QUERY_PATH, BRIDGE_SECURE_CONVERSATIONS_PATH,
+    BRIDGE_SECURE_HEALTH_PATH, BRIDGE_SECURE_JOBS_PATH, BRIDGE_SECURE_JOB_STATUS_PATH, BRIDGE_SECURE_QUERY_PATH,
+    OPENAI_CHAT_PATH, OPENAI_RESPONSES_PATH,
+};
+use apiai_shared::logging;
+use apiai_shared::{
+    AnthropicClient, ApiClient, ApiError, Capabilities, DecodeError, EncryptionPolicy, EncryptionPolicyError, EncryptionRequirement, HealthFailure, JobProgress,
+    JobState, NonceCache, OpenAIClient, OpenAIResponsesClient, PlainJson, PromptBlock, ReasoningEffort, RequestSigner, ResponseEvent,
+    SecureMessenger, SignatureError, StructuredClient, StructuredError, StructuredOutput, TelegramClient, BRIDGE_PROTOCOL_VERSION,
+};
+use serde_json::json;
+use std::sync::Arc;
+use std::time::Duration;
+
+const ENC_KEY: &str = "83d68210ec84a39e2c3eb82a7a6b7afe488ae5684d45a8546c22b1fc0016cb10";
+
+fn telegram(url: String, encryption_key: Option<&str>) -> TelegramClient {
+    TelegramClient::new(
+        url,
+        "bridge-key".to_string(),
+        encryption_key.map(str::to_string),
+        encryption_key.is_some(),
+        true,
+        None,
+    )
+}
+
+// --- Anthropic ---------------------------------------------------------------
+
+#[tokio::test]
+async fn anthropic_sends_messages_request_and_reads_text() {
+    let server = MockServer::start().await;
+    let client = AnthropicClient::new("sk-ant-test".to_string())
+        .with_base_url(server.url())
+        .with_model("claude-test".to_string())
+        .with_system_prompt("Be brief.".to_string());
+
+    let result = client.search("Hello").await.unwrap();
+
+    assert_eq!(result.text, "Mock reply to: Hello");
+    assert_eq!(result.provider, "Anthropic");
+    assert_eq!(result.model.as_deref(), Some("claude-test"));
+
+    let request = &server.requests_to(ANTHROPIC_MESSAGES_PATH)[0];
+    assert_eq!(request.method, "POST");
+    assert_eq!(request.header("x-api-key"), Some("sk-ant-test"));
+    assert_eq!(request.header("anthropic-version"), Some("2023-06-01"));
+    let body = request.json();
+    assert_eq!(body["model"], "claude-test");
+    assert_eq!(body["system"], "Be brief.");
+    assert_eq!(body["messages"][0], json!({"role": "user", "content": "Hello"}));
+}
+
+#[tokio::test]
+async fn anthropic_reports_api_errors() {
+    let server = MockServer::start().await;
+    server.enqueue(
+        ANTHROPIC_MESSAGES_PATH,
+        MockResponse::error(529, json!({"type": "error", "error": {"type": "overloaded_error", "message": "Overloaded"}})),
+    );
+    let client = AnthropicClient::new("sk-ant-test".to_string()).with_base_url(server.url());
+
+    let err = client.search("Hello").await.unwrap_err();
+
+    assert!(err.to_string().contains("Anthropic API error"), "{}", err);
+    assert!(err.to_string().contains("Overloaded"), "{}", err);
+    match err.downcast_ref::<ApiError>() {
+        Some(ApiError::Http { status, kind, .. }) => {
+            assert_eq!(*status, 529);
+            assert_eq!(kind.as_deref(), Some("overloaded_error"));
+        }
+        other => panic!("expected Http error, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn anthropic_schema_changes_name_the_missing_field() {
+    let server = MockServer::start().await;
+    server.enqueue(ANTHROPIC_MESSAGES_PATH, MockResponse::json(json!({"id": "msg_1", "contents": []})));
+    server.enqueue(ANTHROPIC_MESSAGES_PATH, MockResponse::json(json!({"content": [{"type": "tool_use", "id": "toolu_1", "input": {}}]})));
+    let client = AnthropicClient::new("sk-ant-test".to_string()).with_base_url(server.url());
+
+    let err = client.search("Hello").await.unwrap_err();
+    let decode = err.downcast_ref::<DecodeError>().expect("DecodeError");
+    assert!(decode.message.contains("missing field `content`"), "{}", decode);
+
+    let err = client.search("Hello").await.unwrap_err();
+    let decode = err.downcast_ref::<DecodeError>().expect("DecodeError");
+    assert_eq!(decode.path, "content[0]");
+    assert!(decode.message.contains("missing field `name`"), "{}", decode);
+}
+
+#[tokio::test]
+async fn anthropic_requires_api_key() {
+    let server = MockServer::start().await;
+    let client = AnthropicClient::new(String::new()).with_base_url(server.url());
+
+    assert!(client.search("Hello").await.is_err());
+    assert!(server.requests().is_empty());
+}
+
+#[tokio::test]
+async fn anthropic_joins_text_blocks_and_separates_thinking() {
+    let server = MockServer::start().await;
+    server.enqueue(ANTHROPIC_MESSAGES_PATH, MockResponse::json(json!({
+        "content": [
+            {"type": "thinking", "thinking": "The user wants a fact.", "signature": "sig"},
+            {"type": "redacted_thinking", "data": "opaque"},
+            {"type": "text", "text": "Rust 1.0 was released "},
+            {"type": "text", "text": "in May 2015.", "citations": []},
+            {"type": "tool_use", "id": "toolu_1", "name": "lookup", "input": {}},
+            {"type": "server_tool_use", "id": "srvtoolu_1", "name": "web_search", "input": {}}
+        ],
+        "stop_reason": "end_turn",
+        "usage": {"input_tokens": 10, "output_tokens": 20}
+    })));
+    let client = AnthropicClient::new("sk-ant-test".to_string()).with_base_url(server.url());
+
+    let result = client.search("When was Rust 1.0 released?").await.unwrap();
+
+    assert_eq!(result.text, "Rust 1.0 was released in May 2015.");
+    assert_eq!(result.thinking.as_deref(), Some("The user wants a fact."));
+}
+
+#[tokio::test]
+async fn anthropic_extended_thinking_adds_budget_to_max_tokens() {
+    let server = MockServer::start().await;
+    let client = AnthropicClient::new("sk-ant-test".to_string())
+        .with_base_url(server.url())
+        .with_max_tokens(2000)
+        .with_thinking(4000);
+
+    client.search("Prove it").await.unwrap();
+
+    let body = server.requests_to(ANTHROPIC_MESSAGES_PATH)[0].json();
+    assert_eq!(body["thinking"], json!({"type": "enabled", "budget_tokens": 4000}));
+    assert_eq!(body["max_tokens"], 6000);
+
+    let too_small = AnthropicClient::new("sk-ant-test".to_string())
+        .with_base_url(server.url())
+        .with_thinking(100);
+    let err = too_small.search("Prove it").await.unwrap_err().to_string();
+    assert!(err.contains("at least 1024"), "{}", err);
+    assert_eq!(server.requests().len(), 1);
+}
+
+#[tokio::test]
+async fn anthropic_candidates_run_parallel_requests_and_sum_usage() {
+    let server = MockServer::start().await;
+    server.enqueue(ANTHROPIC_MESSAGES_PATH, MockResponse::error(529, json!({"type": "error", "error": {"type": "overloaded_error", "message": "Overloaded"}})));
+    let client = AnthropicClient::new("sk-ant-test".to_string()).with_base_url(server.url());
+
+    let result = client.search_candidates("Write a slogan", 3).await.unwrap();
+
+    // One request failed; the other two still give candidates
+    assert_eq!(result.candidates.len(), 2);
+    assert_eq!(result.text, result.candidates[0]);
+    assert_eq!(server.requests_to(ANTHROPIC_MESSAGES_PATH).len(), 3);
+    let usage = result.usage.unwrap();
+    assert_eq!((usage.input_tokens, usage.output_tokens), (20, 10));
+}
+
+#[tokio::test]
+async fn anthropic_empty_and_refused_answers_are_errors() {
+    let server = MockServer::start().await;
+    server.enqueue(ANTHROPIC_MESSAGES_PATH, MockResponse::json(json!({
+        "content": [{"type": "thinking", "thinking": "Long deliberation", "signature": "sig"}],
+        "stop_reason": "max_tokens"
+    })));
+    server.enqueue(ANTHROPIC_MESSAGES_PATH, MockResponse::json(json!({
+        "content": [],
+        "stop_reason": "refusal"
+    })));
+    let client = AnthropicClient::new("sk-ant-test".to_string()).with_base_url(server.url());
+
+    let err = client.search("Hello").await.unwrap_err();
+    match err.downcast_ref::<ApiError>() {
+        Some(ApiError::EmptyAnswer { stop_reason, .. }) => assert_eq!(stop_reason.as_deref(), Some("max_tokens")),
+        other => panic!("expected EmptyAnswer, got {:?}", other),
+    }
+
+    let err = client.search("Hello").await.unwrap_err();
+    assert!(matches!(err.downcast_ref::<ApiError>(), Some(ApiError::Refused { .. })), "{}", err);
+}
+
+#[tokio::test]
+async fn anthropic_prompt_caching_marks_breakpoints_and_reports_cache_usage() {
+    let server = MockServer::start().await;
+    server.enqueue(ANTHROPIC_MESSAGES_PATH, MockResponse::json(json!({
+        "model": "claude-test",
+        "content": [{"type": "text", "text": "Summary"}],
+        "usage": {
+            "input_tokens": 12,
+            "output_tokens": 30,
+            "cache_creation_input_tokens": 0,
+            "cache_read_input_tokens": 2048
+        }
+    })));
+    let client = AnthropicClient::new("sk-ant-test".to_string())
+        .with_base_url(server.url())
+        .with_cached_system_prompt("Long instructions".to_string())
+        .with_context(vec![
+            PromptBlock::cached("Long document".to_string()),
+            PromptBlock::new("Short note".to_string()),
+        ]);
+
+    let result = client.search("Summarize").await.unwrap();
+
+    let usage = result.usage.unwrap();
+    assert_eq!(usage.input_tokens, 12);
+    assert_eq!(usage.cache_read_input_tokens, Some(2048));
+    assert_eq!(usage.cache_creation_input_tokens, Some(0));
+
+    let body = server.requests_to(ANTHROPIC_MESSAGES_PATH)[0].json();
+    assert_eq!(body["system"][0]["cache_control"], json!({"type": "ephemeral"}));
+    let content = &body["messages"][0]["content"];
+    assert_eq!(content[0], json!({"type": "text", "text": "Long document", "cache_control": {"type": "ephemeral"}}));
+    assert_eq!(content[1], json!({"type": "text", "text": "Short note"}));
+    assert_eq!(content[2], json!({"type": "text", "text": "Summarize"}));
+}
+
+#[tokio::test]
+async fn anthropic_rejects_too_many_cache_breakpoints() {
+    let server = MockServer::start().await;
+    let blocks = (0..5).map(|i| PromptBlock::cached(format!("doc {}", i))).collect();
+    let client = AnthropicClient::new("sk-ant-test".to_string())
+        .with_base_url(server.url())
+        .with_context(blocks);
+
+    let err = client.search("Hello").await.unwrap_err().to_string();
+
+    assert!(err.contains("cache breakpoints"), "{}", err);
+    assert!(server.requests().is_empty());
+}
+
+#[tokio::test]
+async fn anthropic_structured_output_forces_tool_and_retries() {
+    let server = MockServer::start().await;
+    let tool_answer = |input: serde_json::Value| MockResponse::json(json!({
+        "model": "claude-test",
+        "content": [{"type": "tool_use", "id": "toolu_1", "name": "person", "input": input}],
+        "stop_reason": "tool_use",
+        "usage": {"input_tokens": 10, "output_tokens": 5}
+    }));
+    server.enqueue(ANTHROPIC_MESSAGES_PATH, tool_answer(json!({"name": 42})));
+    server.enqueue(ANTHROPIC_MESSAGES_PATH, tool_answer(json!({"name": "Frank Herbert"})));
+
+    let schema = json!({
+        "type": "object",
+        "properties": {"name": {"type": "string"}},
+        "required": ["name"]
+    });
+    let output = StructuredOutput::new("person", schema).with_max_retries(1);
+    let client = AnthropicClient::new("sk-ant-test".to_string()).with_base_url(server.url());
+
+    let result = client.search_structured("Who wrote Dune?", &output).await.unwrap();
+
+    assert_eq!(result.attempts, 2);
+    assert_eq!(result.value, json!({"name": "Frank Herbert"}));
+    let requests = server.requests_to(ANTHROPIC_MESSAGES_PATH);
+    assert_eq!(requests[0].json()["tool_choice"], json!({"type": "tool", "name": "person"}));
+    let retry_prompt = requests[1].json()["messages"][0]["content"].as_str().unwrap().to_string();
+    assert!(retry_prompt.contains("did not match the schema"), "{}", retry_prompt);
+    // Usage covers both attempts
+    let usage = result.result.usage.unwrap();
+    assert_eq!((usage.input_tokens, usage.output_tokens), (20, 10));
+}
+
+#[tokio::test]
+async fn anthropic_structured_output_with_thinking_reprompts_without_tool_call() {
+    let server = MockServer::start().await;
+    let text_answer = MockResponse::json(json!({
+        "model": "claude-test",
+        "content": [{"type": "text", "text": "Frank Herbert wrote Dune."}],
+        "stop_reason": "end_turn",
+        "usage": {"input_tokens": 10, "output_tokens": 7}
+    }));
+    server.enqueue(ANTHROPIC_MESSAGES_PATH, text_answer.clone());
+    server.enqueue(ANTHROPIC_MESSAGES_PATH, MockResponse::json(json!({
+        "model": "claude-test",
+        "content": [{"type": "tool_use", "id": "toolu_1", "name": "person", "input": {"name": "Frank Herbert"}}],
+        "stop_reason": "tool_use",
+        "usage": {"input_tokens": 12, "output_tokens": 5}
+    })));
+    server.enqueue(ANTHROPIC_MESSAGES_PATH, text_answer);
+
+    let schema = json!({"type": "object", "properties": {"name": {"type": "string"}}, "required": ["name"]});
+    let client = AnthropicClient::new("sk-ant-test".to_string())
+        .with_base_url(server.url())
+        .with_thinking(1024);
+
+    let output = StructuredOutput::new("person", schema.clone()).with_max_retries(1);
+    let result = client.search_structured("Who wrote Dune?", &output).await.unwrap();
+
+    assert_eq!((result.attempts, result.value.clone()), (2, json!({"name": "Frank Herbert"})));
+    let usage = result.result.usage.unwrap();
+    assert_eq!((usage.input_tokens, usage.output_tokens), (22, 12));
+    let requests = server.requests_to(ANTHROPIC_MESSAGES_PATH);
+    assert_eq!(requests[0].json()["tool_choice"], json!({"type": "auto"}));
+    let retry_prompt = requests[1].json()["messages"][0]["content"].as_str().unwrap().to_string();
+    assert!(retry_prompt.contains("did not call the 'person' tool"), "{}", retry_prompt);
+
+    // Without retries left the error names the missing tool call
+    let err = client.search_structured("Who wrote Dune?", &StructuredOutput::new("person", schema)).await.unwrap_err();
+    assert!(
+        matches!(err.downcast_ref::<StructuredError>(), Some(StructuredError::MissingToolCall { attempts: 1, .. })),
+        "{}",
+        err
+    );
+}
+
+#[tokio::test]
+async fn structured_output_rejects_non_object_schemas_before_sending() {
+    let server = MockServer::start().await;
+    let client = AnthropicClient::new("sk-ant-test".to_string()).with_base_url(server.url());
+
+    for schema in [json!({"type": "array", "items": {"type": "string"}}), json!({"properties": {"name": {"type": "string"}}})] {
+        let err = client.search_structured("List authors", &StructuredOutput::new("authors", schema)).await.unwrap_err();
+        assert!(matches!(err.downcast_ref::<StructuredError>(), Some(StructuredError::InvalidSchema(_))), "{}", err);
+    }
+    assert!(server.requests_to(ANTHROPIC_MESSAGES_PATH).is_empty());
+}
+
+// --- OpenAI ------------------------------------------------------------------
+
+#[tokio::test]
+async fn openai_sends_chat_completion_and_reads_content() {
+    let server = MockServer::start().await;
+    let client = OpenAIClient::new("sk-test".to_string())
+        .with_base_url(server.openai_url())
+        .with_system_prompt("Be brief.".to_string());
+
+    let result = client.search("Hello").await.unwrap();
+
+    assert_eq!(result.text, "Mock reply to: Hello");
+    assert_eq!(result.provider, "OpenAI");
+    assert_eq!(result.model.as_deref(), Some("gpt-4o"));
+
+    let request = &server.requests_to(OPENAI_CHAT_PATH)[0];
+    assert_eq!(request.header("authorization"), Some("Bearer sk-test"));
+    let body = request.json();
+    assert_eq!(body["messages"][0], json!({"role": "system", "content": "Be brief."}));
+    assert_eq!(body["messages"][1], json!({"role": "user", "content": "Hello"}));
+}
+
+#[tokio::test]
+async fn openai_reasoning_options_and_reasoning_tokens() {
+    let server = MockServer::start().await;
+    server.enqueue(OPENAI_CHAT_PATH, MockResponse::json(json!({
+        "model": "o3-mini",
+        "choices": [{
+            "index": 0,
+            "message": {"role": "assistant", "content": "42", "reasoning_content": "6 times 7"},
+            "finish_reason": "stop"
+        }],
+        "usage": {
+            "prompt_tokens": 20,
+            "completion_tokens": 300,
+            "completion_tokens_details": {"reasoning_tokens": 256}
+        }
+    })));
+    let client = OpenAIClient::new("sk-test".to_string())
+        .with_base_url(server.openai_url())
+        .with_model("o3-mini".to_string())
+        .with_reasoning_effort(ReasoningEffort::High)
+        .with_max_completion_tokens(4000);
+
+    let result = client.search("6*7?").await.unwrap();
+
+    assert_eq!(result.text, "42");
+    assert_eq!(result.thinking.as_deref(), Some("6 times 7"));
+    assert_eq!(result.usage.unwrap().reasoning_tokens, Some(256));
+    let body = server.requests_to(OPENAI_CHAT_PATH)[0].json();
+    assert_eq!(body["reasoning_effort"], "high");
+    assert_eq!(body["max_completion_tokens"], 4000);
+    assert!(body.get("max_tokens").is_none());
+}
+
+#[tokio::test]
+async fn openai_candidates_use_n_in_one_request() {
+    let server = MockServer::start().await;
+    server.enqueue(OPENAI_CHAT_PATH, MockResponse::json(json!({
+        "choices": [
+            {"index": 0, "message": {"role": "assistant", "content": "Draft A"}, "finish_reason": "stop"},
+            {"index": 1, "message": {"role": "assistant", "content": "Draft B"}, "finish_reason": "stop"},
+            {"index": 2, "message": {"role": "assistant", "content": null, "refusal": "No"}, "finish_reason": "stop"}
+        ],
+        "usage": {"prompt_tokens": 10, "completion_tokens": 30}
+    })));
+    let client = OpenAIClient::new("sk-test".to_string()).with_base_url(server.openai_url());
+
+    let result = client.search_candidates("Write a slogan", 3).await.unwrap();
+
+    assert_eq!(result.candidates, vec!["Draft A", "Draft B"]);
+    assert_eq!(result.text, "Draft A");
+    assert_eq!(server.requests_to(OPENAI_CHAT_PATH).len(), 1);
+    assert_eq!(server.requests_to(OPENAI_CHAT_PATH)[0].json()["n"], 3);
+}
+
+#[tokio::test]
+async fn openai_reports_api_errors() {
+    let server = MockServer::start().await;
+    server.enqueue(
+        OPENAI_CHAT_PATH,
+        MockResponse::error(401, json!({"error": {"message": "Incorrect API key provided", "type": "invalid_request_error"}})),
+    );
+    let client = OpenAIClient::new("sk-wrong".to_string()).with_base_url(server.openai_url());
+
+    let err = client.search("Hello").await.unwrap_err().to_string();
+
+    assert!(err.contains("OpenAI API error"), "{}", err);
+    assert!(err.contains("Incorrect API key"), "{}", err);
+}
+
+#[tokio::test]
+async fn openai_schema_changes_name_the_missing_field() {
+    let server = MockServer::start().await;
+    server.enqueue(OPENAI_CHAT_PATH, MockResponse::json(json!({"choices": [{"index": 0, "delta": {"content": "Hi"}}]})));
+    let client = OpenAIClient::new("sk-test".to_string()).with_base_url(server.openai_url());
+
+    let err = client.search("Hello").await.unwrap_err();
+
+    let decode = err.downcast_ref::<DecodeError>().expect("DecodeError");
+    assert_eq!(decode.path, "choices[0]");
+    assert!(decode.message.contains("missing field `message`"), "{}", decode);
+}
+
+#[tokio::test]
+async fn openai_structured_output_uses_json_schema_response_format() {
+    let server = MockServer::start().await;
+    server.enqueue(OPENAI_CHAT_PATH, MockResponse::json(json!({
+        "model": "gpt-4o",
+        "choices": [{"index": 0, "message": {"role": "assistant", "content": "{\"answer\": 4}"}}]
+    })));
+
+    let schema = json!({"type": "object", "properties": {"answer": {"type": "integer"}}, "required": ["answer"]});
+    let output = StructuredOutput::new("math", schema);
+    let client = OpenAIClient::new("sk-test".to_string()).with_base_url(server.openai_url());
+
+    let result = client.search_structured("2+2?", &output).await.unwrap();
+
+    #[derive(serde::Deserialize)]
+    struct Answer {
+        answer: i64,
+    }
+    assert_eq!(result.parse::<Answer>().unwrap().answer, 4);
+    let body = server.requests_to(OPENAI_CHAT_PATH)[0].json();
+    assert_eq!(body["response_format"]["type"], "json_schema");
+    assert_eq!(body["response_format"]["json_schema"]["name"], "math");
+}
+
+#[tokio::test]
+async fn openai_structured_output_fails_after_retries() {
+    let server = MockServer::start().await;
+    for _ in 0..2 {
+        server.enqueue(OPENAI_CHAT_PATH, MockResponse::json(json!({
+            "choices": [{"index": 0, "message": {"role": "assistant", "content": "not json"}}]
+        })));
+    }
+
+    let output = StructuredOutput::new("math", json!({"type": "object"})).with_max_retries(1);
+    let client = OpenAIClient::new("sk-test".to_string()).with_base_url(server.openai_url());
+
+    let err = client.search_structured("2+2?", &output).await.unwrap_err().to_string();
+
+    assert!(err.contains("not valid JSON after 2 attempt(s)"), "{}", err);
+}
+
+// --- OpenAI Responses --------------------------------------------------------
+
+#[tokio::test]
+async fn responses_chain_conversations_by_response_id() {
+    let server = MockServer::start().await;
+    let client = || {
+        OpenAIResponsesClient::new("sk-test".to_string())
+            .with_base_url(server.openai_url())
+            .with_system_prompt("Be brief.".to_string())
+    };
+
+    let first = client().search("Hello").await.unwrap();
+    assert_eq!(first.text, "Mock reply to: Hello");
+    assert_eq!(first.conversation_id.as_deref(), Some("resp_mock"));
+
+    let second = client()
+        .with_previous_response_id(first.conversation_id.unwrap())
+        .with_reasoning_effort(ReasoningEffort::Low)
+        .with_reasoning_summary("auto")
+        .search("And again")
+        .await
+        .unwrap();
+    assert_eq!(second.conversation_id.as_deref(), Some("resp_mock_next"));
+
+    let requests = server.requests_to(OPENAI_RESPONSES_PATH);
+    assert_eq!(requests[0].header("authorization"), Some("Bearer sk-test"));
+    let body = requests[1].json();
+    assert_eq!(body["input"], "And again");
+    assert_eq!(body["instructions"], "Be brief.");
+    assert_eq!(body["previous_response_id"], "resp_mock");
+    assert_eq!(body["reasoning"], json!({"effort": "low", "summary": "auto"}));
+}
+
+#[tokio::test]
+async fn responses_stream_text_and_reasoning_deltas() {
+    let server = MockServer::start().await;
+    let completed = json!({
+        "id": "resp_1",
+        "model": "o4-mini",
+        "status": "completed",
+        "output": [
+            {"type": "reasoning", "summary": [{"type": "summary_text", "text": "Counting."}]},
+            {"type": "message", "content": [{"type": "output_text", "text": "Two words"}]}
+        ],
+        "usage": {"input_tokens": 5, "output_tokens": 40, "output_tokens_details": {"reasoning_tokens": 32}}
+    });
+    server.enqueue(OPENAI_RESPONSES_PATH, MockResponse::sse([
+        json!({"type": "response.created", "response": {"id": "resp_1"}}).to_string(),
+        json!({"type": "response.reasoning_summary_text.delta", "delta": "Counting."}).to_string(),
+        json!({"type": "response.output_text.delta", "delta": "Two "}).to_string(),
+        json!({"type": "response.output_text.delta", "delta": "words"}).to_string(),
+        json!({"type": "response.completed", "response": completed}).to_string(),
+    ]).with_chunk_delay(Duration::from_millis(5)));
+    let client = OpenAIResponsesClient::new("sk-test".to_string()).with_base_url(server.openai_url());
+
+    let mut stream = client.search_stream("Say two words").await.unwrap();
+    let (mut text, mut reasoning, mut result) = (String::new(), String::new(), None);
+    while let Some(event) = stream.next_event().await.unwrap() {
+        match event {
+            ResponseEvent::TextDelta(delta) => text.push_str(&delta),
+            ResponseEvent::ReasoningDelta(delta) => reasoning.push_str(&delta),
+            ResponseEvent::Completed(done) => result = Some(*done),
+        }
+    }
+
+    assert_eq!(text, "Two words");
+    assert_eq!(reasoning, "Counting.");
+    let result = result.unwrap();
+    assert_eq!(result.text, "Two words");
+    assert_eq!(result.thinking.as_deref(), Some("Counting."));
+    assert_eq!(result.conversation_id.as_deref(), Some("resp_1"));
+    assert_eq!(result.usage.unwrap().reasoning_tokens, Some(32));
+    assert_eq!(server.requests_to(OPENAI_RESPONSES_PATH)[0].json()["stream"], true);
+}
+
+// --- Telegram bridge ---------------------------------------------------------
+
+#[tokio::test]
+async fn telegram_plain_query_round_trip() {
+    let server = MockServer::start().await;
+    let client = telegram(server.bridge_url(), None).with_request_id("req-123".to_string());
+
+    let result = client.search("Hello").await.unwrap();
+
+    assert_eq!(result.text, "Mock reply to: Hello");
+    assert_eq!(result.provider, "anthropic");
+    assert_eq!(result.model.as_deref(), Some("mock-model"));
+    assert_eq!(result.conversation_id.as_deref(), Some("mock-conversation"));
+    assert_eq!(result.request_id.as_deref(), Some("req-123"));
+
+    let request = &server.requests_to(BRIDGE_QUERY_PATH)[0];
+    assert_eq!(request.header("x-api-key"), Some("bridge-key"));
+    assert_eq!(request.header("x-app-id"), Some("apiai-v2"));
+    assert_eq!(request.header("x-request-id"), Some("req-123"));
+    let body = request.json();
+    assert_eq!(body["prompt"], "Hello");
+    assert_eq!(body["chat_mode"], true);
+    assert_eq!(body["request_id"], "req-123");
+}
+
+#[tokio::test]
+async fn telegram_sends_upstream_settings_checked_against_advertised_providers() {
+    let server = MockServer::start().await;
+    let client = telegram(server.bridge_url(), None)
+        .with_provider("openai".to_string())
+        .with_model("gpt-4o-mini".to_string())
+        .with_max_tokens(2048)
+        .with_temperature(0.5);
+
+    let providers = client.providers().await.unwrap().unwrap();
+    client.check_upstream(&providers).unwrap();
+    let result = client.search("Hello").await.unwrap();
+    assert_eq!(result.provider, "openai");
+    assert_eq!(result.model.as_deref(), Some("gpt-4o-mini"));
+
+    let body = server.requests_to(BRIDGE_QUERY_PATH)[0].json();
+    assert_eq!(body["provider"], "openai");
+    assert_eq!(body["model"], "gpt-4o-mini");
+    assert_eq!(body["max_tokens"], 2048);
+    assert_eq!(body["temperature"], 0.5);
+
+    let unknown_model = telegram(server.bridge_url(), None).with_model("claude-2".to_string());
+    let err = unknown_model.check_upstream(&providers).unwrap_err().to_string();
+    assert!(err.contains("model 'claude-2'"), "{}", err);
+    let too_long = telegram(server.bridge_url(), None).with_max_tokens(100_000);
+    assert!(too_long.check_upstream(&providers).unwrap_err().to_string().contains("8192"));
+    let unknown_provider = telegram(server.bridge_url(), None).with_provider("mistral".to_string());
+    assert!(unknown_provider.check_upstream(&providers).is_err());
+
+    // Older bridges have no /providers endpoint
+    server.enqueue(BRIDGE_PROVIDERS_PATH, MockResponse::error(404, json!({"detail": "Not Found"})));
+    assert!(client.providers().await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn telegram_capabilities_handshake_adapts_or_refuses() {
+    let server = MockServer::start().await;
+    server.set_encryption_key(ENC_KEY);
+
+    let capabilities = telegram(server.bridge_url(), None).capabilities().await.unwrap();
+    assert!(capabilities.advertised);
+    assert_eq!(capabilities.protocol_version, BRIDGE_PROTOCOL_VERSION);
+    assert_eq!(capabilities.providers.len(), 2);
+    assert_eq!(server.requests_to(BRIDGE_CAPABILITIES_PATH)[0].header("x-protocol-version"), Some("2"));
+
+    // A bridge that requires encryption gets it when a key is configured...
+    let required = Capabilities { encryption: EncryptionRequirement::Required, ..capabilities.clone() };
+    let client = TelegramClient::new(server.bridge_secure_url(), "bridge-key".to_string(), Some(ENC_KEY.to_string()), false, true, None)
+        .with_capabilities(required.clone())
+        .unwrap();
+    client.search("Hello").await.unwrap();
+    assert_eq!(server.requests_to(BRIDGE_SECURE_QUERY_PATH).len(), 1);
+    // ...and is refused without one
+    let err = telegram(server.bridge_url(), None).with_capabilities(required).err().unwrap();
+    assert!(matches!(err.downcast_ref::<ApiError>(), Some(ApiError::Incompatible { .. })), "{}", err);
+    // A bridge without encryption is a policy refusal when encryption is required
+    let unsupported = Capabilities { encryption: EncryptionRequirement::Unsupported, ..capabilities.clone() };
+    let err = telegram(server.bridge_url(), Some(ENC_KEY))
+        .with_encryption_policy(EncryptionPolicy::Require)
+        .with_capabilities(unsupported)
+        .err()
+        .unwrap();
+    assert_eq!(err.downcast_ref::<EncryptionPolicyError>(), Some(&EncryptionPolicyError::BridgeUnencrypted));
+
+    let newer = Capabilities { min_protocol_version: Some(BRIDGE_PROTOCOL_VERSION + 1), ..capabilities.clone() };
+    let err = telegram(server.bridge_url(), None).with_capabilities(newer).err().unwrap().to_string();
+    assert!(err.contains("update the app"), "{}", err);
+    let older = Capabilities { protocol_version: 1, ..capabilities.clone() };
+    let err = telegram(server.bridge_url(), None).with_capabilities(older).err().unwrap().to_string();
+    assert!(err.contains("update the bridge"), "{}", err);
+
+    // Bridges without the endpoint are assumed to speak the current protocol
+    server.enqueue(BRIDGE_CAPABILITIES_PATH, MockResponse::error(404, json!({"detail": "Not Found"})));
+    let assumed = telegram(server.bridge_url(), None).capabilities().await.unwrap();
+    assert!(!assumed.advertised);
+    assert_eq!(assumed.providers.len(), 2);
+}
+
+#[tokio::test]
+async fn telegram_streams_deltas_per_chunk_encrypted_or_falls_back() {
+    let server = MockServer::start().await;
+    server.set_encryption_key(ENC_KEY);
+    let capabilities = telegram(server.bridge_url(), None).capabilities().await.unwrap();
+    assert!(capabilities.streaming);
+
+    for (url, key) in [(server.bridge_url(), None), (server.bridge_secure_url(), Some(ENC_KEY))] {
+        let client = telegram(url, key).with_capabilities(capabilities.clone()).unwrap();
+        let mut stream = client.search_stream("Stream me").await.unwrap();
+        let mut text = String::new();
+        let mut deltas = 0;
+        let result = loop {
+            match stream.next_event().await.unwrap().expect("stream ended early") {
+                ResponseEvent::TextDelta(delta) => {
+                    deltas += 1;
+                    text.push_str(&delta);
+                }
+                ResponseEvent::ReasoningDelta(_) => {}
+                ResponseEvent::Completed(result) => break *result,
+            }
+        };
+        assert_eq!(deltas, 5);
+        assert_eq!(text, "Mock reply to: Stream me");
+        assert_eq!(result.text, text);
+        assert!(stream.next_event().await.unwrap().is_none());
+    }
+    assert_eq!(server.requests_to(BRIDGE_QUERY_PATH)[0].json()["stream"], true);
+    assert!(server.requests_to(BRIDGE_SECURE_QUERY_PATH)[0].json()["data"].is_string());
+
+    // Without negotiated streaming the answer arrives in one piece
+    let mut stream = telegram(server.bridge_url(), None).search_stream("Whole").await.unwrap();
+    match stream.next_event().await.unwrap() {
+        Some(ResponseEvent::Completed(result)) => assert_eq!(result.text, "Mock reply to: Whole"),
+        other => panic!("expected Completed, got {:?}", other),
+    }
+    assert!(server.requests_to(BRIDGE_QUERY_PATH)[1].json().get("stream").is_none());
+}
+
+#[tokio::test]
+async fn telegram_health_check_reports_latency_or_the_failing_step() {
+    let server = MockServer::start().await;
+    server.set_encryption_key(ENC_KEY);
+
+    let report = telegram(server.bridge_secure_url(), Some(ENC_KEY)).check_health().await;
+    assert!(report.ok, "{:?}", report);
+    assert_eq!(report.server_version.as_deref(), Some("mock"));
+    assert!(report.dns_ms.is_some() && report.latency_ms.is_some() && report.encrypted_latency_ms.is_some());
+    assert_eq!((report.api_key_accepted, report.encryption_key_accepted), (Some(true), Some(true)));
+    assert_eq!(server.requests_to(BRIDGE_HEALTH_PATH)[0].header("x-api-key"), Some("bridge-key"));
+
+    // Without a key only the plain round trip runs
+    let report = telegram(server.bridge_url(), None).check_health().await;
+    assert!(report.ok && report.encrypted_latency_ms.is_none() && report.encryption_key_accepted.is_none());
+    assert_eq!(server.requests_to(BRIDGE_SECURE_HEALTH_PATH).len(), 1);
+
+    let wrong_key = "00".repeat(32);
+    let report = telegram(server.bridge_url(), Some(&wrong_key)).check_health().await;
+    assert_eq!(report.failure, Some(HealthFailure::WrongKey));
+    assert_eq!(report.encryption_key_accepted, Some(false));
+
+    server.enqueue(BRIDGE_HEALTH_PATH, MockResponse::error(401, json!({"detail": "Invalid API key"})));
+    let report = telegram(server.bridge_url(), None).check_health().await;
+    assert_eq!(report.failure, Some(HealthFailure::Auth));
+    assert!(report.message.unwrap().contains("Invalid API key"));
+
+    // Plain HTTP behind an https URL fails the handshake
+    let report = telegram(server.bridge_url().replace("http://", "https://"), None).check_health().await;
+    assert_eq!(report.failure, Some(HealthFailure::Tls), "{:?}", report.message);
+
+    let report = telegram("http://bridge.invalid/ai_query".to_string(), None).check_health().await;
+    assert_eq!(report.failure, Some(HealthFailure::Dns), "{:?}", report.message);
+
+    // Nothing listens once the server is gone
+    let url = server.bridge_url();
+    drop(server);
+    let report = telegram(url, None).check_health().await;
+    assert_eq!(report.failure, Some(HealthFailure::Connection), "{:?}", report.message);
+}
+
+#[tokio::test]
+async fn telegram_signs_requests_and_bridge_rejects_tampering_or_replay() {
+    // Same canonical string and HMAC a bridge in another language computes
+    let signer = RequestSigner::new("signing-secret");
+    let body_hash = "324d441c37e0ad3a896107ea67cc36144fc9e607f09c14d2cb2df67b3b825b63";
+    assert_eq!(
+        signer.signature("POST", "/cancel_request", "1700000000", "abc123", body_hash),
+        "c12cc5d3638b887daf289b7d8ce1c59fd6f46fbcec6996a7001ad2d62bc895ec"
+    );
+    assert_eq!(
+        RequestSigner::new(&"k".repeat(100)).signature("POST", "/cancel_request", "1700000000", "abc123", body_hash),
+        "0edddff450ee5266ef75cde6d5254ffe9a7979ba1f45c083e060f7ae61c3fb03"
+    );
+
+    let server = MockServer::start().await;
+    server.set_encryption_key(ENC_KEY);
+    server.set_request_signer(signer.clone().with_max_skew(Duration::from_secs(30)));
+
+    let client = telegram(server.bridge_secure_url(), Some(ENC_KEY)).with_signer(signer.clone());
+    client.search("Hello").await.unwrap();
+    client.cancel().await.unwrap();
+    assert!(client.check_health().await.ok);
+    let sent = server.requests_to(BRIDGE_SECURE_QUERY_PATH).remove(0);
+    assert_eq!(server.requests_to(BRIDGE_CANCEL_PATH)[0].header("x-signature").map(str::len), Some(64));
+
+    // Unsigned and wrongly signed requests are refused
+    for client in [telegram(server.bridge_url(), None), telegram(server.bridge_url(), None).with_signer(RequestSigner::new("other"))] {
+        let err = client.search("Hello").await.unwrap_err();
+        assert!(matches!(err.downcast_ref::<ApiError>(), Some(ApiError::Http { status: 401, .. })), "{}", err);
+    }
+
+    // A captured request cannot be sent again
+    let mut replay = reqwest::Client::new().post(server.bridge_secure_url()).body(sent.body.clone());
+    for (name, value) in &sent.headers {
+        replay = replay.header(name.as_str(), value.as_str());
+    }
+    assert_eq!(replay.send().await.unwrap().status(), 401);
+
+    let header = |name: &str| sent.header(name);
+    let verify = |path: &str, body: &str| signer.verify("POST", path, body.as_bytes(), header, &mut NonceCache::new());
+    assert_eq!(verify(BRIDGE_SECURE_QUERY_PATH, &sent.body), Ok(()));
+    assert_eq!(verify(BRIDGE_SECURE_QUERY_PATH, "{}"), Err(SignatureError::BodyMismatch));
+    assert_eq!(verify(BRIDGE_QUERY_PATH, &sent.body), Err(SignatureError::BadSignature));
+
+    // Old timestamps pass only within the verifier's skew window
+    let sent_at = sent.header("x-timestamp").unwrap().parse::<u64>().unwrap();
+    let empty_hash = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+    let verify_sent_at = |verifier: &RequestSigner, timestamp: u64| {
+        let timestamp = timestamp.to_string();
+        let signature = signer.signature("GET", "/health", &timestamp, "n1", empty_hash);
+        let header = |name: &str| match name {
+            "X-Timestamp" => Some(timestamp.as_str()),
+            "X-Nonce" => Some("n1"),
+            "X-Content-SHA256" => Some(empty_hash),
+            "X-Signature" => Some(signature.as_str()),
+            _ => None,
+        };
+        verifier.verify("GET", "/health", b"", header, &mut NonceCache::new())
+    };
+    assert!(matches!(verify_sent_at(&signer, sent_at - 600), Err(SignatureError::Skewed { allowed: 300, .. })));
+    assert_eq!(verify_sent_at(&signer, sent_at - 200), Ok(()));
+    let strict = signer.clone().with_max_skew(Duration::from_secs(30));
+    assert!(matches!(verify_sent_at(&strict, sent_at - 60), Err(SignatureError::Skewed { allowed: 30, .. })));
+    assert_eq!(verify_sent_at(&strict, sent_at - 10), Ok(()));
+    let lenient = signer.clone().with_max_skew(Duration::from_secs(3600));
+    assert_eq!(verify_sent_at(&lenient, sent_at - 600), Ok(()));
+}
+
+#[tokio::test]
+async fn telegram_manages_server_conversations_plain_or_encrypted() {
+    let server = MockServer::start().await;
+    server.set_encryption_key(ENC_KEY);
+
+    let client = telegram(server.bridge_url(), None);
+    let conversations = client.list_conversations().await.unwrap();
+    assert_eq!(conversations.len(), 1);
+    assert_eq!((conversations[0].id.as_str(), conversations[0].message_count), ("mock-conversation", 2));
+    let messages = client.conversation_messages("mock-conversation").await.unwrap();
+    assert_eq!(messages.iter().map(|m| m.role.as_str()).collect::<Vec<_>>(), ["user", "assistant"]);
+    client.rename_conversation("mock-conversation", "Renamed").await.unwrap();
+    client.reset_conversation("mock-conversation").await.unwrap();
+    client.delete_conversation("mock-conversation").await.unwrap();
+
+    let calls: Vec<(String, String)> = server.requests().into_iter().map(|r| (r.method, r.path)).collect();
+    let expected = [
+        ("GET", "/conversations"),
+        ("GET", "/conversations/mock-conversation/messages"),
+        ("PATCH", "/conversations/mock-conversation"),
+        ("POST", "/conversations/mock-conversation/reset"),
+        ("DELETE", "/conversations/mock-conversation"),
+    ];
+    assert_eq!(calls, expected.map(|(m, p)| (m.to_string(), p.to_string())));
+    assert_eq!(server.requests()[2].json(), json!({"title": "Renamed"}));
+
+    let err = client.delete_conversation("unknown").await.unwrap_err();
+    assert!(matches!(err.downcast_ref::<ApiError>(), Some(ApiError::Http { status: 404, .. })), "{}", err);
+
+    // With encryption every call is an encrypted action on one endpoint
+    let secure = telegram(server.bridge_secure_url(), Some(ENC_KEY));
+    assert_eq!(secure.list_conversations().await.unwrap().len(), 1);
+    assert_eq!(secure.conversation_messages("mock-conversation").await.unwrap()[1].content, "Mock reply to: Hello");
+    secure.reset_conversation("mock-conversation").await.unwrap();
+    let sent = server.requests_to(BRIDGE_SECURE_CONVERSATIONS_PATH);
+    assert_eq!(sent.len(), 3);
+    assert!(sent.iter().all(|r| !r.body.contains("mock-conversation")));
+    let action: serde_json::Value = SecureMessenger::new(ENC_KEY).unwrap().decrypt_json(sent[2].json()["data"].as_str().unwrap()).unwrap();
+    assert_eq!(action, json!({"action": "reset", "conversation_id": "mock-conversation"}));
+}
+
+#[tokio::test]
+async fn telegram_jobs_are_submitted_then_polled_through_network_blips() {
+    let server = MockServer::start().await;
+    server.set_encryption_key(ENC_KEY);
+
+    let client = telegram(server.bridge_url(), None).with_request_id("job-1".to_string());
+    let job_id = client.submit_job("Long question").await.unwrap();
+    assert_eq!(job_id, "job-1");
+    assert_eq!(server.requests_to(BRIDGE_JOBS_PATH)[0].json()["prompt"], "Long question");
+    assert!(matches!(client.poll_job(&job_id, None).await.unwrap(), JobProgress::Pending(JobState::Running)));
+    match client.poll_job(&job_id, None).await.unwrap() {
+        JobProgress::Done(result) => assert_eq!(result.text, "Mock reply to: Long question"),
+        other => panic!("unexpected {:?}", other),
+    }
+
+    // Long polls pass `wait`; a gateway timeout in between is retried
+    let client = telegram(server.bridge_url(), None).with_request_id("job-2".to_string());
+    client.submit_job("Again").await.unwrap();
+    server.enqueue("/jobs/job-2", MockResponse::error(504, json!({"detail": "Gateway Timeout"})));
+    let result = client.wait_for_job("job-2", Duration::from_secs(20)).await.unwrap();
+    assert_eq!(result.text, "Mock reply to: Again");
+    let polls = server.requests_to("/jobs/job-2");
+    assert_eq!(polls.len(), 2);
+
+    server.enqueue("/jobs/job-2", MockResponse::json(json!({"job_id": "job-2", "status": "failed", "error": "upstream overloaded"})));
+    let err = client.wait_for_job("job-2", Duration::from_secs(20)).await.unwrap_err();
+    assert!(matches!(err.downcast_ref::<ApiError>(), Some(ApiError::Failed { message, .. }) if message == "upstream overloaded"), "{}", err);
+    let err = client.job_status("unknown", None).await.unwrap_err();
+    assert!(matches!(err.downcast_ref::<ApiError>(), Some(ApiError::Http { status: 404, .. })), "{}", err);
+
+    // Encrypted: query and statuses travel in envelopes
+    let secure = telegram(server.bridge_secure_url(), Some(ENC_KEY)).with_request_id("job-3".to_string());
+    let job_id = secure.submit_job("Secret question").await.unwrap();
+    let result = secure.wait_for_job(&job_id, Duration::from_secs(5)).await.unwrap();
+    assert_eq!(result.text, "Mock reply to: Secret question");
+    assert!(!server.requests_to(BRIDGE_SECURE_JOBS_PATH)[0].body.contains("Secret question"));
+    let poll = &server.requests_to(BRIDGE_SECURE_JOB_STATUS_PATH)[0];
+    let poll: serde_json::Value = SecureMessenger::new(ENC_KEY).unwrap().decrypt_json(poll.json()["data"].as_str().unwrap()).unwrap();
+    assert_eq!(poll, json!({"job_id": "job-3", "wait": 5}));
+}
+
+#[tokio::test]
+async fn telegram_jobs_are_cancelled_by_job_id() {
+    let server = MockServer::start().await;
+    server.enqueue(BRIDGE_JOBS_PATH, MockResponse::json(json!({"job_id": "bridge-7", "status": "queued"})));
+    let client = telegram(server.bridge_url(), None).with_request_id("request-7".to_string());
+    let job_id = client.submit_job("Long question").await.unwrap();
+    assert_eq!(job_id, "bridge-7");
+
+    server.enqueue(BRIDGE_JOB_CANCEL_PATH, MockResponse::json(json!({"job_id": "bridge-7", "status": "cancelled"})));
+    client.cancel_job(&job_id).await.unwrap();
+    assert_eq!(server.requests_to(BRIDGE_JOB_CANCEL_PATH)[0].json(), json!({"job_id": "bridge-7"}));
+    assert!(server.requests_to(BRIDGE_CANCEL_PATH).is_empty());
+
+    // The mock's own jobs are dropped; unknown ones are a 404
+    let client = telegram(server.bridge_url(), None).with_request_id("job-8".to_string());
+    let job_id = client.submit_job("Another").await.unwrap();
+    client.cancel_job(&job_id).await.unwrap();
+    let err = client.job_status(&job_id, None).await.unwrap_err();
+    assert!(matches!(err.downcast_ref::<ApiError>(), Some(ApiError::Http { status: 404, .. })), "{}", err);
+    let err = client.cancel_job(&job_id).await.unwrap_err();
+    assert!(matches!(err.downcast_ref::<ApiError>(), Some(ApiError::Http { status: 404, .. })), "{}", err);
+}
+
+#[tokio::test]
+async fn telegram_plain_accepts_content_field_and_plain_text() {
+    let server = MockServer::start().await;
+    server.enqueue(BRIDGE_QUERY_PATH, MockResponse::json(json!({"content": "from content"})));
+    server.enqueue(BRIDGE_QUERY_PATH, MockResponse::text("just text"));
+    let client = telegram(server.bridge_url(), None);
+
+    assert_eq!(client.search("a").await.unwrap().text, "from content");
+    assert_eq!(client.search("b").await.unwrap().text, "just text");
+}
+
+#[tokio::test]
+async fn telegram_json_answers_are_checked_and_other_json_is_passed_through() {
+    let server = MockServer::start().await;
+    let echo = json!({"prompt": "Hello", "max_tokens": 1024});
+    server.enqueue(BRIDGE_QUERY_PATH, MockResponse::json(echo.clone()));
+    server.enqueue(BRIDGE_QUERY_PATH, MockResponse::json(json!({"response": 42})));
+    server.enqueue(BRIDGE_QUERY_PATH, MockResponse::text(""));
+    let client = telegram(server.bridge_url(), None);
+
+    // Endpoints like /echo answer with JSON that is not a query response
+    let echoed: serde_json::Value = serde_json::from_str(&client.search("Hello").await.unwrap().text).unwrap();
+    assert_eq!(echoed, echo);
+
+    let err = client.search("Hello").await.unwrap_err();
+    assert!(err.downcast_ref::<DecodeError>().is_some(), "{}", err);
+
+    let err = client.search("Hello").await.unwrap_err();
+    assert!(matches!(err.downcast_ref::<ApiError>(), Some(ApiError::EmptyAnswer { .. })), "{}", err);
+}
+
+#[tokio::test]
+async fn telegram_plain_reports_server_errors() {
+    let server = MockServer::start().await;
+    server.enqueue(BRIDGE_QUERY_PATH, MockResponse::error(403, json!({"detail": "Invalid API key"})));
+    let client = telegram(server.bridge_url(), None);
+
+    let err = client.search("Hello").await.unwrap_err().to_string();
+
+    assert!(err.contains("403"), "{}", err);
+    assert!(err.contains("Invalid API key"), "{}", err);
+}
+
+#[tokio::test]
+async fn telegram_encrypted_query_round_trip() {
+    let server = MockServer::start().await;
+    server.set_encryption_key(ENC_KEY);
+    let client = telegram(server.bridge_secure_url(), Some(ENC_KEY));
+
+    let result = client.search("Secret question").await.unwrap();
+
+    assert_eq!(result.text, "Mock reply to: Secret question");
+    assert_eq!(result.request_id.as_deref(), Some(client.request_id()));
+
+    // Only the encrypted envelope goes over the wire
+    let request = &server.requests_to(BRIDGE_SECURE_QUERY_PATH)[0];
+    assert!(!request.body.contains("Secret question"));
+    let data = request.json()["data"].as_str().unwrap().to_string();
+    let payload: serde_json::Value = SecureMessenger::new(ENC_KEY).unwrap().decrypt_json(&data).unwrap();
+    assert_eq!(payload["prompt"], "Secret question");
+}
+
+#[tokio::test]
+async fn telegram_encrypted_fails_with_wrong_key() {
+    let server = MockServer::start().await;
+    server.set_encryption_key(ENC_KEY);
+    let client = telegram(server.bridge_secure_url(), Some("another key"));
+
+    let err = client.search("Hello").await.unwrap_err().to_string();
+
+    assert!(err.contains("Decryption failed"), "{}", err);
+}
+
+#[tokio::test]
+async fn telegram_answers_parse_the_same_on_every_transport() {
+    let server = MockServer::start().await;
+    server.set_encryption_key(ENC_KEY);
+    let answer = json!({"content": "from content", "model": "m", "conversation_id": "c1"});
+    server.enqueue(BRIDGE_QUERY_PATH, MockResponse::json(answer.clone()));
+    server.enqueue(BRIDGE_SECURE_QUERY_PATH, MockResponse::json(answer.clone()));
+    server.enqueue(BRIDGE_QUERY_PATH, MockResponse::json(json!({"response": 42})));
+    server.enqueue(BRIDGE_SECURE_QUERY_PATH, MockResponse::json(json!({"response": 42})));
+
+    let plain = telegram(server.bridge_url(), None);
+    let encrypted = telegram(server.bridge_secure_url(), Some(ENC_KEY));
+    let plain_result = plain.search("a").await.unwrap();
+    let encrypted_result = encrypted.search("a").await.unwrap();
+
+    assert_eq!(
+        (plain_result.text, plain_result.model, plain_result.conversation_id),
+        (encrypted_result.text, encrypted_result.model, encrypted_result.conversation_id)
+    );
+    let plain_err = plain.search("b").await.unwrap_err().to_string();
+    let encrypted_err = encrypted.search("b").await.unwrap_err().to_string();
+    assert_eq!(plain_err, encrypted_err);
+
+    // An explicit transport wins over the encryption settings
+    let overridden = telegram(server.bridge_url(), Some(ENC_KEY)).with_transport(Arc::new(PlainJson));
+    assert_eq!(overridden.search("c").await.unwrap().text, "Mock reply to: c");
+    assert_eq!(server.requests_to(BRIDGE_QUERY_PATH).last().unwrap().json()["prompt"], "c");
+}
+
+#[tokio::test]
+async fn telegram_encryption_policy_fails_closed() {
+    let server = MockServer::start().await;
+    server.set_encryption_key(ENC_KEY);
+    let policy_error = |err: Box<dyn std::error::Error + Send + Sync>| err.downcast_ref::<EncryptionPolicyError>().cloned();
+
+    // Require: no key, or a transport that does not encrypt, sends nothing
+    let keyless = telegram(server.bridge_url(), None).with_encryption_policy(EncryptionPolicy::Require);
+    assert_eq!(policy_error(keyless.search("secret").await.unwrap_err()), Some(EncryptionPolicyError::KeyMissing));
+    let security = keyless.transport_security();
+    assert_eq!((security.policy, security.transport, security.encrypted), (EncryptionPolicy::Require, None, false));
+    assert!(security.refused.unwrap().contains("no encryption key"));
+    let plain = telegram(server.bridge_url(), Some(ENC_KEY))
+        .with_encryption_policy(EncryptionPolicy::Require)
+        .with_transport(Arc::new(PlainJson));
+    assert_eq!(policy_error(plain.search("secret").await.unwrap_err()), Some(EncryptionPolicyError::Unencrypted("plain")));
+    assert!(server.requests_to(BRIDGE_QUERY_PATH).is_empty());
+
+    // Require encrypts even with `use_encryption` off; Off never does
+    let required = TelegramClient::new(server.bridge_secure_url(), "bridge-key".to_string(), Some(ENC_KEY.to_string()), false, true, None)
+        .with_encryption_policy(EncryptionPolicy::Require);
+    assert_eq!(required.search("secret").await.unwrap().text, "Mock reply to: secret");
+    assert!(!server.requests_to(BRIDGE_SECURE_QUERY_PATH)[0].body.contains("secret"));
+    let off = telegram(server.bridge_url(), Some(ENC_KEY)).with_encryption_policy(EncryptionPolicy::Off);
+    assert_eq!(off.search("open").await.unwrap().text, "Mock reply to: open");
+    assert_eq!(off.transport_security().transport, Some("plain"));
+
+    // Plain text to a remote http:// server only when allowed; loopback and https are fine
+    let remote = telegram("http://bridge.example:8000/ai_query".to_string(), None);
+    assert_eq!(
+        policy_error(remote.search("secret").await.unwrap_err()),
+        Some(EncryptionPolicyError::PlaintextHttp("bridge.example".to_string()))
+    );
+    assert!(remote.clone().allow_plaintext_http(true).transport_security().refused.is_none());
+    assert!(telegram("https://bridge.example/ai_query".to_string(), None).transport_security().refused.is_none());
+    assert!(telegram("http://[::1]:8000/ai_query".to_string(), None).transport_security().refused.is_none());
+}
+
+#[test]
+fn logged_bodies_are_redacted_unless_enabled() {
+    assert_eq!(logging::body("secret prompt"), "<redacted 13 bytes>");
+    logging::set_log_bodies(true);
+    assert_eq!(logging::body("secret prompt"), "secret prompt");
+    logging::set_log_bodies(false);
+}
+
+#[tokio::test]
+async fn telegram_cancel_posts_request_id() {
+    let server = MockServer::start().await;
+    let client = telegram(server.bridge_url(), None);
+
+    client.cancel().await.unwrap();
+
+    let request = &server.requests_to(BRIDGE_CANCEL_PATH)[0];
+    assert_eq!(request.json()["request_id"], client.request_id());
+    assert_eq!(request.header("x-request-id"), Some(client.request_id()));
+}
+
+#[tokio::test]
+async fn telegram_candidates_get_their_own_request_ids() {
+    let server = MockServer::start().await;
+    let client = TelegramClient::new(server.bridge_url(), "bridge-key".to_string(), None, false, false, None)
+        .with_request_id("req-1".to_string());
+
+    let result = client.search_candidates("Hello", 3).await.unwrap();
+
+    assert_eq!(result.candidates.len(), 3);
+    assert_eq!(result.request_id.as_deref(), Some("req-1"));
+    let requests = server.requests_to(BRIDGE_QUERY_PATH);
+    let mut ids: Vec<String> = requests.iter().map(|r| r.json()["request_id"].as_str().unwrap().to_string()).collect();
+    ids.sort();
+    assert_eq!(ids, ["req-1-1", "req-1-2", "req-1-3"]);
+    assert!(requests.iter().all(|r| r.header("x-request-id") == r.json()["request_id"].as_str()));
+
+    // Cancelling reaches every candidate still in flight
+    for _ in 0..3 {
+        server.enqueue(BRIDGE_QUERY_PATH, MockResponse::json(json!({"response": "late"})).with_delay(Duration::from_secs(5)));
+    }
+    let pending = tokio::spawn({
+        let client = client.clone();
+        async move { client.search_candidates("Hello", 3).await }
+    });
+    while server.requests_to(BRIDGE_QUERY_PATH).len() < 6 {
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    client.cancel().await.unwrap();
+    pending.abort();
+    let cancelled: Vec<serde_json::Value> = server.requests_to(BRIDGE_CANCEL_PATH).iter().map(|r| r.json()["request_id"].clone()).collect();
+    assert_eq!(cancelled, [json!("req-1"), json!("req-1-1"), json!("req-1-2"), json!("req-1-3")]);
+
+    // In chat mode every candidate would add a turn to the same conversation
+    let chat = telegram(server.bridge_url(), None);
+    assert!(chat.search_candidates("Hello", 2).await.is_err());
+}
+
+#[tokio::test]
+async fn delayed_response_can_be_abandoned() {
+    let server = MockServer::start().await;
+    server.enqueue(BRIDGE_QUERY_PATH, MockResponse::json(json!({"response": "late"})).with_delay(Duration::from_secs(5)));
+    let client = telegram(server.bridge_url(), None);
+
+    let outcome = tokio::time::timeout(Duration::from_millis(200), client.search("Hello")).await;
+
+    assert!(outcome.is_err(), "request should still be pending");
+}
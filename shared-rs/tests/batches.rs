@@ -0,0 +1,113 @@
+// BatchClient implementations against the local mock server
+
+use apiai_shared::mock_server::{MockResponse, MockServer, ANTHROPIC_BATCHES_PATH, OPENAI_BATCHES_PATH, OPENAI_FILES_PATH};
+use apiai_shared::{match_results, AnthropicClient, BatchClient, BatchRequest, BatchStatus, OpenAIClient};
+use serde_json::json;
+use std::time::Duration;
+
+fn requests() -> Vec<BatchRequest> {
+    vec![
+        BatchRequest::new("q-1", "First question"),
+        BatchRequest::new("q-2", "Second question"),
+        BatchRequest::new("q-3", "Third question"),
+    ]
+}
+
+#[tokio::test]
+async fn anthropic_batch_is_polled_and_results_follow_request_order() {
+    let server = MockServer::start().await;
+    let batch_path = format!("{}/msgbatch_1", ANTHROPIC_BATCHES_PATH);
+    let results_path = format!("{}/results", batch_path);
+    let counts = |processing: u64, succeeded: u64, errored: u64| {
+        json!({"processing": processing, "succeeded": succeeded, "errored": errored, "canceled": 0, "expired": 0})
+    };
+
+    server.enqueue(ANTHROPIC_BATCHES_PATH, MockResponse::json(json!({
+        "id": "msgbatch_1", "processing_status": "in_progress", "request_counts": counts(2, 0, 0)
+    })));
+    server.enqueue(&batch_path, MockResponse::json(json!({
+        "id": "msgbatch_1", "processing_status": "in_progress", "request_counts": counts(1, 1, 0)
+    })));
+    server.enqueue(&batch_path, MockResponse::json(json!({
+        "id": "msgbatch_1", "processing_status": "ended", "request_counts": counts(0, 1, 1),
+        "results_url": format!("{}{}", server.url(), results_path)
+    })));
+    let lines = [
+        json!({"custom_id": "q-2", "result": {"type": "errored", "error": {"type": "error",
+            "error": {"type": "invalid_request_error", "message": "prompt is too long"}}}}),
+        json!({"custom_id": "q-1", "result": {"type": "succeeded", "message": {
+            "id": "msg_1", "model": "claude-test", "content": [{"type": "text", "text": "One"}],
+            "stop_reason": "end_turn", "usage": {"input_tokens": 4, "output_tokens": 1}}}}),
+    ];
+    server.enqueue(&results_path, MockResponse::text(lines.map(|l| l.to_string()).join("\n")));
+
+    let client = AnthropicClient::new("key".to_string()).with_base_url(server.url()).with_model("claude-test".to_string());
+    let batch = requests();
+    let job = client.create_batch(&batch[..2]).await.unwrap();
+    assert_eq!(job.status, BatchStatus::InProgress);
+
+    let mut seen = Vec::new();
+    let job = client.wait_for_batch(&job.id, Duration::from_millis(10), &mut |job| seen.push(job.status)).await.unwrap();
+    assert_eq!(seen, [BatchStatus::InProgress, BatchStatus::Completed]);
+    assert_eq!((job.total, job.succeeded, job.failed), (2, 1, 1));
+
+    let results = match_results(&batch, client.batch_results(&job).await.unwrap());
+    let ids: Vec<&str> = results.iter().map(|r| r.custom_id.as_str()).collect();
+    assert_eq!(ids, ["q-1", "q-2", "q-3"]);
+    let first = results[0].result.as_ref().unwrap();
+    assert_eq!(first.text, "One");
+    assert_eq!(first.usage.as_ref().unwrap().output_tokens, 1);
+    assert_eq!(results[1].error.as_deref(), Some("invalid_request_error: prompt is too long"));
+    assert!(results[2].error.is_some());
+
+    let created = server.requests_to(ANTHROPIC_BATCHES_PATH)[0].json();
+    assert_eq!(created["requests"][1]["custom_id"], "q-2");
+    assert_eq!(created["requests"][1]["params"]["messages"][0]["content"], "Second question");
+    assert_eq!(created["requests"][1]["params"]["model"], "claude-test");
+
+    let invalid = client.create_batch(&[BatchRequest::new("has space", "x")]).await.unwrap_err();
+    assert!(invalid.to_string().contains("Invalid custom id"), "{}", invalid);
+}
+
+#[tokio::test]
+async fn openai_batch_uploads_jsonl_and_reads_output_and_error_files() {
+    let server = MockServer::start().await;
+    server.enqueue(OPENAI_FILES_PATH, MockResponse::json(json!({"id": "file-in", "object": "file"})));
+    server.enqueue(OPENAI_BATCHES_PATH, MockResponse::json(json!({"id": "batch_1", "status": "validating"})));
+    server.enqueue(&format!("{}/batch_1", OPENAI_BATCHES_PATH), MockResponse::json(json!({
+        "id": "batch_1", "status": "completed",
+        "request_counts": {"total": 3, "completed": 1, "failed": 2},
+        "output_file_id": "file-out", "error_file_id": "file-err"
+    })));
+    let output = [
+        json!({"custom_id": "q-1", "response": {"status_code": 200, "body": {
+            "id": "chatcmpl-1", "model": "gpt-test",
+            "choices": [{"index": 0, "message": {"role": "assistant", "content": "One"}, "finish_reason": "stop"}],
+            "usage": {"prompt_tokens": 3, "completion_tokens": 1}}}, "error": null}),
+        json!({"custom_id": "q-2", "response": {"status_code": 400, "body": {
+            "error": {"message": "bad request", "type": "invalid_request_error"}}}, "error": null}),
+    ];
+    server.enqueue(&format!("{}/file-out/content", OPENAI_FILES_PATH),
+        MockResponse::text(output.map(|l| l.to_string()).join("\n")));
+    server.enqueue(&format!("{}/file-err/content", OPENAI_FILES_PATH), MockResponse::text(
+        json!({"custom_id": "q-3", "response": null, "error": {"code": "batch_expired", "message": "not processed"}}).to_string(),
+    ));
+
+    let client = OpenAIClient::new("key".to_string()).with_base_url(server.openai_url()).with_model("gpt-test".to_string());
+    let batch = requests();
+    let job = client.create_batch(&batch).await.unwrap();
+    assert_eq!((job.id.as_str(), job.status), ("batch_1", BatchStatus::InProgress));
+
+    let upload = &server.requests_to(OPENAI_FILES_PATH)[0];
+    assert!(upload.header("content-type").unwrap().starts_with("multipart/form-data"));
+    assert!(upload.body.contains(r#""custom_id":"q-3","method":"POST","url":"/v1/chat/completions""#), "{}", upload.body);
+    let created = server.requests_to(OPENAI_BATCHES_PATH)[0].json();
+    assert_eq!(created, json!({"input_file_id": "file-in", "endpoint": "/v1/chat/completions", "completion_window": "24h"}));
+
+    let job = client.batch_status("batch_1").await.unwrap();
+    assert!(job.status.is_finished());
+    let results = match_results(&batch, client.batch_results(&job).await.unwrap());
+    assert_eq!(results[0].result.as_ref().unwrap().text, "One");
+    assert_eq!(results[1].error.as_deref(), Some("HTTP 400: bad request"));
+    assert_eq!(results[2].error.as_deref(), Some("batch_expired: not processed"));
+}
@@ -0,0 +1,64 @@
+// Recording provider traffic into a cassette and replaying it without the upstream
+
+use apiai_shared::cassette::{CassetteServer, CASSETTE_MISS_STATUS};
+use apiai_shared::mock_server::MockServer;
+use apiai_shared::{AnthropicClient, ApiClient, TelegramClient};
+use std::path::PathBuf;
+
+const ENC_KEY: &str = "83d68210ec84a39e2c3eb82a7a6b7afe488ae5684d45a8546c22b1fc0016cb10";
+
+fn cassette_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("apiai-cassette-{}-{}.json", name, uuid::Uuid::new_v4()))
+}
+
+#[tokio::test]
+async fn records_redacted_and_replays_without_upstream() {
+    let path = cassette_path("anthropic");
+
+    let recorded = {
+        let upstream = MockServer::start().await;
+        let cassette = CassetteServer::record(&path, &upstream.url()).await.unwrap();
+        let client = AnthropicClient::new("sk-ant-secret".to_string()).with_base_url(cassette.url());
+        client.search("What is Rust?").await.unwrap().text
+    };
+
+    let file = std::fs::read_to_string(&path).unwrap();
+    assert!(!file.contains("sk-ant-secret"), "API key leaked into cassette");
+    assert!(file.contains("[REDACTED]"));
+
+    // Upstream is gone; the same request is answered from the cassette
+    let cassette = CassetteServer::replay(&path).await.unwrap();
+    let client = AnthropicClient::new("another-key".to_string()).with_base_url(cassette.url());
+    assert_eq!(client.search("What is Rust?").await.unwrap().text, recorded);
+
+    // A different prompt has no recording and fails loudly
+    let err = client.search("Something else").await.unwrap_err().to_string();
+    assert!(err.contains(&CASSETTE_MISS_STATUS.to_string()) || err.contains("cassette miss"), "{}", err);
+    assert_eq!(cassette.unmatched(), vec!["POST /v1/messages".to_string()]);
+
+    std::fs::remove_file(&path).ok();
+}
+
+#[tokio::test]
+async fn replays_encrypted_bridge_traffic_in_order() {
+    let path = cassette_path("bridge");
+    let client = |url: String| TelegramClient::new(url, "bridge-key".to_string(), Some(ENC_KEY.to_string()), true, false, None);
+
+    let recorded = {
+        let upstream = MockServer::start().await;
+        upstream.set_encryption_key(ENC_KEY);
+        let cassette = CassetteServer::record(&path, &upstream.url()).await.unwrap();
+        cassette.redact(ENC_KEY);
+        client(format!("{}/ai_query/secure", cassette.url())).search("Hi").await.unwrap().text
+    };
+
+    assert!(!std::fs::read_to_string(&path).unwrap().contains("bridge-key"));
+
+    // Encrypted envelopes differ on every request, so match by method and path
+    let cassette = CassetteServer::replay(&path).await.unwrap();
+    cassette.match_in_order();
+    let replayed = client(format!("{}/ai_query/secure", cassette.url())).search("Hi").await.unwrap();
+    assert_eq!(replayed.text, recorded);
+
+    std::fs::remove_file(&path).ok();
+}
@@ -0,0 +1,56 @@
+// Embedding clients against the local mock server
+
+use apiai_shared::mock_server::{MockResponse, MockServer, OLLAMA_EMBED_PATH, OPENAI_EMBEDDINGS_PATH};
+use apiai_shared::{EmbeddingClient, OllamaEmbeddingClient, OpenAIEmbeddingClient};
+use serde_json::json;
+
+fn inputs(n: usize) -> Vec<String> {
+    (0..n).map(|i| format!("text {}", i)).collect()
+}
+
+#[tokio::test]
+async fn openai_compatible_batches_and_keeps_input_order() {
+    let server = MockServer::start().await;
+    // First batch answers out of order; the second falls back to the default [index, input length]
+    server.enqueue(OPENAI_EMBEDDINGS_PATH, MockResponse::json(json!({
+        "data": [
+            {"index": 1, "embedding": [1.0, 2.0]},
+            {"index": 0, "embedding": [0.0, 1.0]}
+        ],
+        "model": "test-embed",
+        "usage": {"prompt_tokens": 3, "total_tokens": 3}
+    })));
+
+    let client = OpenAIEmbeddingClient::compatible(server.openai_url(), String::new())
+        .with_model("test-embed".to_string())
+        .with_dimensions(2)
+        .with_batch_size(2);
+
+    let texts = vec!["a".to_string(), "bb".to_string(), "ccc".to_string()];
+    let result = client.embed(&texts).await.unwrap();
+
+    assert_eq!(result.embeddings, vec![vec![0.0, 1.0], vec![1.0, 2.0], vec![0.0, 3.0]]);
+    assert_eq!(result.model, "test-embed");
+    let usage = result.usage.unwrap();
+    assert_eq!((usage.prompt_tokens, usage.total_tokens), (6, 6));
+
+    let requests = server.requests_to(OPENAI_EMBEDDINGS_PATH);
+    assert_eq!(requests.len(), 2);
+    assert_eq!(requests[0].json()["dimensions"], 2);
+    assert_eq!(requests[0].json()["input"], json!(["a", "bb"]));
+    assert_eq!(requests[1].json()["input"], json!(["ccc"]));
+}
+
+#[tokio::test]
+async fn ollama_reports_prompt_eval_count_as_usage() {
+    let server = MockServer::start().await;
+
+    let client = OllamaEmbeddingClient::new(Some(server.url())).with_batch_size(3);
+    let result = client.embed(&inputs(4)).await.unwrap();
+
+    assert_eq!(result.embeddings.len(), 4);
+    assert_eq!(result.embeddings[3], vec![0.0, 6.0]);
+    assert_eq!(result.model, "nomic-embed-text");
+    assert_eq!(result.usage.unwrap().prompt_tokens, 40);
+    assert_eq!(server.requests_to(OLLAMA_EMBED_PATH).len(), 2);
+}
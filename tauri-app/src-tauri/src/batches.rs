@@ -0,0 +1,388 @@
+// ============================================================================
+// Batch Jobs
+// ============================================================================
+//
+// Prompts from a JSONL or CSV file are submitted as one Anthropic or OpenAI
+// batch job. Jobs are stored with their prompts in `batches.json` in the app
+// data directory, so progress can be checked and results exported after a
+// restart.
+//
+// JSONL: one prompt per line, either a JSON string or an object with "prompt"
+// and optionally "custom_id":
+//
+//     {"custom_id": "q-1", "prompt": "Summarize ..."}
+//
+// CSV: a header row with a "prompt" column and optionally a "custom_id" column.
+// Prompts without an id get "row-<line number>".
+
+use apiai_shared::{match_results, AnthropicClient, BatchClient, BatchJob, BatchRequest, OpenAIClient};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager, State};
+
+use crate::{AppConfig, AppState};
+
+const BATCHES_FILE: &str = "batches.json";
+
+/// A submitted job as stored on disk
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct StoredBatch {
+    provider: String, // "anthropic" or "openai"
+    #[serde(default)]
+    model: Option<String>,
+    #[serde(default)]
+    system_prompt: Option<String>,
+    source_file: String,
+    submitted_at: String,
+    updated_at: String,
+    job: BatchJob,
+    requests: Vec<BatchRequest>,
+}
+
+/// A job as shown in the UI, without its prompts
+#[derive(Debug, Clone, Serialize)]
+pub struct BatchInfo {
+    provider: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    model: Option<String>,
+    source_file: String,
+    submitted_at: String,
+    updated_at: String,
+    prompts: usize,
+    job: BatchJob,
+}
+
+impl From<&StoredBatch> for BatchInfo {
+    fn from(batch: &StoredBatch) -> Self {
+        Self {
+            provider: batch.provider.clone(),
+            model: batch.model.clone(),
+            source_file: batch.source_file.clone(),
+            submitted_at: batch.submitted_at.clone(),
+            updated_at: batch.updated_at.clone(),
+            prompts: batch.requests.len(),
+            job: batch.job.clone(),
+        }
+    }
+}
+
+/// One row of an export
+#[derive(Debug, Serialize)]
+struct ExportRow {
+    custom_id: String,
+    prompt: String,
+    response: Option<String>,
+    error: Option<String>,
+    model: Option<String>,
+    input_tokens: Option<u64>,
+    output_tokens: Option<u64>,
+}
+
+fn batches_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app.path().app_data_dir().map_err(|e| format!("Failed to resolve app data dir: {}", e))?;
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    Ok(dir.join(BATCHES_FILE))
+}
+
+fn load_batches(path: &Path) -> Result<Vec<StoredBatch>, String> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content = fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    serde_json::from_str(&content).map_err(|e| format!("Failed to parse {}: {}", path.display(), e))
+}
+
+fn save_batches(path: &Path, batches: &[StoredBatch]) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(batches).map_err(|e| format!("Failed to serialize batches: {}", e))?;
+    fs::write(path, json).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+fn find_batch<'a>(batches: &'a mut [StoredBatch], batch_id: &str) -> Result<&'a mut StoredBatch, String> {
+    batches.iter_mut()
+        .find(|batch| batch.job.id == batch_id)
+        .ok_or_else(|| format!("Unknown batch: {}", batch_id))
+}
+
+fn batch_client(
+    provider: &str,
+    model: &Option<String>,
+    system_prompt: &Option<String>,
+    config: &AppConfig,
+) -> Result<Box<dyn BatchClient>, String> {
+    match provider {
+        "anthropic" => {
+            let mut client = AnthropicClient::new(config.api_keys.anthropic.clone());
+            if let Some(model) = model {
+                client = client.with_model(model.clone());
+            }
+            if let Some(system_prompt) = system_prompt {
+                client = client.with_system_prompt(system_prompt.clone());
+            }
+            Ok(Box::new(client))
+        }
+        "openai" => {
+            let mut client = OpenAIClient::new(config.api_keys.openai.clone());
+            if let Some(model) = model {
+                client = client.with_model(model.clone());
+            }
+            if let Some(system_prompt) = system_prompt {
+                client = client.with_system_prompt(system_prompt.clone());
+            }
+            Ok(Box::new(client))
+        }
+        other => Err(format!("Batch jobs are not supported for provider: {}", other)),
+    }
+}
+
+fn is_csv(path: &str) -> bool {
+    Path::new(path)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("csv"))
+}
+
+/// Read prompts from a JSONL or CSV file (chosen by extension)
+fn read_requests(file_path: &str) -> Result<Vec<BatchRequest>, String> {
+    let content = fs::read_to_string(file_path).map_err(|e| format!("Failed to read {}: {}", file_path, e))?;
+    let requests = if is_csv(file_path) {
+        parse_csv(&content)?
+    } else {
+        parse_jsonl(&content)?
+    };
+    if requests.is_empty() {
+        return Err(format!("No prompts found in {}", file_path));
+    }
+    Ok(requests)
+}
+
+fn parse_jsonl(content: &str) -> Result<Vec<BatchRequest>, String> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Line {
+        Prompt(String),
+        Entry {
+            #[serde(default)]
+            custom_id: Option<String>,
+            prompt: String,
+        },
+    }
+
+    let mut requests = Vec::new();
+    for (index, line) in content.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let line: Line = serde_json::from_str(line)
+            .map_err(|e| format!("Line {}: expected a string or an object with \"prompt\": {}", index + 1, e))?;
+        let (custom_id, prompt) = match line {
+            Line::Prompt(prompt) => (None, prompt),
+            Line::Entry { custom_id, prompt } => (custom_id, prompt),
+        };
+        requests.push(BatchRequest::new(custom_id.unwrap_or_else(|| format!("row-{}", index + 1)), prompt));
+    }
+    Ok(requests)
+}
+
+fn parse_csv(content: &str) -> Result<Vec<BatchRequest>, String> {
+    let mut reader = csv::Reader::from_reader(content.as_bytes());
+    let headers = reader.headers().map_err(|e| format!("Failed to read CSV header: {}", e))?.clone();
+    let column = |name: &str| headers.iter().position(|h| h.trim().eq_ignore_ascii_case(name));
+    let prompt_column = column("prompt").ok_or("CSV file needs a \"prompt\" column")?;
+    let id_column = column("custom_id");
+
+    let mut requests = Vec::new();
+    for (index, record) in reader.records().enumerate() {
+        let record = record.map_err(|e| format!("Failed to read CSV row {}: {}", index + 2, e))?;
+        let prompt = record.get(prompt_column).unwrap_or_default();
+        if prompt.trim().is_empty() {
+            continue;
+        }
+        let custom_id = id_column
+            .and_then(|i| record.get(i))
+            .filter(|id| !id.is_empty())
+            .map(str::to_string)
+            .unwrap_or_else(|| format!("row-{}", index + 2));
+        requests.push(BatchRequest::new(custom_id, prompt));
+    }
+    Ok(requests)
+}
+
+/// Submit the prompts of a JSONL or CSV file as one batch job
+#[tauri::command]
+pub async fn submit_batch(
+    file_path: String,
+    provider: String,
+    model: Option<String>,
+    system_prompt: Option<String>,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<BatchInfo, String> {
+    let requests = read_requests(&file_path)?;
+    let config = state.config.lock().unwrap().clone();
+    let model = model.filter(|m| !m.is_empty());
+    let system_prompt = system_prompt.filter(|s| !s.is_empty());
+
+    let client = batch_client(&provider, &model, &system_prompt, &config)?;
+    let job = client.create_batch(&requests).await.map_err(|e| e.to_string())?;
+    tracing::info!(batch_id = %job.id, prompts = requests.len(), "batch submitted");
+
+    let now = chrono::Local::now().to_rfc3339();
+    let batch = StoredBatch {
+        provider,
+        model,
+        system_prompt,
+        source_file: file_path,
+        submitted_at: now.clone(),
+        updated_at: now,
+        job,
+        requests,
+    };
+
+    let path = batches_path(&app)?;
+    let _guard = state.batch_lock.lock().await;
+    let mut batches = load_batches(&path)?;
+    let info = BatchInfo::from(&batch);
+    batches.push(batch);
+    save_batches(&path, &batches)?;
+    Ok(info)
+}
+
+/// Submitted jobs, newest first, as last seen
+#[tauri::command]
+pub async fn list_batches(app: AppHandle, state: State<'_, AppState>) -> Result<Vec<BatchInfo>, String> {
+    let path = batches_path(&app)?;
+    let _guard = state.batch_lock.lock().await;
+    let batches = load_batches(&path)?;
+    Ok(batches.iter().rev().map(BatchInfo::from).collect())
+}
+
+/// Fetch the current status of a job from its provider
+#[tauri::command]
+pub async fn refresh_batch(batch_id: String, app: AppHandle, state: State<'_, AppState>) -> Result<BatchInfo, String> {
+    update_batch(&batch_id, false, &app, &state).await
+}
+
+#[tauri::command]
+pub async fn cancel_batch(batch_id: String, app: AppHandle, state: State<'_, AppState>) -> Result<BatchInfo, String> {
+    update_batch(&batch_id, true, &app, &state).await
+}
+
+async fn update_batch(batch_id: &str, cancel: bool, app: &AppHandle, state: &AppState) -> Result<BatchInfo, String> {
+    let config = state.config.lock().unwrap().clone();
+    let path = batches_path(app)?;
+    let _guard = state.batch_lock.lock().await;
+    let mut batches = load_batches(&path)?;
+    let batch = find_batch(&mut batches, batch_id)?;
+
+    let client = batch_client(&batch.provider, &batch.model, &batch.system_prompt, &config)?;
+    batch.job = if cancel {
+        client.cancel_batch(batch_id).await
+    } else {
+        client.batch_status(batch_id).await
+    }.map_err(|e| e.to_string())?;
+    batch.updated_at = chrono::Local::now().to_rfc3339();
+
+    let info = BatchInfo::from(&*batch);
+    save_batches(&path, &batches)?;
+    Ok(info)
+}
+
+/// Write the results of a finished job next to its prompts, as CSV or JSONL
+/// (chosen by the extension of `output_path`). Returns the number of rows.
+#[tauri::command]
+pub async fn export_batch_results(
+    batch_id: String,
+    output_path: String,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<usize, String> {
+    let info = update_batch(&batch_id, false, &app, &state).await?;
+    if !info.job.status.is_finished() {
+        return Err(format!(
+            "Batch {} is still running ({} of {} done)",
+            batch_id,
+            info.job.succeeded + info.job.failed,
+            info.job.total
+        ));
+    }
+
+    let config = state.config.lock().unwrap().clone();
+    let batch = {
+        let _guard = state.batch_lock.lock().await;
+        let mut batches = load_batches(&batches_path(&app)?)?;
+        find_batch(&mut batches, &batch_id)?.clone()
+    };
+    let client = batch_client(&batch.provider, &batch.model, &batch.system_prompt, &config)?;
+    let results = client.batch_results(&batch.job).await.map_err(|e| e.to_string())?;
+
+    let rows: Vec<ExportRow> = batch.requests.iter()
+        .zip(match_results(&batch.requests, results))
+        .map(|(request, item)| {
+            let usage = item.result.as_ref().and_then(|r| r.usage.as_ref());
+            ExportRow {
+                custom_id: item.custom_id,
+                prompt: request.query.clone(),
+                input_tokens: usage.map(|u| u.input_tokens),
+                output_tokens: usage.map(|u| u.output_tokens),
+                model: item.result.as_ref().and_then(|r| r.model.clone()),
+                response: item.result.map(|r| r.text),
+                error: item.error,
+            }
+        })
+        .collect();
+
+    if is_csv(&output_path) {
+        let mut writer = csv::Writer::from_path(&output_path).map_err(|e| format!("Failed to create {}: {}", output_path, e))?;
+        for row in &rows {
+            writer.serialize(row).map_err(|e| format!("Failed to write {}: {}", output_path, e))?;
+        }
+        writer.flush().map_err(|e| format!("Failed to write {}: {}", output_path, e))?;
+    } else {
+        let mut jsonl = String::new();
+        for row in &rows {
+            jsonl.push_str(&serde_json::to_string(row).map_err(|e| format!("Failed to serialize results: {}", e))?);
+            jsonl.push('\n');
+        }
+        fs::write(&output_path, jsonl).map_err(|e| format!("Failed to write {}: {}", output_path, e))?;
+    }
+
+    Ok(rows.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids_and_prompts(requests: &[BatchRequest]) -> Vec<(&str, &str)> {
+        requests.iter().map(|r| (r.custom_id.as_str(), r.query.as_str())).collect()
+    }
+
+    #[test]
+    fn jsonl_takes_strings_and_objects() {
+        let content = "\"First\"\n\n{\"custom_id\": \"q-2\", \"prompt\": \"Second\"}\n{\"prompt\": \"Third\"}\n";
+        let requests = parse_jsonl(content).unwrap();
+        assert_eq!(ids_and_prompts(&requests), [("row-1", "First"), ("q-2", "Second"), ("row-4", "Third")]);
+    }
+
+    #[test]
+    fn jsonl_without_prompt_names_the_line() {
+        let err = parse_jsonl("\"ok\"\n{\"custom_id\": \"q-2\"}").unwrap_err();
+        assert!(err.starts_with("Line 2:"), "{}", err);
+        assert!(parse_jsonl("not json").is_err());
+    }
+
+    #[test]
+    fn csv_uses_header_columns_and_skips_blank_rows() {
+        let content = "Custom_ID,Prompt\nq-1,First\n,Second\nq-3,\n\nq-5,\"Fifth, quoted\"\n";
+        let requests = parse_csv(content).unwrap();
+        assert_eq!(ids_and_prompts(&requests), [("q-1", "First"), ("row-3", "Second"), ("q-5", "Fifth, quoted")]);
+    }
+
+    #[test]
+    fn csv_without_prompt_header_is_refused() {
+        let err = parse_csv("What is Rust?\nWhat is Tauri?\n").unwrap_err();
+        assert!(err.contains("\"prompt\" column"), "{}", err);
+        assert!(parse_csv("").is_err());
+    }
+}
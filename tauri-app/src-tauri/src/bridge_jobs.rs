@@ -0,0 +1,310 @@
+// ============================================================================
+// Bridge Jobs
+// ============================================================================
+//
+// With `bridge.use_jobs` on and a bridge that advertises jobs, `perform_search`
+// submits the query as a job and long-polls for the answer instead of holding
+// one connection open. Pending jobs are kept in `bridge_jobs.json` in the app
+// data directory until they finish, so after a restart `list_bridge_jobs`
+// shows them and `resume_bridge_job` picks up the answer. Keys are not stored;
+// the frontend passes them like for `perform_search`. Neither is the prompt:
+// only the start of it, and only for unencrypted jobs.
+
+use apiai_shared::{ApiError, SearchResult, TelegramClient};
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager, State};
+
+use crate::{bridge_policy, bridge_signer, AppState, SearchError, SearchResponse};
+
+const JOBS_FILE: &str = "bridge_jobs.json";
+
+/// How long one status request may wait on the bridge
+const LONG_POLL: Duration = Duration::from_secs(25);
+
+/// Characters of the prompt kept as `PendingJob::preview`
+const PREVIEW_CHARS: usize = 60;
+
+/// A submitted job, enough to poll it again after a restart
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PendingJob {
+    pub job_id: String,
+    pub request_id: String,
+    pub telegram_url: String,
+    pub use_encryption: bool,
+    pub chat_mode: bool,
+    pub conversation_id: Option<String>,
+    /// Start of the prompt, for the "still waiting" notice; `None` for
+    /// encrypted jobs
+    #[serde(default)]
+    pub preview: Option<String>,
+    /// Unix seconds
+    pub submitted_at: u64,
+}
+
+fn jobs_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app.path().app_data_dir().map_err(|e| format!("Failed to resolve app data dir: {}", e))?;
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    Ok(dir.join(JOBS_FILE))
+}
+
+fn load_jobs(path: &Path) -> Result<Vec<PendingJob>, String> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content = fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    serde_json::from_str(&content).map_err(|e| format!("Failed to parse {}: {}", path.display(), e))
+}
+
+fn save_jobs(path: &Path, jobs: &[PendingJob]) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(jobs).map_err(|e| format!("Failed to serialize jobs: {}", e))?;
+    fs::write(path, json).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+fn preview(prompt: &str, use_encryption: bool) -> Option<String> {
+    if use_encryption {
+        return None;
+    }
+    let mut chars = prompt.chars();
+    let mut preview: String = chars.by_ref().take(PREVIEW_CHARS).collect();
+    if chars.next().is_some() {
+        preview.push('…');
+    }
+    Some(preview)
+}
+
+/// Add a job, replacing one stored under the same job id
+fn remember(jobs: &mut Vec<PendingJob>, job: PendingJob) {
+    jobs.retain(|stored| stored.job_id != job.job_id);
+    jobs.push(job);
+}
+
+/// Remove and return the jobs matching a job id or request id
+fn forget(jobs: &mut Vec<PendingJob>, id: &str) -> Vec<PendingJob> {
+    let (forgotten, kept) = std::mem::take(jobs)
+        .into_iter()
+        .partition(|job| job.job_id == id || job.request_id == id);
+    *jobs = kept;
+    forgotten
+}
+
+async fn store_job(app: &AppHandle, job: PendingJob) -> Result<(), String> {
+    let path = jobs_path(app)?;
+    let state = app.state::<AppState>();
+    let _guard = state.bridge_job_lock.lock().await;
+    let mut jobs = load_jobs(&path)?;
+    remember(&mut jobs, job);
+    save_jobs(&path, &jobs)
+}
+
+/// Drop jobs by job id or request id and return them; unknown ids are ignored
+pub(crate) async fn forget_job(app: &AppHandle, id: &str) -> Result<Vec<PendingJob>, String> {
+    let path = jobs_path(app)?;
+    let state = app.state::<AppState>();
+    let _guard = state.bridge_job_lock.lock().await;
+    let mut jobs = load_jobs(&path)?;
+    let forgotten = forget(&mut jobs, id);
+    if !forgotten.is_empty() {
+        save_jobs(&path, &jobs)?;
+    }
+    Ok(forgotten)
+}
+
+/// The bridge could not be reached, so it may still be working on the job
+fn bridge_unreachable(error: &(dyn Error + Send + Sync + 'static)) -> bool {
+    error.downcast_ref::<reqwest::Error>().is_some()
+        || matches!(error.downcast_ref::<ApiError>(), Some(ApiError::Http { status: 502..=504, .. }))
+}
+
+/// Wait for a stored job; it is forgotten once it finished or the bridge
+/// disowned it, and kept when the bridge is unreachable
+async fn wait_for_stored(
+    app: &AppHandle,
+    client: &TelegramClient,
+    job_id: &str,
+) -> Result<SearchResult, Box<dyn Error + Send + Sync>> {
+    let outcome = client.wait_for_job(job_id, LONG_POLL).await;
+    match &outcome {
+        Err(e) if bridge_unreachable(e.as_ref()) => {}
+        _ => {
+            forget_job(app, job_id).await?;
+        }
+    }
+    outcome
+}
+
+/// `perform_search` in job mode: submit, remember the job, then wait for it
+pub(crate) async fn job_search(
+    app: &AppHandle,
+    client: &TelegramClient,
+    query: &str,
+    telegram_url: &str,
+    use_encryption: bool,
+    chat_mode: bool,
+    conversation_id: Option<String>,
+) -> Result<SearchResult, Box<dyn Error + Send + Sync>> {
+    let job_id = client.submit_job(query).await?;
+    store_job(app, PendingJob {
+        job_id: job_id.clone(),
+        request_id: client.request_id().to_string(),
+        telegram_url: telegram_url.to_string(),
+        use_encryption,
+        chat_mode,
+        conversation_id,
+        preview: preview(query, use_encryption),
+        submitted_at: SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default(),
+    })
+    .await?;
+    wait_for_stored(app, client, &job_id).await
+}
+
+fn job_client(job: &PendingJob, api_key: String, encryption_key: Option<String>, state: &AppState) -> TelegramClient {
+    let config = state.config.lock().unwrap().clone();
+    let client = TelegramClient::new(
+        job.telegram_url.clone(),
+        api_key,
+        encryption_key,
+        job.use_encryption,
+        job.chat_mode,
+        job.conversation_id.clone(),
+    )
+    .with_request_id(job.request_id.clone());
+    let client = bridge_policy(client, &config.security);
+    match bridge_signer(config.bridge.signing_secret.as_deref(), config.bridge.max_clock_skew_secs) {
+        Some(signer) => client.with_signer(signer),
+        None => client,
+    }
+}
+
+async fn find_job(app: &AppHandle, state: &AppState, job_id: &str) -> Result<PendingJob, String> {
+    let path = jobs_path(app)?;
+    let _guard = state.bridge_job_lock.lock().await;
+    load_jobs(&path)?
+        .into_iter()
+        .find(|job| job.job_id == job_id)
+        .ok_or_else(|| format!("Unknown bridge job: {}", job_id))
+}
+
+/// Jobs still waiting for an answer, oldest first
+#[tauri::command]
+pub async fn list_bridge_jobs(app: AppHandle, state: State<'_, AppState>) -> Result<Vec<PendingJob>, String> {
+    let path = jobs_path(&app)?;
+    let _guard = state.bridge_job_lock.lock().await;
+    load_jobs(&path)
+}
+
+/// Wait for a job submitted earlier (e.g. before the app was restarted)
+#[tauri::command]
+pub async fn resume_bridge_job(
+    job_id: String,
+    api_key: String,
+    encryption_key: Option<String>,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<SearchResponse, SearchError> {
+    let job = find_job(&app, &state, &job_id).await.map_err(SearchError::Failed)?;
+    let client = job_client(&job, api_key, encryption_key, &state);
+    let result = wait_for_stored(&app, &client, &job_id).await?;
+    Ok(SearchResponse {
+        text: result.text,
+        provider: result.provider,
+        model: result.model,
+        conversation_id: result.conversation_id,
+        request_id: Some(job.request_id),
+        usage: result.usage,
+        thinking: result.thinking,
+        candidates: result.candidates,
+    })
+}
+
+/// Cancel a pending job on the bridge by its job id (best effort) and forget it
+#[tauri::command]
+pub async fn discard_bridge_job(
+    job_id: String,
+    api_key: String,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let job = find_job(&app, &state, &job_id).await?;
+    if let Err(e) = job_client(&job, api_key, None, &state).cancel_job(&job.job_id).await {
+        tracing::warn!(job_id = %job_id, error = %e, "could not cancel bridge job");
+    }
+    forget_job(&app, &job_id).await.map(|_| ())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn job(job_id: &str, request_id: &str, preview: &str) -> PendingJob {
+        PendingJob {
+            job_id: job_id.to_string(),
+            request_id: request_id.to_string(),
+            telegram_url: "http://127.0.0.1:8000/ai_query".to_string(),
+            use_encryption: false,
+            chat_mode: true,
+            conversation_id: Some("conv-1".to_string()),
+            preview: Some(preview.to_string()),
+            submitted_at: 1_700_000_000,
+        }
+    }
+
+    fn job_ids(jobs: &[PendingJob]) -> Vec<&str> {
+        jobs.iter().map(|job| job.job_id.as_str()).collect()
+    }
+
+    #[test]
+    fn pending_jobs_survive_a_save_and_load() {
+        let dir = std::env::temp_dir().join(format!("bridge-jobs-test-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(JOBS_FILE);
+        assert!(load_jobs(&path).unwrap().is_empty());
+
+        save_jobs(&path, &[job("job-1", "req-1", "First"), job("job-2", "req-2", "Second")]).unwrap();
+        let loaded = load_jobs(&path).unwrap();
+        assert_eq!(job_ids(&loaded), ["job-1", "job-2"]);
+        assert_eq!(loaded[1].preview.as_deref(), Some("Second"));
+        assert_eq!(loaded[0].conversation_id.as_deref(), Some("conv-1"));
+        assert_eq!((loaded[0].chat_mode, loaded[0].submitted_at), (true, 1_700_000_000));
+
+        fs::write(&path, "not json").unwrap();
+        assert!(load_jobs(&path).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn jobs_are_replaced_by_job_id() {
+        let mut jobs = vec![job("job-1", "req-1", "First")];
+        remember(&mut jobs, job("job-2", "req-2", "Second"));
+        remember(&mut jobs, job("job-1", "req-1", "First again"));
+        assert_eq!(job_ids(&jobs), ["job-2", "job-1"]);
+        assert_eq!(jobs[1].preview.as_deref(), Some("First again"));
+    }
+
+    #[test]
+    fn jobs_are_forgotten_by_job_or_request_id() {
+        let mut jobs = vec![job("job-1", "req-1", "First"), job("job-2", "req-2", "Second"), job("job-3", "req-3", "Third")];
+        assert_eq!(job_ids(&forget(&mut jobs, "job-1")), ["job-1"]);
+        assert_eq!(job_ids(&forget(&mut jobs, "req-3")), ["job-3"]);
+        assert!(forget(&mut jobs, "job-9").is_empty());
+        assert_eq!(job_ids(&jobs), ["job-2"]);
+    }
+
+    #[test]
+    fn only_a_preview_of_unencrypted_prompts_is_kept() {
+        assert_eq!(preview("Short question", false).as_deref(), Some("Short question"));
+        let long = "ж".repeat(PREVIEW_CHARS + 5);
+        assert_eq!(preview(&long, false), Some(format!("{}…", "ж".repeat(PREVIEW_CHARS))));
+        assert_eq!(preview("Secret question", true), None);
+
+        // Files written before the preview still load
+        let legacy = r#"[{"job_id": "job-1", "request_id": "req-1", "telegram_url": "http://127.0.0.1:8000/ai_query",
+            "use_encryption": true, "chat_mode": false, "conversation_id": null, "prompt": "Old", "submitted_at": 1}]"#;
+        let jobs: Vec<PendingJob> = serde_json::from_str(legacy).unwrap();
+        assert_eq!(jobs[0].preview, None);
+        assert!(!serde_json::to_string(&jobs).unwrap().contains("Old"));
+    }
+}
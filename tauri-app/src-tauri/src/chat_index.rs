@@ -0,0 +1,371 @@
+// ============================================================================
+// Semantic Search over Saved Chats
+// ============================================================================
+//
+// Messages of every chat in a directory are split into chunks, embedded with
+// the configured embeddings provider and stored in `.semantic_index` inside
+// that directory. Chunks whose text did not change keep their embedding, so
+// re-indexing a saved chat only embeds what is new.
+
+use apiai_shared::{EmbeddingClient, OllamaEmbeddingClient, OpenAIEmbeddingClient};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::State;
+
+use crate::{AppState, ChatHistory, EmbeddingsConfig};
+
+const INDEX_FILE: &str = ".semantic_index";
+const INDEX_VERSION: u32 = 1;
+const MAX_CHUNK_CHARS: usize = 1000;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct ChatIndex {
+    version: u32,
+    model: String,
+    #[serde(default)]
+    dimensions: Option<u32>, // Requested vector size, None for the model's default
+    files: HashMap<String, IndexedFile>, // Keyed by chat file name
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct IndexedFile {
+    last_modified: String, // ChatMetadata.last_modified when indexed
+    chunks: Vec<IndexedChunk>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct IndexedChunk {
+    message_index: usize,
+    chunk_index: usize,
+    role: String,
+    timestamp: String,
+    text: String,
+    embedding: Vec<f32>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SemanticHit {
+    path: String,
+    filename: String,
+    message_index: usize, // Position of the message in ChatHistory.messages
+    chunk_index: usize,   // Position of the chunk within that message
+    role: String,
+    timestamp: String,
+    text: String,
+    score: f32, // Cosine similarity, higher is closer
+}
+
+/// Build the embeddings client described by the config.
+/// `openai_key` is used when the embeddings section has no key of its own.
+pub(crate) fn embedding_client(config: &EmbeddingsConfig, openai_key: &str) -> Result<Box<dyn EmbeddingClient>, String> {
+    let api_key = if config.api_key.is_empty() { openai_key } else { &config.api_key };
+
+    let client: Box<dyn EmbeddingClient> = match config.provider.as_str() {
+        "openai" => {
+            if api_key.is_empty() {
+                return Err("OpenAI API key is missing for embeddings".to_string());
+            }
+            let mut client = match config.base_url {
+                Some(ref url) => OpenAIEmbeddingClient::compatible(url.clone(), api_key.to_string()),
+                None => OpenAIEmbeddingClient::new(api_key.to_string()),
+            };
+            if let Some(ref model) = config.model {
+                client = client.with_model(model.clone());
+            }
+            if let Some(dimensions) = config.dimensions {
+                client = client.with_dimensions(dimensions);
+            }
+            Box::new(client)
+        }
+        "openai_compatible" => {
+            let url = config.base_url.clone()
+                .ok_or("Embeddings base_url is required for an OpenAI-compatible server")?;
+            let mut client = OpenAIEmbeddingClient::compatible(url, config.api_key.clone());
+            if let Some(ref model) = config.model {
+                client = client.with_model(model.clone());
+            }
+            if let Some(dimensions) = config.dimensions {
+                client = client.with_dimensions(dimensions);
+            }
+            Box::new(client)
+        }
+        "ollama" => {
+            let mut client = OllamaEmbeddingClient::new(config.base_url.clone());
+            if let Some(ref model) = config.model {
+                client = client.with_model(model.clone());
+            }
+            if let Some(dimensions) = config.dimensions {
+                client = client.with_dimensions(dimensions);
+            }
+            Box::new(client)
+        }
+        other => return Err(format!("Unknown embeddings provider: {}", other)),
+    };
+
+    Ok(client)
+}
+
+/// Split message text into chunks of at most MAX_CHUNK_CHARS, preferring paragraph breaks
+fn chunk_text(text: &str) -> Vec<String> {
+    let mut chunks = Vec::new();
+    let mut current = String::new();
+
+    for paragraph in text.split("\n\n").map(str::trim).filter(|p| !p.is_empty()) {
+        if !current.is_empty() && current.chars().count() + paragraph.chars().count() + 2 > MAX_CHUNK_CHARS {
+            chunks.push(std::mem::take(&mut current));
+        }
+        if paragraph.chars().count() > MAX_CHUNK_CHARS {
+            // Paragraph alone is too long: hard-split on char boundaries
+            let chars: Vec<char> = paragraph.chars().collect();
+            for piece in chars.chunks(MAX_CHUNK_CHARS) {
+                chunks.push(piece.iter().collect());
+            }
+            continue;
+        }
+        if !current.is_empty() {
+            current.push_str("\n\n");
+        }
+        current.push_str(paragraph);
+    }
+    if !current.is_empty() {
+        chunks.push(current);
+    }
+
+    chunks
+}
+
+fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() {
+        return 0.0;
+    }
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm_a: f32 = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norm_b: f32 = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm_a == 0.0 || norm_b == 0.0 {
+        0.0
+    } else {
+        dot / (norm_a * norm_b)
+    }
+}
+
+fn index_path(directory: &Path) -> PathBuf {
+    directory.join(INDEX_FILE)
+}
+
+/// Load the index, starting over if it is missing, unreadable or built with
+/// another model or vector size
+fn load_index(directory: &Path, client: &dyn EmbeddingClient) -> ChatIndex {
+    fs::read_to_string(index_path(directory))
+        .ok()
+        .and_then(|content| serde_json::from_str::<ChatIndex>(&content).ok())
+        .filter(|index| {
+            index.version == INDEX_VERSION && index.model == client.model() && index.dimensions == client.dimensions()
+        })
+        .unwrap_or_else(|| ChatIndex {
+            version: INDEX_VERSION,
+            model: client.model().to_string(),
+            dimensions: client.dimensions(),
+            files: HashMap::new(),
+        })
+}
+
+fn save_index(directory: &Path, index: &ChatIndex) -> Result<(), String> {
+    let content = serde_json::to_string(index)
+        .map_err(|e| format!("Failed to serialize semantic index: {}", e))?;
+    fs::write(index_path(directory), content)
+        .map_err(|e| format!("Failed to write semantic index in {:?}: {}", directory, e))
+}
+
+/// (Re-)index one chat, embedding only chunks that are not already in `previous`
+async fn index_chat(
+    chat: &ChatHistory,
+    previous: Option<&IndexedFile>,
+    client: &dyn EmbeddingClient,
+) -> Result<IndexedFile, String> {
+    let known: HashMap<&str, &Vec<f32>> = previous
+        .map(|file| file.chunks.iter().map(|c| (c.text.as_str(), &c.embedding)).collect())
+        .unwrap_or_default();
+
+    let mut chunks = Vec::new();
+    for (message_index, message) in chat.messages.iter().enumerate() {
+        for (chunk_index, text) in chunk_text(&message.content).into_iter().enumerate() {
+            chunks.push(IndexedChunk {
+                message_index,
+                chunk_index,
+                role: message.role.clone(),
+                timestamp: message.timestamp.clone(),
+                embedding: known.get(text.as_str()).map(|e| (*e).clone()).unwrap_or_default(),
+                text,
+            });
+        }
+    }
+
+    let missing: Vec<usize> = (0..chunks.len()).filter(|&i| chunks[i].embedding.is_empty()).collect();
+    if !missing.is_empty() {
+        let texts: Vec<String> = missing.iter().map(|&i| chunks[i].text.clone()).collect();
+        let result = client.embed(&texts).await.map_err(|e| format!("Embedding failed: {}", e))?;
+        for (i, embedding) in missing.into_iter().zip(result.embeddings) {
+            chunks[i].embedding = embedding;
+        }
+    }
+
+    Ok(IndexedFile {
+        last_modified: chat.metadata.last_modified.clone(),
+        chunks,
+    })
+}
+
+/// Bring the index of `directory` up to date with the chat files in it
+async fn refresh_index(directory: &Path, client: &dyn EmbeddingClient) -> Result<ChatIndex, String> {
+    let mut index = load_index(directory, client);
+
+    let entries = fs::read_dir(directory)
+        .map_err(|e| format!("Failed to read directory {:?}: {}", directory, e))?;
+
+    let mut present = Vec::new();
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.extension().and_then(|s| s.to_str()) != Some("json") {
+            continue;
+        }
+        let Some(chat) = fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str::<ChatHistory>(&content).ok())
+        else {
+            continue;
+        };
+
+        let filename = entry.file_name().to_string_lossy().to_string();
+        let up_to_date = index.files.get(&filename)
+            .is_some_and(|file| file.last_modified == chat.metadata.last_modified);
+        if !up_to_date {
+            let indexed = index_chat(&chat, index.files.get(&filename), client).await?;
+            index.files.insert(filename.clone(), indexed);
+        }
+        present.push(filename);
+    }
+
+    // Forget chats that were deleted
+    index.files.retain(|filename, _| present.contains(filename));
+
+    save_index(directory, &index)?;
+    Ok(index)
+}
+
+/// Update the index entry for a chat that was just saved to `file_path`
+pub(crate) async fn update_for_saved_chat(
+    file_path: &Path,
+    chat: &ChatHistory,
+    client: &dyn EmbeddingClient,
+) -> Result<(), String> {
+    let directory = file_path.parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    let filename = file_path.file_name()
+        .map(|s| s.to_string_lossy().to_string())
+        .ok_or_else(|| format!("Invalid chat file path: {:?}", file_path))?;
+
+    let mut index = load_index(directory, client);
+    let indexed = index_chat(chat, index.files.get(&filename), client).await?;
+    index.files.insert(filename, indexed);
+    save_index(directory, &index)
+}
+
+#[tauri::command]
+pub async fn semantic_search_chats(
+    directory: String,
+    query: String,
+    limit: Option<usize>,
+    state: State<'_, AppState>,
+) -> Result<Vec<SemanticHit>, String> {
+    let dir_path = Path::new(&directory);
+    if !dir_path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let client = {
+        let config = state.config.lock().unwrap();
+        embedding_client(&config.embeddings, &config.api_keys.openai)?
+    };
+
+    let index = {
+        let _guard = state.index_lock.lock().await;
+        refresh_index(dir_path, client.as_ref()).await?
+    };
+
+    let query_embedding = client.embed(&[query]).await
+        .map_err(|e| format!("Embedding failed: {}", e))?
+        .embeddings
+        .into_iter()
+        .next()
+        .ok_or("Embeddings provider returned no vector for the query")?;
+
+    let mut hits = Vec::new();
+    for (filename, file) in &index.files {
+        let path = dir_path.join(filename).to_string_lossy().to_string();
+        for chunk in &file.chunks {
+            hits.push(SemanticHit {
+                path: path.clone(),
+                filename: filename.clone(),
+                message_index: chunk.message_index,
+                chunk_index: chunk.chunk_index,
+                role: chunk.role.clone(),
+                timestamp: chunk.timestamp.clone(),
+                text: chunk.text.clone(),
+                score: cosine_similarity(&query_embedding, &chunk.embedding),
+            });
+        }
+    }
+
+    hits.sort_by(|a, b| b.score.total_cmp(&a.score));
+    hits.truncate(limit.unwrap_or(20));
+
+    Ok(hits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chunk_text_joins_short_paragraphs() {
+        assert_eq!(chunk_text("one\n\n  two  \n\n\n\nthree"), ["one\n\ntwo\n\nthree"]);
+        assert!(chunk_text(" \n\n ").is_empty());
+    }
+
+    #[test]
+    fn chunk_text_breaks_at_paragraph_boundaries() {
+        let first = "a".repeat(600);
+        let second = "b".repeat(600);
+        assert_eq!(chunk_text(&format!("{}\n\n{}", first, second)), [first.clone(), second]);
+
+        // Exactly MAX_CHUNK_CHARS including the separator still fits in one chunk
+        let fits = "c".repeat(MAX_CHUNK_CHARS - first.len() - 2);
+        assert_eq!(chunk_text(&format!("{}\n\n{}", first, fits)).len(), 1);
+    }
+
+    #[test]
+    fn chunk_text_splits_long_multibyte_paragraphs_on_chars() {
+        let paragraph = "é".repeat(MAX_CHUNK_CHARS * 2 + 5);
+        let chunks = chunk_text(&paragraph);
+        let lengths: Vec<usize> = chunks.iter().map(|c| c.chars().count()).collect();
+        assert_eq!(lengths, [MAX_CHUNK_CHARS, MAX_CHUNK_CHARS, 5]);
+        assert_eq!(chunks.concat(), paragraph);
+    }
+
+    #[test]
+    fn cosine_similarity_of_vectors() {
+        assert!((cosine_similarity(&[1.0, 2.0], &[2.0, 4.0]) - 1.0).abs() < 1e-6);
+        assert!(cosine_similarity(&[1.0, 0.0], &[0.0, 1.0]).abs() < 1e-6);
+        assert!((cosine_similarity(&[1.0, 0.0], &[-1.0, 0.0]) + 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn cosine_similarity_of_zero_or_mismatched_vectors_is_zero() {
+        assert_eq!(cosine_similarity(&[0.0, 0.0], &[1.0, 2.0]), 0.0);
+        assert_eq!(cosine_similarity(&[1.0, 2.0], &[1.0, 2.0, 3.0]), 0.0);
+        assert_eq!(cosine_similarity(&[], &[]), 0.0);
+    }
+}
@@ -0,0 +1,112 @@
+// ============================================================================
+// Bridge Conversations
+// ============================================================================
+//
+// Chat mode leaves a conversation on the Telegram bridge for every
+// `conversation_id` it hands back. These commands list, inspect, rename, reset
+// and delete them. Connection settings come from the frontend like for
+// `perform_search`; with `use_encryption` the calls go over the encrypted
+// endpoint, and requests are signed when `bridge.signing_secret` is set. The
+// encryption policy applies like for `perform_search`.
+
+use apiai_shared::{ConversationMessage, ConversationSummary, TelegramClient};
+use tauri::State;
+
+use crate::{bridge_policy, bridge_signer, AppState};
+
+fn bridge_client(
+    telegram_url: String,
+    api_key: String,
+    encryption_key: Option<String>,
+    use_encryption: bool,
+    state: &AppState,
+) -> TelegramClient {
+    let config = state.config.lock().unwrap().clone();
+    let client = TelegramClient::new(telegram_url, api_key, encryption_key, use_encryption, true, None);
+    let client = bridge_policy(client, &config.security);
+    match bridge_signer(config.bridge.signing_secret.as_deref(), config.bridge.max_clock_skew_secs) {
+        Some(signer) => client.with_signer(signer),
+        None => client,
+    }
+}
+
+#[tauri::command]
+pub async fn list_bridge_conversations(
+    telegram_url: String,
+    api_key: String,
+    encryption_key: Option<String>,
+    use_encryption: bool,
+    state: State<'_, AppState>,
+) -> Result<Vec<ConversationSummary>, String> {
+    bridge_client(telegram_url, api_key, encryption_key, use_encryption, &state)
+        .list_conversations()
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_bridge_conversation(
+    telegram_url: String,
+    api_key: String,
+    encryption_key: Option<String>,
+    use_encryption: bool,
+    conversation_id: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<ConversationMessage>, String> {
+    bridge_client(telegram_url, api_key, encryption_key, use_encryption, &state)
+        .conversation_messages(&conversation_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn rename_bridge_conversation(
+    telegram_url: String,
+    api_key: String,
+    encryption_key: Option<String>,
+    use_encryption: bool,
+    conversation_id: String,
+    title: String,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let title = title.trim();
+    if title.is_empty() {
+        return Err("Title must not be empty".to_string());
+    }
+    bridge_client(telegram_url, api_key, encryption_key, use_encryption, &state)
+        .rename_conversation(&conversation_id, title)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Clear the conversation's history on the bridge; the id stays valid for chat mode
+#[tauri::command]
+pub async fn reset_bridge_conversation(
+    telegram_url: String,
+    api_key: String,
+    encryption_key: Option<String>,
+    use_encryption: bool,
+    conversation_id: String,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    bridge_client(telegram_url, api_key, encryption_key, use_encryption, &state)
+        .reset_conversation(&conversation_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn delete_bridge_conversation(
+    telegram_url: String,
+    api_key: String,
+    encryption_key: Option<String>,
+    use_encryption: bool,
+    conversation_id: String,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    bridge_client(telegram_url, api_key, encryption_key, use_encryption, &state)
+        .delete_conversation(&conversation_id)
+        .await
+        .map_err(|e| e.to_string())
+}
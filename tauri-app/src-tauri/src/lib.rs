@@ -1,17 +1,37 @@
 // Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
 
 // Use shared library for API and encryption
-use apiai_shared::{ApiClient, AnthropicClient, OpenAIClient, TelegramClient};
+use apiai_shared::{
+    ApiClient, AnthropicClient, Capabilities, EncryptionPolicy, EncryptionPolicyError, HealthReport, OpenAIClient,
+    OpenAIResponsesClient, ReasoningEffort, RequestSigner, ResponseEvent, SearchResult, TelegramClient, TokenUsage,
+    TransportSecurity,
+};
 
-use tauri::{State, Manager};
-use std::sync::Mutex;
+mod batches;
+mod bridge_jobs;
+mod chat_index;
+mod conversations;
+mod logging;
+mod templates;
+
+use tauri::{AppHandle, Emitter, State, Manager};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
 use serde::{Deserialize, Serialize};
 use std::fs;
+use tokio::task::AbortHandle;
 
 #[derive(Clone, Serialize, Deserialize)]
 struct SecurityConfig {
     pin_code: String,
     require_pin: bool,
+    /// When bridge traffic must be encrypted: "require", "prefer" (follow
+    /// `telegram_use_encryption`) or "off"
+    #[serde(default)]
+    bridge_encryption: EncryptionPolicy,
+    /// Let unencrypted bridge traffic go to a remote server over plain http://
+    #[serde(default)]
+    allow_plaintext_http: bool,
 }
 
 #[derive(Clone, Serialize, Deserialize)]
@@ -31,11 +51,108 @@ struct UiConfig {
     window_height: Option<f64>,
 }
 
+/// Embeddings provider used to index saved chats for semantic search.
+/// Missing fields take their defaults, so a partial section does not reset the config.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+struct EmbeddingsConfig {
+    enabled: bool,
+    provider: String, // "openai", "openai_compatible" or "ollama"
+    api_key: String,  // Falls back to api_keys.openai for "openai"
+    base_url: Option<String>,
+    model: Option<String>,
+    dimensions: Option<u32>,
+}
+
+/// Defaults for reasoning models; `perform_search` can override them per request
+#[derive(Clone, Default, Serialize, Deserialize)]
+struct ReasoningConfig {
+    /// Anthropic extended thinking budget in tokens (None = thinking off)
+    #[serde(default)]
+    thinking_budget: Option<u32>,
+    /// OpenAI reasoning effort: "minimal", "low", "medium" or "high"
+    #[serde(default)]
+    reasoning_effort: Option<String>,
+    /// OpenAI `max_completion_tokens` (reasoning included)
+    #[serde(default)]
+    max_completion_tokens: Option<u32>,
+}
+
+/// What the Telegram bridge should forward requests to; `perform_search` can override it per request.
+/// Missing fields take their defaults, so a partial section does not reset the config.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+struct BridgeConfig {
+    /// Upstream provider name as advertised by the bridge ("anthropic", "openai", ...)
+    provider: String,
+    /// Upstream model (None = the bridge's default for the provider)
+    model: Option<String>,
+    max_tokens: u32,
+    temperature: Option<f32>,
+    /// Shared secret for HMAC request signing; empty or None sends unsigned requests
+    signing_secret: Option<String>,
+    /// Clock difference tolerated when verifying signed requests, in seconds (None = 5 minutes)
+    max_clock_skew_secs: Option<u64>,
+    /// Submit queries as jobs and poll for the answer when the bridge supports it
+    use_jobs: bool,
+}
+
+impl Default for BridgeConfig {
+    fn default() -> Self {
+        Self {
+            provider: "anthropic".to_string(),
+            model: None,
+            max_tokens: 1024,
+            temperature: None,
+            signing_secret: None,
+            max_clock_skew_secs: None,
+            use_jobs: false,
+        }
+    }
+}
+
+/// Signer for the bridge when a signing secret is set
+fn bridge_signer(signing_secret: Option<&str>, max_clock_skew_secs: Option<u64>) -> Option<RequestSigner> {
+    let signer = RequestSigner::new(signing_secret.filter(|secret| !secret.is_empty())?);
+    Some(match max_clock_skew_secs {
+        Some(secs) => signer.with_max_skew(std::time::Duration::from_secs(secs)),
+        None => signer,
+    })
+}
+
+/// Apply the encryption policy from `security` to a bridge client
+fn bridge_policy(client: TelegramClient, security: &SecurityConfig) -> TelegramClient {
+    client
+        .with_encryption_policy(security.bridge_encryption)
+        .allow_plaintext_http(security.allow_plaintext_http)
+}
+
+impl Default for EmbeddingsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            provider: "openai".to_string(),
+            api_key: "".to_string(),
+            base_url: None,
+            model: None,
+            dimensions: None,
+        }
+    }
+}
+
 #[derive(Clone, Serialize, Deserialize)]
 struct AppConfig {
     security: SecurityConfig,
     api_keys: ApiKeysConfig,
     ui: UiConfig,
+    #[serde(default)]
+    embeddings: EmbeddingsConfig,
+    #[serde(default)]
+    reasoning: ReasoningConfig,
+    #[serde(default)]
+    bridge: BridgeConfig,
+    #[serde(default)]
+    logging: logging::LoggingConfig,
 }
 
 impl Default for AppConfig {
@@ -44,6 +161,8 @@ impl Default for AppConfig {
             security: SecurityConfig {
                 pin_code: "1234".to_string(),
                 require_pin: true,
+                bridge_encryption: EncryptionPolicy::default(),
+                allow_plaintext_http: false,
             },
             api_keys: ApiKeysConfig {
                 anthropic: "".to_string(),
@@ -58,12 +177,67 @@ impl Default for AppConfig {
                 window_width: None,
                 window_height: None,
             },
+            embeddings: EmbeddingsConfig::default(),
+            reasoning: ReasoningConfig::default(),
+            bridge: BridgeConfig::default(),
+            logging: logging::LoggingConfig::default(),
         }
     }
 }
 
 struct AppState {
     config: Mutex<AppConfig>,
+    /// Searches currently running, keyed by the id the frontend generated for them
+    requests: Mutex<HashMap<String, InFlightRequest>>,
+    /// Serializes reads and writes of semantic index files
+    index_lock: tokio::sync::Mutex<()>,
+    /// Serializes reads and writes of the stored batch jobs
+    batch_lock: tokio::sync::Mutex<()>,
+    /// Capabilities handshake result per bridge URL
+    bridge_capabilities: Mutex<HashMap<String, Capabilities>>,
+    /// Serializes reads and writes of the pending bridge jobs
+    bridge_job_lock: tokio::sync::Mutex<()>,
+}
+
+/// Handle to a running `perform_search` so `cancel_request` can stop it
+struct InFlightRequest {
+    abort: AbortHandle,
+    /// Set for Telegram bridge requests, which must also be cancelled server-side
+    bridge: Option<Arc<TelegramClient>>,
+}
+
+/// Payload of the `request-started` event, emitted before the search is sent
+#[derive(Clone, Serialize)]
+struct RequestStarted {
+    request_id: String,
+    provider: String,
+}
+
+/// Payload of the `search-delta` event: the next piece of a streamed answer
+#[derive(Clone, Serialize)]
+struct SearchDelta {
+    request_id: String,
+    text: String,
+}
+
+/// Error returned to the frontend by `perform_search`.
+/// Serialized as `{ "kind": "cancelled" }`, `{ "kind": "insecure", "message": "..." }`
+/// (refused by the encryption policy, nothing was sent) or `{ "kind": "failed", "message": "..." }`
+#[derive(Debug, Serialize)]
+#[serde(tag = "kind", content = "message", rename_all = "snake_case")]
+enum SearchError {
+    Cancelled,
+    Insecure(String),
+    Failed(String),
+}
+
+impl From<Box<dyn std::error::Error + Send + Sync>> for SearchError {
+    fn from(error: Box<dyn std::error::Error + Send + Sync>) -> Self {
+        match error.downcast_ref::<EncryptionPolicyError>() {
+            Some(refused) => SearchError::Insecure(refused.to_string()),
+            None => SearchError::Failed(format!("Error: {}", error)),
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -73,6 +247,13 @@ struct SearchResponse {
     model: Option<String>,
     conversation_id: Option<String>,
     request_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    usage: Option<TokenUsage>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    thinking: Option<String>,
+    /// Every draft when several were requested; `text` is the first
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    candidates: Vec<String>,
 }
 
 #[tauri::command]
@@ -82,12 +263,13 @@ fn get_config(state: State<AppState>) -> AppConfig {
 }
 
 #[tauri::command]
-fn save_config(new_config: AppConfig, state: State<AppState>) -> Result<(), String> {
+fn save_config(new_config: AppConfig, app: AppHandle, state: State<AppState>) -> Result<(), String> {
     // Update state
     {
         let mut config = state.config.lock().unwrap();
         *config = new_config.clone();
     }
+    logging::apply(&app, &new_config.logging);
 
     // Save to file
     let path = get_config_path();
@@ -113,38 +295,400 @@ fn is_pin_required(state: State<AppState>) -> bool {
     config.security.require_pin
 }
 
+/// Everything needed to run one search, independent of how it was requested
+/// (directly from the chat UI or through a prompt template)
+struct SearchParams {
+    query: String,
+    provider: String,
+    api_key: String,
+    model: Option<String>,
+    system_prompt: Option<String>,
+    /// Ask Anthropic to cache the system prompt (ignored by other providers)
+    cache_system_prompt: bool,
+    reasoning: ReasoningConfig,
+    /// Number of drafts to request (1 = a single answer)
+    candidates: usize,
+    /// Upstream settings for the Telegram bridge; `model` overrides `bridge.model`
+    bridge: BridgeConfig,
+    telegram_url: Option<String>,
+    encryption_key: Option<String>,
+    use_encryption: bool,
+    chat_mode: bool,
+    conversation_id: Option<String>,
+    request_id: Option<String>,
+}
+
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 async fn perform_search(
     query: String,
     provider: String,
     api_key: String,
+    model: Option<String>,
+    system_prompt: Option<String>,
+    cache_system_prompt: Option<bool>,
+    thinking_budget: Option<u32>,
+    reasoning_effort: Option<String>,
+    candidates: Option<usize>,
+    upstream_provider: Option<String>,
+    max_tokens: Option<u32>,
+    temperature: Option<f32>,
     telegram_url: Option<String>,
     encryption_key: Option<String>,
     use_encryption: bool,
     chat_mode: bool,
-    conversation_id: Option<String>
-) -> Result<SearchResponse, String> {
-    let client: Box<dyn ApiClient> = match provider.as_str() {
-        "anthropic" => Box::new(AnthropicClient::new(api_key)),
-        "openai" => Box::new(OpenAIClient::new(api_key)),
+    conversation_id: Option<String>,
+    request_id: Option<String>,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<SearchResponse, SearchError> {
+    let (mut reasoning, mut bridge) = {
+        let config = state.config.lock().unwrap();
+        (config.reasoning.clone(), config.bridge.clone())
+    };
+    if thinking_budget.is_some() {
+        reasoning.thinking_budget = thinking_budget;
+    }
+    if reasoning_effort.is_some() {
+        reasoning.reasoning_effort = reasoning_effort;
+    }
+    if let Some(upstream_provider) = upstream_provider {
+        bridge.provider = upstream_provider;
+    }
+    if let Some(max_tokens) = max_tokens {
+        bridge.max_tokens = max_tokens;
+    }
+    if temperature.is_some() {
+        bridge.temperature = temperature;
+    }
+
+    run_search(&app, &state, SearchParams {
+        query,
+        provider,
+        api_key,
+        model,
+        system_prompt,
+        cache_system_prompt: cache_system_prompt.unwrap_or(false),
+        reasoning,
+        candidates: candidates.unwrap_or(1),
+        bridge,
+        telegram_url,
+        encryption_key,
+        use_encryption,
+        chat_mode,
+        conversation_id,
+        request_id,
+    }).await
+}
+
+async fn run_search(
+    app: &AppHandle,
+    state: &AppState,
+    params: SearchParams,
+) -> Result<SearchResponse, SearchError> {
+    let SearchParams {
+        mut query,
+        provider,
+        api_key,
+        model,
+        system_prompt,
+        cache_system_prompt,
+        reasoning,
+        candidates,
+        bridge: bridge_config,
+        telegram_url,
+        encryption_key,
+        use_encryption,
+        chat_mode,
+        conversation_id,
+        request_id,
+    } = params;
+    let request_id = request_id.unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+
+    let mut bridge = None;
+    let mut bridge_query = None;
+    let client: Arc<dyn ApiClient> = match provider.as_str() {
+        "anthropic" => {
+            let mut client = AnthropicClient::new(api_key);
+            if let Some(model) = model {
+                client = client.with_model(model);
+            }
+            if let Some(system_prompt) = system_prompt {
+                client = if cache_system_prompt {
+                    client.with_cached_system_prompt(system_prompt)
+                } else {
+                    client.with_system_prompt(system_prompt)
+                };
+            }
+            if let Some(budget) = reasoning.thinking_budget {
+                client = client.with_thinking(budget);
+            }
+            Arc::new(client)
+        },
+        "openai" => {
+            let mut client = OpenAIClient::new(api_key);
+            if let Some(model) = model {
+                client = client.with_model(model);
+            }
+            if let Some(system_prompt) = system_prompt {
+                client = client.with_system_prompt(system_prompt);
+            }
+            if let Some(effort) = reasoning.reasoning_effort {
+                let effort: ReasoningEffort = effort.parse().map_err(SearchError::Failed)?;
+                client = client.with_reasoning_effort(effort);
+            }
+            if let Some(max_completion_tokens) = reasoning.max_completion_tokens {
+                client = client.with_max_completion_tokens(max_completion_tokens);
+            }
+            Arc::new(client)
+        },
+        "openai_responses" => {
+            let mut client = OpenAIResponsesClient::new(api_key);
+            if let Some(model) = model {
+                client = client.with_model(model);
+            }
+            if let Some(system_prompt) = system_prompt {
+                client = client.with_system_prompt(system_prompt);
+            }
+            if let Some(effort) = reasoning.reasoning_effort {
+                let effort: ReasoningEffort = effort.parse().map_err(SearchError::Failed)?;
+                client = client.with_reasoning_effort(effort).with_reasoning_summary("auto");
+            }
+            if let Some(max_output_tokens) = reasoning.max_completion_tokens {
+                client = client.with_max_output_tokens(max_output_tokens);
+            }
+            // The response id comes back as conversation_id; chat mode continues from it
+            if let Some(previous) = conversation_id.filter(|_| chat_mode) {
+                client = client.with_previous_response_id(previous);
+            }
+            Arc::new(client)
+        },
         "telegram" => {
+            // The bridge protocol has no system prompt field, so send it inline
+            if let Some(system_prompt) = system_prompt {
+                query = format!("{}\n\n{}", system_prompt, query);
+            }
             let url = telegram_url.unwrap_or_default();
+            let job_conversation = conversation_id.clone();
             // Handle URL construction if needed (port logic can be done in frontend or here)
-            Box::new(TelegramClient::new(url, api_key, encryption_key, use_encryption, chat_mode, conversation_id))
+            let mut client = TelegramClient::new(url.clone(), api_key, encryption_key, use_encryption, chat_mode, conversation_id)
+                .with_request_id(request_id.clone())
+                .with_provider(bridge_config.provider)
+                .with_max_tokens(bridge_config.max_tokens);
+            if let Some(model) = model.or(bridge_config.model) {
+                client = client.with_model(model);
+            }
+            if let Some(temperature) = bridge_config.temperature {
+                client = client.with_temperature(temperature);
+            }
+            if let Some(signer) = bridge_signer(bridge_config.signing_secret.as_deref(), bridge_config.max_clock_skew_secs) {
+                client = client.with_signer(signer);
+            }
+            client = bridge_policy(client, &state.config.lock().unwrap().security);
+            // The handshake waits for the search task; this client can already cancel on the server
+            bridge_query = Some(BridgeQuery {
+                client: client.clone(),
+                url,
+                conversation_id: job_conversation,
+                use_jobs: bridge_config.use_jobs,
+                use_encryption,
+                chat_mode,
+            });
+            let telegram = Arc::new(client);
+            bridge = Some(telegram.clone());
+            telegram
         },
-        _ => return Err("Unknown provider".to_string()),
+        _ => return Err(SearchError::Failed("Unknown provider".to_string())),
+    };
+
+    // Run the whole search, bridge handshake included, as one task so cancel_request
+    // can abort it at any point (dropping the future closes the underlying connection).
+    // It is registered before anything is sent or announced.
+    let task_app = app.clone();
+    let task_request_id = request_id.clone();
+    let started = std::time::Instant::now();
+    let task = {
+        let mut requests = state.requests.lock().unwrap();
+        let task = tokio::spawn(async move {
+            match bridge_query {
+                Some(bridge_query) => bridge_search(&task_app, &task_request_id, bridge_query, &query, candidates).await,
+                None if candidates > 1 => client.search_candidates(&query, candidates).await.map_err(SearchError::from),
+                None => client.search(&query).await.map_err(SearchError::from),
+            }
+        });
+        requests.insert(request_id.clone(), InFlightRequest { abort: task.abort_handle(), bridge });
+        task
     };
 
-    match client.search(&query).await {
-        Ok(result) => Ok(SearchResponse {
+    // Let the frontend learn the id (and offer cancellation) before the response arrives
+    let _ = app.emit("request-started", RequestStarted {
+        request_id: request_id.clone(),
+        provider: provider.clone(),
+    });
+
+    let outcome = task.await;
+    state.requests.lock().unwrap().remove(&request_id);
+    let elapsed_ms = started.elapsed().as_millis() as u64;
+    match &outcome {
+        Ok(Ok(result)) => tracing::info!(
+            request_id = %request_id,
+            provider = %provider,
+            elapsed_ms,
+            response_bytes = result.text.len(),
+            "search finished"
+        ),
+        Ok(Err(e)) => tracing::warn!(request_id = %request_id, provider = %provider, elapsed_ms, error = ?e, "search failed"),
+        Err(_) => tracing::info!(request_id = %request_id, provider = %provider, elapsed_ms, "search cancelled"),
+    }
+
+    match outcome {
+        Ok(Ok(result)) => Ok(SearchResponse {
             text: result.text,
             provider: result.provider,
             model: result.model,
             conversation_id: result.conversation_id,
-            request_id: result.request_id,
+            request_id: Some(request_id),
+            usage: result.usage,
+            thinking: result.thinking,
+            candidates: result.candidates,
         }),
-        Err(e) => Err(format!("Error: {}", e)),
+        Ok(Err(e)) => Err(e),
+        Err(e) if e.is_cancelled() => Err(SearchError::Cancelled),
+        Err(e) => Err(SearchError::Failed(format!("Error: {}", e))),
+    }
+}
+
+/// A Telegram bridge search as configured in `run_search`
+struct BridgeQuery {
+    client: TelegramClient,
+    url: String,
+    conversation_id: Option<String>,
+    use_jobs: bool,
+    use_encryption: bool,
+    chat_mode: bool,
+}
+
+/// Capabilities handshake, then the query as a job, a stream or a single request
+async fn bridge_search(
+    app: &AppHandle,
+    request_id: &str,
+    bridge_query: BridgeQuery,
+    query: &str,
+    candidates: usize,
+) -> Result<SearchResult, SearchError> {
+    let BridgeQuery { mut client, url, conversation_id, use_jobs, use_encryption, chat_mode } = bridge_query;
+    if let Some(capabilities) = bridge_capabilities(&app.state::<AppState>(), &url, &client).await {
+        client = client.with_capabilities(capabilities).map_err(SearchError::from)?;
+    }
+    let negotiated = client.negotiated_capabilities();
+    let jobs = negotiated.is_some_and(|c| c.jobs);
+    let streaming = negotiated.is_some_and(|c| c.streaming);
+
+    let outcome = if use_jobs && jobs && candidates <= 1 {
+        bridge_jobs::job_search(app, &client, query, &url, use_encryption, chat_mode, conversation_id).await
+    } else if streaming && candidates <= 1 {
+        // Bridges that negotiated streaming send the answer piece by piece
+        stream_search(app, request_id, &client, query).await
+    } else if candidates > 1 {
+        client.search_candidates(query, candidates).await
+    } else {
+        client.search(query).await
+    };
+    outcome.map_err(SearchError::from)
+}
+
+/// Stream a bridge answer, emitting `search-delta` for every piece of text
+async fn stream_search(
+    app: &AppHandle,
+    request_id: &str,
+    client: &TelegramClient,
+    query: &str,
+) -> Result<SearchResult, Box<dyn std::error::Error + Send + Sync>> {
+    let mut stream = client.search_stream(query).await?;
+    while let Some(event) = stream.next_event().await? {
+        match event {
+            ResponseEvent::TextDelta(text) => {
+                let _ = app.emit("search-delta", SearchDelta { request_id: request_id.to_string(), text });
+            }
+            ResponseEvent::ReasoningDelta(_) => {}
+            ResponseEvent::Completed(result) => return Ok(*result),
+        }
     }
+    Err("Bridge stream ended without an answer".into())
+}
+
+/// Capabilities of the bridge at `url`, negotiated once per server.
+/// Unreachable bridges are not cached so the search itself reports the error.
+async fn bridge_capabilities(state: &AppState, url: &str, client: &TelegramClient) -> Option<Capabilities> {
+    if let Some(cached) = state.bridge_capabilities.lock().unwrap().get(url) {
+        return Some(cached.clone());
+    }
+    match client.capabilities().await {
+        Ok(capabilities) => {
+            state.bridge_capabilities.lock().unwrap().insert(url.to_string(), capabilities.clone());
+            Some(capabilities)
+        }
+        Err(e) => {
+            tracing::warn!(url, error = %e, "could not fetch bridge capabilities");
+            None
+        }
+    }
+}
+
+/// Run the capabilities handshake again (e.g. after the server was updated) and cache the result.
+/// An empty `providers` list means the bridge does not advertise them and any settings are passed through.
+#[tauri::command]
+async fn get_bridge_capabilities(
+    telegram_url: String,
+    api_key: String,
+    signing_secret: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<Capabilities, String> {
+    let mut client = TelegramClient::new(telegram_url.clone(), api_key, None, false, false, None);
+    let max_skew = state.config.lock().unwrap().bridge.max_clock_skew_secs;
+    if let Some(signer) = bridge_signer(signing_secret.as_deref(), max_skew) {
+        client = client.with_signer(signer);
+    }
+    let capabilities = client.capabilities().await.map_err(|e| e.to_string())?;
+    state.bridge_capabilities.lock().unwrap().insert(telegram_url, capabilities.clone());
+    Ok(capabilities)
+}
+
+/// Encryption policy and what a bridge call with these settings would be sent
+/// with, or why it would be refused. Nothing is sent. `policy` and
+/// `allow_plaintext_http` preview unsaved settings; by default the saved ones apply.
+#[tauri::command]
+fn get_bridge_security(
+    telegram_url: String,
+    encryption_key: Option<String>,
+    use_encryption: bool,
+    policy: Option<EncryptionPolicy>,
+    allow_plaintext_http: Option<bool>,
+    state: State<AppState>,
+) -> TransportSecurity {
+    let mut security = state.config.lock().unwrap().security.clone();
+    security.bridge_encryption = policy.unwrap_or(security.bridge_encryption);
+    security.allow_plaintext_http = allow_plaintext_http.unwrap_or(security.allow_plaintext_http);
+    let client = TelegramClient::new(telegram_url, String::new(), encryption_key, use_encryption, false, None);
+    bridge_policy(client, &security).transport_security()
+}
+
+/// Probe the bridge without sending a query: DNS, TLS, API key and, when given, the encryption key.
+/// Failures are part of the report (`failure` says which step), so the UI can name the cause.
+#[tauri::command]
+async fn check_bridge_health(
+    telegram_url: String,
+    api_key: String,
+    encryption_key: Option<String>,
+    signing_secret: Option<String>,
+    state: State<'_, AppState>,
+) -> HealthReport {
+    let mut client = TelegramClient::new(telegram_url, api_key, encryption_key, false, false, None);
+    let max_skew = state.config.lock().unwrap().bridge.max_clock_skew_secs;
+    if let Some(signer) = bridge_signer(signing_secret.as_deref(), max_skew) {
+        client = client.with_signer(signer);
+    }
+    client.check_health().await
 }
 
 #[tauri::command]
@@ -175,49 +719,47 @@ fn reset_window_size(state: State<AppState>) -> Result<(), String> {
     Ok(())
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct CancelRequestBody {
-    request_id: String,
+/// Cancel on the bridge: jobs by their job id, anything else by request id
+async fn cancel_on_bridge(
+    bridge: &TelegramClient,
+    jobs: &[bridge_jobs::PendingJob],
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    if jobs.is_empty() {
+        return bridge.cancel().await;
+    }
+    for job in jobs {
+        bridge.cancel_job(&job.job_id).await?;
+    }
+    Ok(())
 }
 
 #[tauri::command]
 async fn cancel_request(
     request_id: String,
-    telegram_url: String,
-    api_key: String,
+    app: AppHandle,
+    state: State<'_, AppState>,
 ) -> Result<String, String> {
-    // Build cancel endpoint URL
-    let cancel_url = if telegram_url.contains("/ai_query") {
-        telegram_url.replace("/ai_query", "/cancel_request")
-    } else {
-        format!("{}/cancel_request", telegram_url.trim_end_matches('/'))
+    let entry = state.requests.lock().unwrap().remove(&request_id);
+    let Some(entry) = entry else {
+        return Ok("No request in flight with this id (it may have completed)".to_string());
     };
-    
-    // Create HTTP client
-    let client = reqwest::Client::new();
-    
-    // Prepare request body
-    let body = CancelRequestBody { request_id };
-    
-    // Send POST request
-    match client.post(&cancel_url)
-        .header("X-API-KEY", api_key)
-        .header("X-APP-ID", "apiai-v2")
-        .json(&body)
-        .send()
-        .await
-    {
-        Ok(response) => {
-            if response.status().is_success() {
-                Ok("Request cancelled successfully".to_string())
-            } else {
-                Ok(format!("Cancel request returned status: {}", response.status()))
-            }
-        }
-        Err(e) => {
-            // Don't fail if cancel fails - request might have already completed
-            Ok(format!("Cancel request failed (request may have completed): {}", e))
-        }
+
+    // Stop the local future first so the caller gets Cancelled right away
+    entry.abort.abort();
+    let jobs = bridge_jobs::forget_job(&app, &request_id).await.unwrap_or_else(|e| {
+        tracing::warn!(request_id = %request_id, error = %e, "could not forget bridge job");
+        Vec::new()
+    });
+
+    match entry.bridge {
+        // Best effort: the local request is already aborted, so a failed server
+        // cancel is reported but not treated as an error. Jobs are cancelled by
+        // the job id the bridge answered with.
+        Some(bridge) => match cancel_on_bridge(&bridge, &jobs).await {
+            Ok(()) => Ok("Request cancelled successfully".to_string()),
+            Err(e) => Ok(format!("Request cancelled locally; server cancel failed: {}", e)),
+        },
+        None => Ok("Request cancelled successfully".to_string()),
     }
 }
 
@@ -234,6 +776,14 @@ struct ChatMessage {
     provider: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     model: Option<String>,
+    /// Model reasoning shown collapsed above the answer
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    thinking: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    reasoning_tokens: Option<u64>,
+    /// Drafts that were not chosen; `content` holds the chosen one
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    alternatives: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -267,19 +817,68 @@ struct SavedChatInfo {
 }
 
 #[tauri::command]
-fn save_chat_history(
+async fn save_chat_history(
     chat_data: ChatHistory,
     file_path: String,
+    app: AppHandle,
+    state: State<'_, AppState>,
 ) -> Result<(), String> {
     let content = serde_json::to_string_pretty(&chat_data)
         .map_err(|e| format!("Failed to serialize chat history: {}", e))?;
     
     fs::write(&file_path, content)
         .map_err(|e| format!("Failed to write chat history to {:?}: {}", file_path, e))?;
+
+    // Keep the semantic index current in the background; a failure there must not fail the save
+    let client = {
+        let config = state.config.lock().unwrap();
+        if config.embeddings.enabled {
+            Some(chat_index::embedding_client(&config.embeddings, &config.api_keys.openai))
+        } else {
+            None
+        }
+    };
+    match client {
+        Some(Ok(client)) => {
+            tokio::spawn(async move {
+                let state = app.state::<AppState>();
+                let _guard = state.index_lock.lock().await;
+                let path = std::path::Path::new(&file_path);
+                if let Err(e) = chat_index::update_for_saved_chat(path, &chat_data, client.as_ref()).await {
+                    tracing::warn!(path = ?file_path, error = %e, "failed to update semantic index");
+                }
+            });
+        }
+        Some(Err(e)) => tracing::warn!(error = %e, "semantic index not updated"),
+        None => {}
+    }
     
     Ok(())
 }
 
+/// Make one of a saved message's alternatives its content; the previous
+/// content becomes an alternative in its place
+#[tauri::command]
+async fn select_alternative(
+    file_path: String,
+    message_index: usize,
+    alternative_index: usize,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<ChatHistory, String> {
+    let mut chat = load_chat_history(file_path.clone())?;
+
+    let message = chat.messages.get_mut(message_index)
+        .ok_or_else(|| format!("Chat has no message {}", message_index))?;
+    let alternative = message.alternatives.get_mut(alternative_index)
+        .ok_or_else(|| format!("Message {} has no alternative {}", message_index, alternative_index))?;
+    std::mem::swap(&mut message.content, alternative);
+    chat.metadata.last_modified = chrono::Local::now().to_rfc3339();
+
+    save_chat_history(chat.clone(), file_path, app, state).await?;
+    Ok(chat)
+}
+
 #[tauri::command]
 fn load_chat_history(file_path: String) -> Result<ChatHistory, String> {
     let content = fs::read_to_string(&file_path)
@@ -369,6 +968,9 @@ fn import_text_chat(file_path: String) -> Result<ChatHistory, String> {
                         timestamp: chrono::Local::now().to_rfc3339(),
                         provider: None,
                         model: None,
+                        thinking: None,
+                        reasoning_tokens: None,
+                        alternatives: Vec::new(),
                     });
                 }
             }
@@ -385,6 +987,9 @@ fn import_text_chat(file_path: String) -> Result<ChatHistory, String> {
                         timestamp: chrono::Local::now().to_rfc3339(),
                         provider: None,
                         model: None,
+                        thinking: None,
+                        reasoning_tokens: None,
+                        alternatives: Vec::new(),
                     });
                 }
             }
@@ -410,6 +1015,9 @@ fn import_text_chat(file_path: String) -> Result<ChatHistory, String> {
                 timestamp: chrono::Local::now().to_rfc3339(),
                 provider: None,
                 model: None,
+                thinking: None,
+                reasoning_tokens: None,
+                alternatives: Vec::new(),
             });
         }
     }
@@ -465,7 +1073,6 @@ fn get_config_path() -> std::path::PathBuf {
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     let config_path = get_config_path();
-    println!("Loading config from: {:?}", config_path);
 
     let config = if let Ok(content) = fs::read_to_string(&config_path) {
         serde_json::from_str(&content).unwrap_or_default()
@@ -479,13 +1086,29 @@ pub fn run() {
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_fs::init())
-        .manage(AppState { config: Mutex::new(config) })
+        .manage(AppState {
+            config: Mutex::new(config),
+            requests: Mutex::new(HashMap::new()),
+            index_lock: tokio::sync::Mutex::new(()),
+            batch_lock: tokio::sync::Mutex::new(()),
+            bridge_capabilities: Mutex::new(HashMap::new()),
+            bridge_job_lock: tokio::sync::Mutex::new(()),
+        })
         .setup(move |app| {
+            match logging::init(app.handle(), &config_clone.logging) {
+                Ok(logging) => {
+                    app.manage(logging);
+                }
+                // No subscriber was installed, so stderr is all there is
+                Err(e) => eprintln!("Logging is disabled: {}", e),
+            }
+            tracing::info!(path = ?config_path, "config loaded");
+
             // Set window size on startup if saved in config
             if let Some(window) = app.get_webview_window("main") {
                 if let Some(width) = config_clone.ui.window_width {
                     if let Some(height) = config_clone.ui.window_height {
-                        println!("Setting window size on startup: {}x{}", width, height);
+                        tracing::debug!(width, height, "restoring window size");
                         let _ = window.set_size(tauri::LogicalSize::new(width, height));
                     }
                 }
@@ -501,11 +1124,63 @@ pub fn run() {
             save_window_size, 
             reset_window_size,
             cancel_request,
+            get_bridge_capabilities,
+            check_bridge_health,
+            get_bridge_security,
             save_chat_history,
+            select_alternative,
             load_chat_history,
             list_saved_chats,
-            import_text_chat
+            import_text_chat,
+            batches::submit_batch,
+            batches::list_batches,
+            batches::refresh_batch,
+            batches::cancel_batch,
+            batches::export_batch_results,
+            bridge_jobs::list_bridge_jobs,
+            bridge_jobs::resume_bridge_job,
+            bridge_jobs::discard_bridge_job,
+            conversations::list_bridge_conversations,
+            conversations::get_bridge_conversation,
+            conversations::rename_bridge_conversation,
+            conversations::reset_bridge_conversation,
+            conversations::delete_bridge_conversation,
+            chat_index::semantic_search_chats,
+            templates::list_templates,
+            templates::render_template,
+            templates::run_template
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The saved config with `section` replaced by `partial`
+    fn config_with(section: &str, partial: serde_json::Value) -> AppConfig {
+        let mut saved = AppConfig::default();
+        saved.security.pin_code = "9876".to_string();
+        let mut json = serde_json::to_value(&saved).unwrap();
+        json[section] = partial;
+        serde_json::from_value(json).unwrap()
+    }
+
+    #[test]
+    fn partial_bridge_section_keeps_the_config() {
+        let config = config_with("bridge", serde_json::json!({"use_jobs": true}));
+        assert_eq!(config.security.pin_code, "9876");
+        assert!(config.bridge.use_jobs);
+        assert_eq!((config.bridge.provider.as_str(), config.bridge.max_tokens), ("anthropic", 1024));
+    }
+
+    #[test]
+    fn partial_embeddings_section_keeps_the_config() {
+        let config = config_with("embeddings", serde_json::json!({"model": "nomic-embed-text"}));
+        assert_eq!(config.security.pin_code, "9876");
+        assert_eq!(config.embeddings.model.as_deref(), Some("nomic-embed-text"));
+        assert!(!config.embeddings.enabled);
+        assert_eq!(config.embeddings.provider, "openai");
+    }
+}
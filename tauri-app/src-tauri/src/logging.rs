@@ -0,0 +1,103 @@
+// ============================================================================
+// Logging
+// ============================================================================
+//
+// Logs go to stderr and to a daily rotating file in `logs/` under the app data
+// directory (`apiai.<date>.log`; the newest `logging.max_files` are kept).
+// `logging.level` is a tracing filter ("info", "apiai_shared=debug,info", ...)
+// and `RUST_LOG` overrides it. Prompts and answers are redacted unless
+// `logging.log_bodies` is set. Saving the config applies the level and body
+// setting at once; `max_files` takes effect on the next start.
+
+use apiai_shared::logging::set_log_bodies;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use tauri::{AppHandle, Manager};
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::{fmt, prelude::*, reload, EnvFilter, Registry};
+
+const LOG_DIR: &str = "logs";
+const DEFAULT_LEVEL: &str = "info";
+const DEFAULT_MAX_FILES: usize = 7;
+
+#[derive(Clone, Serialize, Deserialize)]
+pub(crate) struct LoggingConfig {
+    /// Tracing filter directives
+    #[serde(default = "default_level")]
+    pub level: String,
+    /// Log prompts and answers in full instead of their size
+    #[serde(default)]
+    pub log_bodies: bool,
+    /// Daily log files to keep
+    #[serde(default = "default_max_files")]
+    pub max_files: usize,
+}
+
+fn default_level() -> String {
+    DEFAULT_LEVEL.to_string()
+}
+
+fn default_max_files() -> usize {
+    DEFAULT_MAX_FILES
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        Self {
+            level: default_level(),
+            log_bodies: false,
+            max_files: DEFAULT_MAX_FILES,
+        }
+    }
+}
+
+/// Managed state: changes the filter at runtime and flushes the log file when dropped
+pub(crate) struct Logging {
+    filter: reload::Handle<EnvFilter, Registry>,
+    _guard: WorkerGuard,
+}
+
+fn filter(config: &LoggingConfig) -> EnvFilter {
+    EnvFilter::try_from_default_env()
+        .or_else(|_| EnvFilter::try_new(&config.level))
+        .unwrap_or_else(|_| EnvFilter::new(DEFAULT_LEVEL))
+}
+
+/// Install the global subscriber; fails when the log directory cannot be used
+pub(crate) fn init(app: &AppHandle, config: &LoggingConfig) -> Result<Logging, String> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to resolve app data dir: {}", e))?
+        .join(LOG_DIR);
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    let appender = RollingFileAppender::builder()
+        .rotation(Rotation::DAILY)
+        .filename_prefix("apiai")
+        .filename_suffix("log")
+        .max_log_files(config.max_files.max(1))
+        .build(&dir)
+        .map_err(|e| format!("Failed to open a log file in {}: {}", dir.display(), e))?;
+    let (writer, guard) = tracing_appender::non_blocking(appender);
+
+    let (filter, handle) = reload::Layer::new(filter(config));
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(fmt::layer().with_writer(std::io::stderr))
+        .with(fmt::layer().with_ansi(false).with_writer(writer))
+        .try_init()
+        .map_err(|e| format!("Failed to install the logger: {}", e))?;
+    set_log_bodies(config.log_bodies);
+    Ok(Logging { filter: handle, _guard: guard })
+}
+
+/// Apply changed settings to the running logger
+pub(crate) fn apply(app: &AppHandle, config: &LoggingConfig) {
+    set_log_bodies(config.log_bodies);
+    if let Some(logging) = app.try_state::<Logging>() {
+        if let Err(e) = logging.filter.reload(filter(config)) {
+            tracing::warn!(error = %e, "log level not changed");
+        }
+    }
+}
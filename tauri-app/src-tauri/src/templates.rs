@@ -0,0 +1,354 @@
+// ============================================================================
+// Prompt Templates
+// ============================================================================
+//
+// A template is a JSON file in the templates directory:
+//
+// {
+//   "name": "translate",
+//   "description": "Translate text to English",
+//   "system_prompt": "You are a professional translator.",
+//   "cache_system_prompt": false,
+//   "prompt": "Translate to {{language}}:\n\n{{text}}",
+//   "provider": "anthropic",
+//   "model": "claude-3-5-sonnet-20241022"
+// }
+//
+// Everything except "prompt" is optional; "name" defaults to the file name.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use tauri::{AppHandle, State};
+
+use crate::{run_search, AppState, SearchError, SearchParams, SearchResponse};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PromptTemplate {
+    #[serde(default)]
+    name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    system_prompt: Option<String>,
+    /// Mark the system prompt as an Anthropic prompt-cache breakpoint
+    #[serde(default)]
+    cache_system_prompt: bool,
+    prompt: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    provider: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    model: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TemplateInfo {
+    #[serde(flatten)]
+    template: PromptTemplate,
+    path: String,
+    variables: Vec<String>, // In order of first appearance
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RenderedTemplate {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    system_prompt: Option<String>,
+    cache_system_prompt: bool,
+    prompt: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    provider: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    model: Option<String>,
+}
+
+impl PromptTemplate {
+    /// Variables used by the system prompt and the prompt, without duplicates
+    fn variables(&self) -> Vec<String> {
+        let mut variables = Vec::new();
+        for text in self.system_prompt.iter().chain(std::iter::once(&self.prompt)) {
+            for var in placeholders(text) {
+                if !variables.contains(&var) {
+                    variables.push(var);
+                }
+            }
+        }
+        variables
+    }
+
+    /// Substitute all variables. Fails listing every variable without a value.
+    fn render(&self, values: &HashMap<String, String>) -> Result<RenderedTemplate, String> {
+        let mut missing = BTreeSet::new();
+        let system_prompt = self.system_prompt
+            .as_ref()
+            .map(|text| substitute(text, values, &mut missing));
+        let prompt = substitute(&self.prompt, values, &mut missing);
+
+        if !missing.is_empty() {
+            let names: Vec<String> = missing.into_iter().collect();
+            return Err(format!(
+                "Template '{}' is missing values for: {}",
+                self.name,
+                names.join(", ")
+            ));
+        }
+
+        Ok(RenderedTemplate {
+            system_prompt,
+            cache_system_prompt: self.cache_system_prompt,
+            prompt,
+            provider: self.provider.clone(),
+            model: self.model.clone(),
+        })
+    }
+}
+
+enum Part<'a> {
+    Literal(&'a str),
+    Var(&'a str),
+}
+
+/// Split `text` into literal parts and `{{ name }}` placeholders.
+/// Unterminated or empty braces are kept as literal text.
+fn parts(text: &str) -> Vec<Part<'_>> {
+    let mut parts = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        let Some(len) = rest[start + 2..].find("}}") else {
+            break;
+        };
+        let end = start + 4 + len;
+        let name = rest[start + 2..end - 2].trim();
+        if name.is_empty() {
+            parts.push(Part::Literal(&rest[..end]));
+        } else {
+            parts.push(Part::Literal(&rest[..start]));
+            parts.push(Part::Var(name));
+        }
+        rest = &rest[end..];
+    }
+    parts.push(Part::Literal(rest));
+    parts
+}
+
+fn placeholders(text: &str) -> Vec<String> {
+    parts(text)
+        .into_iter()
+        .filter_map(|part| match part {
+            Part::Var(name) => Some(name.to_string()),
+            Part::Literal(_) => None,
+        })
+        .collect()
+}
+
+fn substitute(text: &str, values: &HashMap<String, String>, missing: &mut BTreeSet<String>) -> String {
+    let mut out = String::with_capacity(text.len());
+    for part in parts(text) {
+        match part {
+            Part::Literal(literal) => out.push_str(literal),
+            Part::Var(name) => match values.get(name) {
+                Some(value) => out.push_str(value),
+                None => {
+                    missing.insert(name.to_string());
+                }
+            },
+        }
+    }
+    out
+}
+
+fn load_templates(directory: &str) -> Result<Vec<TemplateInfo>, String> {
+    let dir_path = std::path::Path::new(directory);
+
+    if !dir_path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let entries = fs::read_dir(dir_path)
+        .map_err(|e| format!("Failed to read directory {:?}: {}", directory, e))?;
+
+    let mut templates = Vec::new();
+    for entry in entries.flatten() {
+        let path = entry.path();
+
+        // Only process .json files
+        if path.extension().and_then(|s| s.to_str()) != Some("json") {
+            continue;
+        }
+
+        let parsed = fs::read_to_string(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|content| serde_json::from_str::<PromptTemplate>(&content).map_err(|e| e.to_string()));
+
+        match parsed {
+            Ok(mut template) => {
+                if template.name.is_empty() {
+                    template.name = path.file_stem()
+                        .map(|s| s.to_string_lossy().to_string())
+                        .unwrap_or_default();
+                }
+                let variables = template.variables();
+                templates.push(TemplateInfo {
+                    template,
+                    path: path.to_string_lossy().to_string(),
+                    variables,
+                });
+            }
+            Err(e) => tracing::warn!(path = ?path, error = %e, "skipping invalid template"),
+        }
+    }
+
+    templates.sort_by(|a, b| a.template.name.cmp(&b.template.name));
+    Ok(templates)
+}
+
+fn find_template(directory: &str, name: &str) -> Result<PromptTemplate, String> {
+    load_templates(directory)?
+        .into_iter()
+        .find(|t| t.template.name == name)
+        .map(|t| t.template)
+        .ok_or_else(|| format!("Template '{}' not found in {:?}", name, directory))
+}
+
+#[tauri::command]
+pub fn list_templates(directory: String) -> Result<Vec<TemplateInfo>, String> {
+    load_templates(&directory)
+}
+
+#[tauri::command]
+pub fn render_template(
+    directory: String,
+    name: String,
+    variables: HashMap<String, String>,
+) -> Result<RenderedTemplate, String> {
+    find_template(&directory, &name)?.render(&variables)
+}
+
+/// Provider and model for a run: `provider` overrides the template's
+/// `default`, and then the template's model is dropped. `None` when there is
+/// no provider at all.
+fn resolve_provider(
+    provider: Option<String>,
+    default: Option<String>,
+    model: Option<String>,
+) -> Option<(String, Option<String>)> {
+    match (provider, default) {
+        (Some(provider), Some(default)) if provider != default => Some((provider, None)),
+        (provider, default) => Some((provider.or(default)?, model)),
+    }
+}
+
+/// Render a template and send it through the same path as `perform_search`.
+/// `provider` overrides the template's default provider; the template's model
+/// only goes with its own provider.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn run_template(
+    directory: String,
+    name: String,
+    variables: HashMap<String, String>,
+    provider: Option<String>,
+    api_key: String,
+    telegram_url: Option<String>,
+    encryption_key: Option<String>,
+    use_encryption: bool,
+    request_id: Option<String>,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<SearchResponse, SearchError> {
+    let rendered = find_template(&directory, &name)
+        .and_then(|template| template.render(&variables))
+        .map_err(SearchError::Failed)?;
+
+    let (provider, model) = resolve_provider(provider, rendered.provider, rendered.model)
+        .ok_or_else(|| SearchError::Failed(format!("Template '{}' has no default provider", name)))?;
+
+    let (reasoning, bridge) = {
+        let config = state.config.lock().unwrap();
+        (config.reasoning.clone(), config.bridge.clone())
+    };
+
+    run_search(&app, &state, SearchParams {
+        query: rendered.prompt,
+        provider,
+        api_key,
+        model,
+        system_prompt: rendered.system_prompt,
+        cache_system_prompt: rendered.cache_system_prompt,
+        reasoning,
+        candidates: 1,
+        bridge,
+        telegram_url,
+        encryption_key,
+        use_encryption,
+        chat_mode: false,
+        conversation_id: None,
+        request_id,
+    }).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn values(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+
+    #[test]
+    fn parts_keep_unterminated_and_empty_braces() {
+        let parts: Vec<String> = parts("a {{ x }} b {{}} c {{y")
+            .into_iter()
+            .map(|part| match part {
+                Part::Literal(text) => format!("L({})", text),
+                Part::Var(name) => format!("V({})", name),
+            })
+            .collect();
+        assert_eq!(parts, ["L(a )", "V(x)", "L( b {{}})", "L( c {{y)"]);
+    }
+
+    #[test]
+    fn placeholders_in_order_with_repeats() {
+        assert_eq!(placeholders("{{a}} {{ b }} {{a}}"), ["a", "b", "a"]);
+        assert!(placeholders("no variables").is_empty());
+    }
+
+    #[test]
+    fn substitute_repeated_placeholder() {
+        let mut missing = BTreeSet::new();
+        let out = substitute("{{x}}-{{ x }}", &values(&[("x", "1")]), &mut missing);
+        assert_eq!(out, "1-1");
+        assert!(missing.is_empty());
+    }
+
+    #[test]
+    fn substitute_collects_missing_variables() {
+        let mut missing = BTreeSet::new();
+        let out = substitute("{{b}} {{a}} {{b}}", &HashMap::new(), &mut missing);
+        assert_eq!(out, "  ");
+        assert_eq!(missing.into_iter().collect::<Vec<_>>(), ["a", "b"]);
+    }
+
+    #[test]
+    fn substitute_ignores_unused_variables() {
+        let mut missing = BTreeSet::new();
+        let out = substitute("Hi {{name}}", &values(&[("name", "Ann"), ("extra", "unused")]), &mut missing);
+        assert_eq!(out, "Hi Ann");
+        assert!(missing.is_empty());
+    }
+
+    #[test]
+    fn overriding_the_provider_drops_the_template_model() {
+        let resolved = resolve_provider(Some("openai".into()), Some("claude".into()), Some("claude-opus".into()));
+        assert_eq!(resolved, Some(("openai".to_string(), None)));
+    }
+
+    #[test]
+    fn the_template_provider_keeps_its_model() {
+        let model = Some("claude-opus".to_string());
+        let resolved = resolve_provider(None, Some("claude".into()), model.clone());
+        assert_eq!(resolved, Some(("claude".to_string(), model.clone())));
+        let resolved = resolve_provider(Some("claude".into()), Some("claude".into()), model.clone());
+        assert_eq!(resolved, Some(("claude".to_string(), model)));
+        assert_eq!(resolve_provider(None, None, None), None);
+    }
+}
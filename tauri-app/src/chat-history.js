@@ -66,6 +66,21 @@ function getCurrentChatData() {
             message.model = chatHistoryMetadata.model;
         }
 
+        // Model reasoning, restored collapsed when the chat is loaded
+        if (msg.dataset.thinking) {
+            message.thinking = msg.dataset.thinking;
+        }
+        if (msg.dataset.reasoningTokens) {
+            message.reasoning_tokens = Number(msg.dataset.reasoningTokens);
+        }
+
+        // Drafts that were not picked stay attached to the message
+        if (msg.dataset.candidates) {
+            const selected = Number(msg.dataset.selected || 0);
+            message.alternatives = JSON.parse(msg.dataset.candidates)
+                .filter((_, index) => index !== selected);
+        }
+
         return message;
     });
 
@@ -193,7 +208,12 @@ function restoreChatFromData(chatData) {
             window.appendMessage(msg.content, msg.role, {
                 provider: msg.provider,
                 model: msg.model,
-                timestamp: msg.timestamp
+                timestamp: msg.timestamp,
+                thinking: msg.thinking,
+                reasoningTokens: msg.reasoning_tokens,
+                candidates: msg.alternatives && msg.alternatives.length
+                    ? [msg.content, ...msg.alternatives]
+                    : undefined
             });
         }
     });
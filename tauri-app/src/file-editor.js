@@ -396,10 +396,10 @@ Please provide the modified file content. Return ONLY the file content, without
         console.error('File editor error:', error);
 
         // Check if aborted
-        if (window.abortRequested) {
+        if (error?.kind === 'cancelled' || window.abortRequested) {
             appendMessage('⚠️ File processing cancelled by user', 'system');
         } else {
-            appendMessage(`❌ Error processing file: ${error}`, 'error');
+            appendMessage(`❌ Error processing file: ${error?.message ?? error}`, 'error');
         }
     } finally {
         window.isProcessing = false;
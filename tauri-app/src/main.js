@@ -1,5 +1,6 @@
 const { invoke } = window.__TAURI__.core;
 const { getCurrentWindow } = window.__TAURI__.window;
+const { listen } = window.__TAURI__.event;
 
 let conversationId = null;
 const CORRECT_PIN = "1234"; // From config_qt.json
@@ -44,8 +45,40 @@ const protectedFields = document.querySelectorAll('.protected-field');
 let isProcessing = false;
 let abortRequested = false; // Flag to abort current request
 window.abortRequested = false; // Make it globally accessible
-let currentRequestId = null; // Track current request ID for server cancellation
-let currentTelegramUrl = null; // Track current telegram URL
+let currentRequestId = null; // Client-generated ID of the in-flight request, used for cancellation
+
+// The backend announces every request ID before sending it (covers requests that
+// were started without an ID, e.g. from the file editor)
+listen('request-started', (event) => {
+  if (!currentRequestId) {
+    currentRequestId = event.payload.request_id;
+  }
+  console.log('Request started:', event.payload);
+});
+// Streamed bridge answers arrive piece by piece; show them in a temporary
+// message that the final answer replaces
+let streamingMessage = null;
+let streamingText = '';
+
+listen('search-delta', (event) => {
+  if (event.payload.request_id !== currentRequestId) return;
+  if (!streamingMessage) {
+    streamingMessage = document.createElement('div');
+    streamingMessage.className = 'message ai streaming';
+    streamingText = '';
+    chatHistory.appendChild(streamingMessage);
+  }
+  streamingText += event.payload.text;
+  streamingMessage.innerHTML = formatMessageText(streamingText);
+  scrollToBottom();
+});
+
+function clearStreamingMessage() {
+  streamingMessage?.remove();
+  streamingMessage = null;
+  streamingText = '';
+}
+
 // Use window.settingsUnlocked so it's accessible from other scripts
 window.settingsUnlocked = false;
 
@@ -149,6 +182,37 @@ async function init() {
 
   // Setup all event listeners
   setupEventListeners();
+
+  resumeBridgeJobs();
+}
+
+// Bridge jobs submitted before the app was closed: wait for their answers
+async function resumeBridgeJobs() {
+  let jobs = [];
+  try {
+    jobs = await invoke('list_bridge_jobs');
+  } catch (e) {
+    console.error('Failed to load pending bridge jobs:', e);
+    return;
+  }
+
+  const keys = window.appConfig?.api_keys || {};
+  for (const job of jobs) {
+    const label = job.preview ? `"${job.preview}"` : job.job_id;
+    appendMessage(`⏳ Waiting for a bridge job from an earlier session: ${label}`, 'system');
+    invoke('resume_bridge_job', {
+      jobId: job.job_id,
+      apiKey: keys.telegram_key || '',
+      encryptionKey: job.use_encryption ? (keys.telegram_enc_key || null) : null
+    }).then(response => {
+      appendMessage(response.text, 'ai', { provider: response.provider, model: response.model });
+      if (response.conversation_id && job.chat_mode) {
+        conversationId = response.conversation_id;
+      }
+    }).catch(error => {
+      appendMessage(`Bridge job failed: ${error?.message ?? error}`, 'error');
+    }).finally(scrollToBottom);
+  }
 }
 
 function setupEventListeners() {
@@ -222,7 +286,7 @@ function updateApiKeyField() {
       apiKeyInput.value = window.appConfig.api_keys.telegram_key || '';
     } else if (provider === 'anthropic') {
       apiKeyInput.value = window.appConfig.api_keys.anthropic || '';
-    } else if (provider === 'openai') {
+    } else if (provider === 'openai' || provider === 'openai_responses') {
       apiKeyInput.value = window.appConfig.api_keys.openai || '';
     }
   }
@@ -645,7 +709,7 @@ function setupEchoHandler() {
     } catch (error) {
       const endTime = performance.now();
       const roundTripTime = Math.round(endTime - startTime);
-      appendMessage(`❌ Echo Error: ${error}\n⏱️ Time: ${roundTripTime}ms`, 'error');
+      appendMessage(`❌ Echo Error: ${error?.message ?? error}\n⏱️ Time: ${roundTripTime}ms`, 'error');
     } finally {
       isProcessing = false;
       echoBtn.disabled = false;
@@ -689,6 +753,9 @@ function updateProviderInfo() {
       case 'openai':
         infoText = 'OpenAI GPT (gpt-4o)';
         break;
+      case 'openai_responses':
+        infoText = 'OpenAI Responses API (gpt-4o)';
+        break;
       default:
         infoText = '';
     }
@@ -729,7 +796,7 @@ apiKeyInput.addEventListener('input', () => {
     window.appConfig.api_keys.telegram_key = apiKeyInput.value;
   } else if (provider === 'anthropic') {
     window.appConfig.api_keys.anthropic = apiKeyInput.value;
-  } else if (provider === 'openai') {
+  } else if (provider === 'openai' || provider === 'openai_responses') {
     window.appConfig.api_keys.openai = apiKeyInput.value;
   }
 });
@@ -811,11 +878,11 @@ async function sendMessage() {
     if (useEnc) {
       encryptionKey = encryptionKeyInput.value;
     }
-
-    // Store telegram URL for potential cancellation
-    currentTelegramUrl = telegramUrl;
   }
 
+  // Generate the request ID up front so the request can be cancelled while pending
+  currentRequestId = crypto.randomUUID();
+
   // Log network request to history sidebar
   if (window.logNetworkRequest) {
     window.logNetworkRequest(telegramUrl || 'Direct API', useEnc, query);
@@ -830,21 +897,19 @@ async function sendMessage() {
       encryptionKey,
       useEncryption: useEnc,
       chatMode,
-      conversationId
+      conversationId,
+      candidates: Math.max(1, Number(document.getElementById('candidates')?.value) || 1),
+      requestId: currentRequestId
     });
 
-    // Store request_id for potential cancellation
-    if (response.request_id) {
-      currentRequestId = response.request_id;
-      console.log('Stored request_id:', currentRequestId);
-    }
-
     // Check if aborted
     if (abortRequested || window.abortRequested) {
       appendMessage('⚠️ Request cancelled by user', 'system');
       return;
     }
 
+    clearStreamingMessage();
+
     // Remove provider information from response text if present
     let cleanedText = response.text
       .split('\n')
@@ -859,9 +924,22 @@ async function sendMessage() {
 
     appendMessage(cleanedText, 'ai', {
       provider: response.provider,
-      model: response.model
+      model: response.model,
+      thinking: response.thinking,
+      reasoningTokens: response.usage?.reasoning_tokens,
+      candidates: response.candidates
     });
 
+    // Show prompt cache savings (Anthropic prompt caching)
+    const usage = response.usage;
+    if (usage && (usage.cache_read_input_tokens || usage.cache_creation_input_tokens)) {
+      appendMessage(
+        `💾 Prompt cache: ${usage.cache_read_input_tokens || 0} tokens read, ` +
+        `${usage.cache_creation_input_tokens || 0} tokens written, ${usage.input_tokens} uncached input tokens`,
+        'system'
+      );
+    }
+
     // Update provider info for Telegram
     if (provider === 'telegram' && response.provider && response.model) {
       currentProviderInfo.provider = response.provider;
@@ -880,12 +958,17 @@ async function sendMessage() {
 
   } catch (error) {
     // Check if error was due to abort
-    if (abortRequested || window.abortRequested) {
-      appendMessage('⚠️ Request cancelled by user', 'system');
-      return; // Don't show error if user aborted
+    if (error?.kind === 'cancelled' || abortRequested || window.abortRequested) {
+      return; // abortRequest() already reported the cancellation
     }
-    appendMessage(`Error: ${error}`, 'error');
+    if (error?.kind === 'insecure') {
+      appendMessage(`🔒 Not sent: ${error.message}. Check the encryption policy in Provider Settings.`, 'error');
+      return;
+    }
+    appendMessage(`Error: ${error?.message ?? error}`, 'error');
   } finally {
+    clearStreamingMessage();
+    currentRequestId = null;
     isProcessing = false;
     abortRequested = false;
     window.abortRequested = false;
@@ -907,30 +990,24 @@ async function abortRequest() {
   console.log('isProcessing:', isProcessing);
   console.log('window.isProcessing:', window.isProcessing);
   console.log('currentRequestId:', currentRequestId);
-  console.log('currentTelegramUrl:', currentTelegramUrl);
 
   // Force abort even if isProcessing is false (in case of race condition)
   console.log('Setting abort flags...');
   abortRequested = true;
   window.abortRequested = true;
 
-  // Send cancel request to server (best effort - don't fail if this fails)
-  if (currentRequestId && currentTelegramUrl) {
-    console.log('Sending cancel request to server...');
+  // Abort the in-flight request (the backend also notifies the bridge server if needed)
+  if (currentRequestId) {
+    console.log('Sending cancel request...');
     try {
-      const apiKey = document.getElementById('api-key')?.value || '';
-      await invoke('cancel_request', {
-        requestId: currentRequestId,
-        telegramUrl: currentTelegramUrl,
-        apiKey: apiKey
-      });
-      console.log('Cancel request sent to server');
+      const result = await invoke('cancel_request', { requestId: currentRequestId });
+      console.log('Cancel result:', result);
     } catch (error) {
-      console.warn('Failed to send cancel request to server:', error);
+      console.warn('Failed to cancel request:', error);
       // Don't fail - just log it
     }
   } else {
-    console.log('No request_id or telegram_url - skipping server cancel');
+    console.log('No request_id - skipping cancel');
   }
 
   // Update UI immediately
@@ -959,18 +1036,41 @@ async function abortRequest() {
 
   // Clear tracking variables
   currentRequestId = null;
-  currentTelegramUrl = null;
 
   console.log('=== ABORT COMPLETED ===');
 }
 
+// Simple markdown-like parsing for bold text
+function formatMessageText(text) {
+  return text.replace(/\*\*(.*?)\*\*/g, '<strong>$1</strong>')
+    .replace(/\n/g, '<br>');
+}
+
 function appendMessage(text, type, metadata = {}) {
   const div = document.createElement('div');
   div.className = `message ${type}`;
 
-  // Simple markdown-like parsing for bold text
-  const formattedText = text.replace(/\*\*(.*?)\*\*/g, '<strong>$1</strong>')
-    .replace(/\n/g, '<br>');
+  const formattedText = formatMessageText(text);
+
+  // Model reasoning (extended thinking), collapsed above the answer.
+  // Kept in data attributes so chat-history.js can save it with the message.
+  if (metadata.thinking) {
+    div.dataset.thinking = metadata.thinking;
+    if (metadata.reasoningTokens) {
+      div.dataset.reasoningTokens = metadata.reasoningTokens;
+    }
+    const details = document.createElement('details');
+    details.className = 'thinking';
+    const summary = document.createElement('summary');
+    summary.textContent = metadata.reasoningTokens
+      ? `🧠 Thinking (${metadata.reasoningTokens} tokens)`
+      : '🧠 Thinking';
+    const thinkingText = document.createElement('div');
+    thinkingText.textContent = metadata.thinking;
+    details.appendChild(summary);
+    details.appendChild(thinkingText);
+    div.appendChild(details);
+  }
 
   const contentDiv = document.createElement('div');
   contentDiv.className = 'content';
@@ -978,6 +1078,29 @@ function appendMessage(text, type, metadata = {}) {
 
   div.appendChild(contentDiv);
 
+  // Several drafts: let the user pick one. The shown draft is saved as the
+  // message content, the others as its alternatives (see chat-history.js).
+  if (metadata.candidates && metadata.candidates.length > 1) {
+    div.dataset.candidates = JSON.stringify(metadata.candidates);
+    div.dataset.selected = '0';
+
+    const picker = document.createElement('div');
+    picker.className = 'candidate-picker';
+    metadata.candidates.forEach((candidate, index) => {
+      const button = document.createElement('button');
+      button.className = 'secondary-btn small-btn';
+      button.textContent = `Draft ${index + 1}`;
+      button.classList.toggle('active', index === 0);
+      button.addEventListener('click', () => {
+        contentDiv.innerHTML = formatMessageText(candidate);
+        div.dataset.selected = String(index);
+        picker.querySelectorAll('button').forEach((b, i) => b.classList.toggle('active', i === index));
+      });
+      picker.appendChild(button);
+    });
+    div.appendChild(picker);
+  }
+
   // Don't add provider/model info to messages - it's shown in header instead
 
   chatHistory.appendChild(div);
@@ -56,9 +56,191 @@ document.addEventListener('DOMContentLoaded', () => {
         document.getElementById('modal-use-encryption').checked = useEnc;
         document.getElementById('modal-show-keys').checked = false;
 
+        // Upstream provider/model the bridge forwards to
+        const bridge = window.appConfig?.bridge || {};
+        setUpstreamProvider(bridge.provider || 'anthropic');
+        document.getElementById('modal-upstream-model').value = bridge.model || '';
+        document.getElementById('modal-max-tokens').value = bridge.max_tokens || 1024;
+        document.getElementById('modal-temperature').value = bridge.temperature ?? '';
+        document.getElementById('modal-signing-secret').value = bridge.signing_secret || '';
+        document.getElementById('modal-use-jobs').checked = !!bridge.use_jobs;
+        document.getElementById('modal-upstream-status').textContent = '';
+        document.getElementById('modal-health-status').textContent = '';
+
+        // Encryption policy (global, from the security section)
+        const security = window.appConfig?.security || {};
+        document.getElementById('modal-encryption-policy').value = security.bridge_encryption || 'prefer';
+        document.getElementById('modal-allow-plaintext-http').checked = !!security.allow_plaintext_http;
+
         providerSettingsModal.classList.remove('hidden');
+        loadUpstreamProviders();
+        showBridgeSecurity();
     });
 
+    // Providers from the bridge capabilities (null when it does not advertise them)
+    let upstreamProviders = null;
+
+    const setUpstreamProvider = (name) => {
+        const select = document.getElementById('modal-upstream-provider');
+        if (![...select.options].some(option => option.value === name)) {
+            select.add(new Option(name, name));
+        }
+        select.value = name;
+    };
+
+    const showUpstreamModels = () => {
+        const name = document.getElementById('modal-upstream-provider').value;
+        const upstream = upstreamProviders?.find(p => p.name === name);
+        const datalist = document.getElementById('modal-upstream-models');
+        datalist.innerHTML = '';
+        (upstream?.models || []).forEach(model => datalist.appendChild(new Option(model, model)));
+        document.getElementById('modal-upstream-model').placeholder =
+            upstream?.default_model ? `Bridge default (${upstream.default_model})` : 'Bridge default';
+        const maxTokens = document.getElementById('modal-max-tokens');
+        maxTokens.max = upstream?.max_tokens || '';
+    };
+
+    const describeCapabilities = (capabilities) => {
+        if (!capabilities.advertised) {
+            return upstreamProviders
+                ? `Older bridge (no handshake). Offers: ${upstreamProviders.map(p => p.name).join(', ')}`
+                : 'Older bridge (no handshake); settings are sent as entered';
+        }
+        const parts = [`Protocol v${capabilities.protocol_version}`];
+        if (capabilities.server_version) parts.push(`server ${capabilities.server_version}`);
+        parts.push(`encryption ${capabilities.encryption}`);
+        parts.push(capabilities.streaming ? 'streaming' : 'no streaming');
+        if (capabilities.jobs) parts.push('jobs');
+        if (upstreamProviders) parts.push(`offers: ${upstreamProviders.map(p => p.name).join(', ')}`);
+        return parts.join(' · ');
+    };
+
+    // Bridge URL as entered in the modal (host + port, or a full URL)
+    const modalBridgeUrl = () => {
+        const host = document.getElementById('modal-telegram-url').value;
+        const port = document.getElementById('modal-telegram-port').value;
+        if (!host) return null;
+        return host.includes('://') ? host : `http://${host}:${port || '8000'}/ai_query`;
+    };
+
+    // What a query with the settings in the modal would be sent with, or why it would be refused
+    const showBridgeSecurity = async () => {
+        const status = document.getElementById('modal-security-status');
+        const telegramUrl = modalBridgeUrl();
+        if (!telegramUrl) {
+            status.textContent = '';
+            return;
+        }
+        try {
+            const { invoke } = window.__TAURI__.core;
+            const security = await invoke('get_bridge_security', {
+                telegramUrl,
+                encryptionKey: document.getElementById('modal-encryption-key').value || null,
+                useEncryption: document.getElementById('modal-use-encryption').checked,
+                policy: document.getElementById('modal-encryption-policy').value,
+                allowPlaintextHttp: document.getElementById('modal-allow-plaintext-http').checked
+            });
+            if (security.refused) {
+                status.textContent = `⛔ Queries will not be sent: ${security.refused}`;
+            } else if (security.encrypted) {
+                status.textContent = `🔒 Encrypted (${security.transport})${security.https ? ' over https' : ''}`;
+            } else {
+                status.textContent = security.https ? '🔓 Not encrypted, but over https' : '⚠️ Not encrypted (local server)';
+            }
+        } catch (e) {
+            status.textContent = `⚠️ ${e}`;
+        }
+    };
+
+    ['modal-telegram-url', 'modal-telegram-port', 'modal-encryption-key'].forEach(id =>
+        document.getElementById(id)?.addEventListener('change', showBridgeSecurity));
+    ['modal-use-encryption', 'modal-encryption-policy', 'modal-allow-plaintext-http'].forEach(id =>
+        document.getElementById(id)?.addEventListener('change', showBridgeSecurity));
+
+    const loadUpstreamProviders = async () => {
+        const status = document.getElementById('modal-upstream-status');
+        const telegramUrl = modalBridgeUrl();
+        if (!telegramUrl) return;
+
+        try {
+            const { invoke } = window.__TAURI__.core;
+            const capabilities = await invoke('get_bridge_capabilities', {
+                telegramUrl,
+                apiKey: document.getElementById('modal-api-key').value,
+                signingSecret: document.getElementById('modal-signing-secret').value || null
+            });
+            upstreamProviders = capabilities.providers.length ? capabilities.providers : null;
+            if (upstreamProviders) {
+                const current = document.getElementById('modal-upstream-provider').value;
+                const select = document.getElementById('modal-upstream-provider');
+                select.innerHTML = '';
+                upstreamProviders.forEach(p => select.add(new Option(p.name, p.name)));
+                setUpstreamProvider(current);
+            }
+            status.textContent = describeCapabilities(capabilities);
+            if (capabilities.encryption === 'required') {
+                document.getElementById('modal-use-encryption').checked = true;
+            }
+        } catch (e) {
+            upstreamProviders = null;
+            status.textContent = `Could not reach the bridge: ${e}`;
+        }
+        showUpstreamModels();
+    };
+
+    const describeHealth = (report) => {
+        const timings = [];
+        if (report.latency_ms != null) timings.push(`${report.latency_ms} ms`);
+        if (report.encrypted_latency_ms != null) timings.push(`${report.encrypted_latency_ms} ms encrypted`);
+        const parts = [];
+        if (report.ok) {
+            parts.push('✅ Bridge reachable');
+            if (report.server_version) parts.push(`server ${report.server_version}`);
+            if (timings.length) parts.push(timings.join(', '));
+            if (report.encryption_key_accepted) parts.push('encryption key OK');
+        } else {
+            const causes = {
+                invalid_url: 'Invalid URL',
+                dns: 'Host not found (DNS)',
+                connection: 'Connection refused or unreachable',
+                tls: 'TLS handshake failed',
+                timeout: 'Timed out',
+                auth: 'API key rejected',
+                wrong_key: 'Encryption key rejected',
+                server: 'Bridge error'
+            };
+            parts.push(`❌ ${causes[report.failure] || report.failure}`);
+        }
+        if (report.message) parts.push(report.message);
+        return parts.join(' · ');
+    };
+
+    const checkBridgeHealth = async () => {
+        const status = document.getElementById('modal-health-status');
+        const telegramUrl = modalBridgeUrl();
+        if (!telegramUrl) {
+            status.textContent = 'Enter the bridge address first';
+            return;
+        }
+        status.textContent = 'Checking…';
+        try {
+            const { invoke } = window.__TAURI__.core;
+            const report = await invoke('check_bridge_health', {
+                telegramUrl,
+                apiKey: document.getElementById('modal-api-key').value,
+                encryptionKey: document.getElementById('modal-encryption-key').value || null,
+                signingSecret: document.getElementById('modal-signing-secret').value || null
+            });
+            status.textContent = describeHealth(report);
+        } catch (e) {
+            status.textContent = `Health check failed: ${e}`;
+        }
+    };
+
+    document.getElementById('modal-upstream-provider')?.addEventListener('change', showUpstreamModels);
+    document.getElementById('modal-check-health')?.addEventListener('click', checkBridgeHealth);
+    document.getElementById('modal-load-providers')?.addEventListener('click', loadUpstreamProviders);
+
     // Close modal
     const closeModal = () => {
         providerSettingsModal.classList.add('hidden');
@@ -97,6 +279,41 @@ document.addEventListener('DOMContentLoaded', () => {
             newConfig.api_keys.telegram_enc_key = modalEncryptionKey;
             newConfig.api_keys.telegram_use_encryption = modalUseEncryption;
 
+            const upstreamProvider = document.getElementById('modal-upstream-provider').value;
+            const upstreamModel = document.getElementById('modal-upstream-model').value.trim();
+            const maxTokens = Number(document.getElementById('modal-max-tokens').value) || 1024;
+            const temperature = document.getElementById('modal-temperature').value;
+
+            // Check against what the bridge advertises before saving
+            const upstream = upstreamProviders?.find(p => p.name === upstreamProvider);
+            if (upstreamProviders && !upstream) {
+                alert(`Bridge does not offer provider '${upstreamProvider}'`);
+                return;
+            }
+            if (upstream && upstreamModel && upstream.models.length && !upstream.models.includes(upstreamModel)) {
+                alert(`Bridge does not offer model '${upstreamModel}' for ${upstreamProvider}`);
+                return;
+            }
+            if (upstream?.max_tokens && maxTokens > upstream.max_tokens) {
+                alert(`Max tokens is limited to ${upstream.max_tokens} for ${upstreamProvider}`);
+                return;
+            }
+
+            newConfig.bridge = {
+                ...newConfig.bridge,
+                provider: upstreamProvider,
+                model: upstreamModel || null,
+                max_tokens: maxTokens,
+                temperature: temperature === '' ? null : Number(temperature),
+                signing_secret: document.getElementById('modal-signing-secret').value || null,
+                use_jobs: document.getElementById('modal-use-jobs').checked
+            };
+            newConfig.security = {
+                ...newConfig.security,
+                bridge_encryption: document.getElementById('modal-encryption-policy').value,
+                allow_plaintext_http: document.getElementById('modal-allow-plaintext-http').checked
+            };
+
             try {
                 const { invoke } = window.__TAURI__.core;
                 await invoke('save_config', { newConfig });
@@ -125,13 +342,16 @@ document.addEventListener('DOMContentLoaded', () => {
     modalShowKeys?.addEventListener('change', (e) => {
         const apiKeyInput = document.getElementById('modal-api-key');
         const encKeyInput = document.getElementById('modal-encryption-key');
+        const signingInput = document.getElementById('modal-signing-secret');
 
         if (e.target.checked) {
             apiKeyInput.type = 'text';
             encKeyInput.type = 'text';
+            signingInput.type = 'text';
         } else {
             apiKeyInput.type = 'password';
             encKeyInput.type = 'password';
+            signingInput.type = 'password';
         }
     });
 });